@@ -0,0 +1,860 @@
+use crate::{
+    error::{check_ty, describe, unwrap_or_panic},
+    types::{self, Type},
+    value::{
+        BasicBlock, CastOp, ConstOpcode, FloatBinOp, FloatPredicate, Function, Instruction,
+        IntBinOp, IntFlags, IntPredicate, Opcode, UnpackedValue, Value,
+    },
+    Context, Error,
+};
+
+/// Creates instructions and inserts them into a block
+///
+/// Operations which the [`Context`] can fold are folded when their operands
+/// are constants instead of creating an instruction, so those methods return
+/// a [`Value`] rather than an [`Instruction`]
+#[derive(Clone, Copy)]
+pub struct IrBuilder<'ctx> {
+    ctx: Context<'ctx>,
+    block: Option<BasicBlock<'ctx>>,
+    // new instructions go right before this one, or at the end of `block` if it is `None`
+    before: Option<Instruction<'ctx>>,
+}
+
+macro_rules! int_binops {
+    ($($name:ident $try_name:ident: $op:ident)*) => {$(
+        #[doc = concat!("Build `", stringify!($name), "`, see [`IrBuilder::build_int_binop`]")]
+        #[track_caller]
+        pub fn $name(
+            &self,
+            lhs: impl Into<Value<'ctx>>,
+            rhs: impl Into<Value<'ctx>>,
+        ) -> Value<'ctx> {
+            self.build_int_binop(IntBinOp::$op, IntFlags::NONE, lhs, rhs)
+        }
+
+        pub fn $try_name(
+            &self,
+            lhs: impl Into<Value<'ctx>>,
+            rhs: impl Into<Value<'ctx>>,
+        ) -> Result<Value<'ctx>, Error> {
+            self.try_build_int_binop(IntBinOp::$op, IntFlags::NONE, lhs, rhs)
+        }
+    )*};
+}
+
+macro_rules! float_binops {
+    ($($name:ident $try_name:ident: $op:ident)*) => {$(
+        /// # Panics
+        ///
+        /// If `lhs` and `rhs` aren't floats (or vectors of floats) of the same type
+        #[track_caller]
+        pub fn $name(
+            &self,
+            lhs: impl Into<Value<'ctx>>,
+            rhs: impl Into<Value<'ctx>>,
+        ) -> Instruction<'ctx> {
+            unwrap_or_panic(self.$try_name(lhs, rhs))
+        }
+
+        pub fn $try_name(
+            &self,
+            lhs: impl Into<Value<'ctx>>,
+            rhs: impl Into<Value<'ctx>>,
+        ) -> Result<Instruction<'ctx>, Error> {
+            let opcode = Opcode::FloatBinary(FloatBinOp::$op);
+            self.try_build_instruction(opcode, &[lhs.into(), rhs.into()], &[])
+        }
+    )*};
+}
+
+macro_rules! casts {
+    ($($name:ident $try_name:ident: $op:ident)*) => {$(
+        #[doc = concat!("Build `", stringify!($name), "`, see [`IrBuilder::build_cast`]")]
+        #[track_caller]
+        pub fn $name(
+            &self,
+            value: impl Into<Value<'ctx>>,
+            ty: impl Into<Type<'ctx>>,
+        ) -> Value<'ctx> {
+            self.build_cast(CastOp::$op, value, ty)
+        }
+
+        pub fn $try_name(
+            &self,
+            value: impl Into<Value<'ctx>>,
+            ty: impl Into<Type<'ctx>>,
+        ) -> Result<Value<'ctx>, Error> {
+            self.try_build_cast(CastOp::$op, value, ty)
+        }
+    )*};
+}
+
+impl<'ctx> IrBuilder<'ctx> {
+    /// A builder which isn't positioned in any block
+    pub fn new(ctx: Context<'ctx>) -> Self {
+        Self {
+            ctx,
+            block: None,
+            before: None,
+        }
+    }
+
+    /// A builder which inserts at the end of `block`
+    pub fn at_end(ctx: Context<'ctx>, block: BasicBlock<'ctx>) -> Self {
+        let mut builder = Self::new(ctx);
+        builder.position_at_end(block);
+        builder
+    }
+
+    #[inline]
+    pub fn ctx(&self) -> Context<'ctx> {
+        self.ctx
+    }
+
+    /// The block new instructions are inserted into
+    #[inline]
+    pub fn block(&self) -> Option<BasicBlock<'ctx>> {
+        self.block
+    }
+
+    /// The instruction new instructions are inserted before, `None` if they
+    /// are inserted at the end of the block
+    #[inline]
+    pub fn insert_before(&self) -> Option<Instruction<'ctx>> {
+        self.before
+    }
+
+    pub fn position_at_end(&mut self, block: BasicBlock<'ctx>) {
+        self.block = Some(block);
+        self.before = None;
+    }
+
+    /// Insert new instructions right before `inst`
+    ///
+    /// # Panics
+    ///
+    /// If `inst` isn't in a block
+    #[track_caller]
+    pub fn position_before(&mut self, inst: Instruction<'ctx>) {
+        unwrap_or_panic(self.try_position_before(inst))
+    }
+
+    pub fn try_position_before(&mut self, inst: Instruction<'ctx>) -> Result<(), Error> {
+        let block = inst.parent().ok_or_else(|| Error::NotInserted {
+            value: describe(inst),
+        })?;
+
+        self.block = Some(block);
+        self.before = Some(inst);
+        Ok(())
+    }
+
+    pub fn clear_position(&mut self) {
+        self.block = None;
+        self.before = None;
+    }
+
+    /// Insert an instruction which isn't in a block yet at the current position
+    ///
+    /// # Panics
+    ///
+    /// If the builder isn't positioned, or for the same reasons as [`BasicBlock::append`]
+    #[track_caller]
+    pub fn insert(&self, inst: Instruction<'ctx>) {
+        unwrap_or_panic(self.try_insert(inst))
+    }
+
+    pub fn try_insert(&self, inst: Instruction<'ctx>) -> Result<(), Error> {
+        match (self.block, self.before) {
+            (_, Some(before)) => inst.try_insert_before(before),
+            (Some(block), None) => block.try_append(inst),
+            (None, None) => Err(Error::NoInsertionPoint),
+        }
+    }
+
+    /// Create an instruction with [`Context::instruction`] and insert it at the current position
+    ///
+    /// # Panics
+    ///
+    /// If the builder isn't positioned, or the operands or blocks don't match the opcode
+    #[track_caller]
+    pub fn build_instruction(
+        &self,
+        opcode: Opcode<'ctx>,
+        operands: &[Value<'ctx>],
+        blocks: &[BasicBlock<'ctx>],
+    ) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_instruction(opcode, operands, blocks))
+    }
+
+    pub fn try_build_instruction(
+        &self,
+        opcode: Opcode<'ctx>,
+        operands: &[Value<'ctx>],
+        blocks: &[BasicBlock<'ctx>],
+    ) -> Result<Instruction<'ctx>, Error> {
+        if self.block.is_none() {
+            return Err(Error::NoInsertionPoint);
+        }
+
+        let inst = self.ctx.try_instruction(opcode, operands, blocks)?;
+        if let Err(err) = self.try_insert(inst) {
+            // don't leave the operands used by an instruction which isn't in any block
+            self.ctx.erase_instruction(inst);
+            return Err(err);
+        }
+        Ok(inst)
+    }
+
+    int_binops! {
+        build_add try_build_add: Add
+        build_sub try_build_sub: Sub
+        build_mul try_build_mul: Mul
+        build_udiv try_build_udiv: UDiv
+        build_sdiv try_build_sdiv: SDiv
+        build_urem try_build_urem: URem
+        build_srem try_build_srem: SRem
+        build_shl try_build_shl: Shl
+        build_lshr try_build_lshr: LShr
+        build_ashr try_build_ashr: AShr
+        build_and try_build_and: And
+        build_or try_build_or: Or
+        build_xor try_build_xor: Xor
+    }
+
+    /// `lhs op rhs`, which is folded if both are constants
+    ///
+    /// # Panics
+    ///
+    /// If `lhs` and `rhs` aren't integers (or vectors of integers) of the same type
+    #[track_caller]
+    pub fn build_int_binop(
+        &self,
+        op: IntBinOp,
+        flags: IntFlags,
+        lhs: impl Into<Value<'ctx>>,
+        rhs: impl Into<Value<'ctx>>,
+    ) -> Value<'ctx> {
+        unwrap_or_panic(self.try_build_int_binop(op, flags, lhs, rhs))
+    }
+
+    pub fn try_build_int_binop(
+        &self,
+        op: IntBinOp,
+        flags: IntFlags,
+        lhs: impl Into<Value<'ctx>>,
+        rhs: impl Into<Value<'ctx>>,
+    ) -> Result<Value<'ctx>, Error> {
+        let (lhs, rhs) = (lhs.into(), rhs.into());
+
+        if let (UnpackedValue::ConstInt(a), UnpackedValue::ConstInt(b)) =
+            (lhs.unpack(), rhs.unpack())
+        {
+            return self.ctx.try_const_int_binop(op, flags, a, b);
+        }
+
+        // addresses can still be added and subtracted as constant expressions
+        if flags == IntFlags::NONE && lhs.is_constant() && rhs.is_constant() {
+            let folded = match op {
+                IntBinOp::Add => self.ctx.try_const_add(lhs, rhs).ok(),
+                IntBinOp::Sub => self.ctx.try_const_sub(lhs, rhs).ok(),
+                _ => None,
+            };
+            if let Some(folded) = folded {
+                return Ok(folded);
+            }
+        }
+
+        let opcode = Opcode::IntBinary { op, flags };
+        Ok(self
+            .try_build_instruction(opcode, &[lhs, rhs], &[])?
+            .erase())
+    }
+
+    float_binops! {
+        build_fadd try_build_fadd: FAdd
+        build_fsub try_build_fsub: FSub
+        build_fmul try_build_fmul: FMul
+        build_fdiv try_build_fdiv: FDiv
+        build_frem try_build_frem: FRem
+    }
+
+    /// # Panics
+    ///
+    /// If `value` isn't a float or a vector of floats
+    #[track_caller]
+    pub fn build_fneg(&self, value: impl Into<Value<'ctx>>) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_fneg(value))
+    }
+
+    pub fn try_build_fneg(
+        &self,
+        value: impl Into<Value<'ctx>>,
+    ) -> Result<Instruction<'ctx>, Error> {
+        self.try_build_instruction(Opcode::FNeg, &[value.into()], &[])
+    }
+
+    /// Compare two integers or pointers, which is folded if both are integer constants
+    ///
+    /// # Panics
+    ///
+    /// If `lhs` and `rhs` don't have the same type, or it isn't an integer,
+    /// pointer or vector of those
+    #[track_caller]
+    pub fn build_icmp(
+        &self,
+        pred: IntPredicate,
+        lhs: impl Into<Value<'ctx>>,
+        rhs: impl Into<Value<'ctx>>,
+    ) -> Value<'ctx> {
+        unwrap_or_panic(self.try_build_icmp(pred, lhs, rhs))
+    }
+
+    pub fn try_build_icmp(
+        &self,
+        pred: IntPredicate,
+        lhs: impl Into<Value<'ctx>>,
+        rhs: impl Into<Value<'ctx>>,
+    ) -> Result<Value<'ctx>, Error> {
+        let (lhs, rhs) = (lhs.into(), rhs.into());
+
+        if let (UnpackedValue::ConstInt(a), UnpackedValue::ConstInt(b)) =
+            (lhs.unpack(), rhs.unpack())
+        {
+            return Ok(self.ctx.try_const_icmp(pred, a, b)?.erase());
+        }
+
+        Ok(self
+            .try_build_instruction(Opcode::ICmp(pred), &[lhs, rhs], &[])?
+            .erase())
+    }
+
+    /// # Panics
+    ///
+    /// If `lhs` and `rhs` don't have the same type, or it isn't a float or vector of floats
+    #[track_caller]
+    pub fn build_fcmp(
+        &self,
+        pred: FloatPredicate,
+        lhs: impl Into<Value<'ctx>>,
+        rhs: impl Into<Value<'ctx>>,
+    ) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_fcmp(pred, lhs, rhs))
+    }
+
+    pub fn try_build_fcmp(
+        &self,
+        pred: FloatPredicate,
+        lhs: impl Into<Value<'ctx>>,
+        rhs: impl Into<Value<'ctx>>,
+    ) -> Result<Instruction<'ctx>, Error> {
+        self.try_build_instruction(Opcode::FCmp(pred), &[lhs.into(), rhs.into()], &[])
+    }
+
+    casts! {
+        build_trunc try_build_trunc: Trunc
+        build_zext try_build_zext: ZExt
+        build_sext try_build_sext: SExt
+        build_fptrunc try_build_fptrunc: FPTrunc
+        build_fpext try_build_fpext: FPExt
+        build_fptoui try_build_fptoui: FPToUI
+        build_fptosi try_build_fptosi: FPToSI
+        build_uitofp try_build_uitofp: UIToFP
+        build_sitofp try_build_sitofp: SIToFP
+        build_ptr_to_int try_build_ptr_to_int: PtrToInt
+        build_int_to_ptr try_build_int_to_ptr: IntToPtr
+        build_bitcast try_build_bitcast: BitCast
+        build_addrspace_cast try_build_addrspace_cast: AddrSpaceCast
+    }
+
+    /// Convert `value` to `ty`, which is folded for the casts constant
+    /// expressions support if `value` is a constant
+    ///
+    /// # Panics
+    ///
+    /// If the cast isn't valid between the type of `value` and `ty`
+    #[track_caller]
+    pub fn build_cast(
+        &self,
+        op: CastOp,
+        value: impl Into<Value<'ctx>>,
+        ty: impl Into<Type<'ctx>>,
+    ) -> Value<'ctx> {
+        unwrap_or_panic(self.try_build_cast(op, value, ty))
+    }
+
+    pub fn try_build_cast(
+        &self,
+        op: CastOp,
+        value: impl Into<Value<'ctx>>,
+        ty: impl Into<Type<'ctx>>,
+    ) -> Result<Value<'ctx>, Error> {
+        let (value, ty) = (value.into(), ty.into());
+
+        let const_opcode = match op {
+            CastOp::Trunc => Some(ConstOpcode::Trunc),
+            CastOp::ZExt => Some(ConstOpcode::ZExt),
+            CastOp::SExt => Some(ConstOpcode::SExt),
+            CastOp::PtrToInt => Some(ConstOpcode::PtrToInt),
+            CastOp::IntToPtr => Some(ConstOpcode::IntToPtr),
+            CastOp::BitCast => Some(ConstOpcode::BitCast),
+            _ => None,
+        };
+
+        if let Some(const_opcode) = const_opcode.filter(|_| value.is_constant()) {
+            let folded = self
+                .ctx
+                .value()
+                .try_const_cast(self.ctx.alloc(), const_opcode, value, ty);
+            if let Ok(folded) = folded {
+                return Ok(folded);
+            }
+        }
+
+        Ok(self
+            .try_build_instruction(Opcode::Cast { op, ty }, &[value], &[])?
+            .erase())
+    }
+
+    /// Allocate a `ty` on the stack of the function
+    ///
+    /// # Panics
+    ///
+    /// If `ty` isn't sized
+    #[track_caller]
+    pub fn build_alloca(&self, ty: impl Into<Type<'ctx>>) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_alloca(ty))
+    }
+
+    pub fn try_build_alloca(&self, ty: impl Into<Type<'ctx>>) -> Result<Instruction<'ctx>, Error> {
+        let one = self.ctx.try_const_u32(self.ctx.i32(), 1)?;
+        self.try_build_array_alloca(ty, one)
+    }
+
+    /// Allocate `len` values of type `ty` on the stack of the function
+    ///
+    /// # Panics
+    ///
+    /// If `ty` isn't sized, or `len` isn't an integer
+    #[track_caller]
+    pub fn build_array_alloca(
+        &self,
+        ty: impl Into<Type<'ctx>>,
+        len: impl Into<Value<'ctx>>,
+    ) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_array_alloca(ty, len))
+    }
+
+    pub fn try_build_array_alloca(
+        &self,
+        ty: impl Into<Type<'ctx>>,
+        len: impl Into<Value<'ctx>>,
+    ) -> Result<Instruction<'ctx>, Error> {
+        let opcode = Opcode::Alloca {
+            allocated_ty: ty.into(),
+            align: None,
+        };
+        self.try_build_instruction(opcode, &[len.into()], &[])
+    }
+
+    /// Load a `ty` from `ptr`
+    ///
+    /// # Panics
+    ///
+    /// If `ty` isn't sized, or `ptr` isn't a pointer
+    #[track_caller]
+    pub fn build_load(
+        &self,
+        ty: impl Into<Type<'ctx>>,
+        ptr: impl Into<Value<'ctx>>,
+    ) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_load(ty, ptr))
+    }
+
+    pub fn try_build_load(
+        &self,
+        ty: impl Into<Type<'ctx>>,
+        ptr: impl Into<Value<'ctx>>,
+    ) -> Result<Instruction<'ctx>, Error> {
+        let opcode = Opcode::Load {
+            ty: ty.into(),
+            align: None,
+        };
+        self.try_build_instruction(opcode, &[ptr.into()], &[])
+    }
+
+    /// Store `value` to `ptr`
+    ///
+    /// # Panics
+    ///
+    /// If `value` isn't sized, or `ptr` isn't a pointer
+    #[track_caller]
+    pub fn build_store(
+        &self,
+        value: impl Into<Value<'ctx>>,
+        ptr: impl Into<Value<'ctx>>,
+    ) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_store(value, ptr))
+    }
+
+    pub fn try_build_store(
+        &self,
+        value: impl Into<Value<'ctx>>,
+        ptr: impl Into<Value<'ctx>>,
+    ) -> Result<Instruction<'ctx>, Error> {
+        let opcode = Opcode::Store { align: None };
+        self.try_build_instruction(opcode, &[value.into(), ptr.into()], &[])
+    }
+
+    /// The address of the element of `source_ty` selected by `indices`, starting at `base`,
+    /// which is folded if all operands are constants, see [`Context::const_gep`]
+    ///
+    /// # Panics
+    ///
+    /// If `base` isn't a pointer, or the indices don't select an element of `source_ty`
+    #[track_caller]
+    pub fn build_gep(
+        &self,
+        source_ty: impl Into<Type<'ctx>>,
+        base: impl Into<Value<'ctx>>,
+        indices: &[Value<'ctx>],
+    ) -> Value<'ctx> {
+        unwrap_or_panic(self.try_build_gep(source_ty, base, indices))
+    }
+
+    pub fn try_build_gep(
+        &self,
+        source_ty: impl Into<Type<'ctx>>,
+        base: impl Into<Value<'ctx>>,
+        indices: &[Value<'ctx>],
+    ) -> Result<Value<'ctx>, Error> {
+        self.gep(source_ty.into(), base.into(), indices, false)
+    }
+
+    /// Like [`IrBuilder::build_gep`], but the address must be inside the allocation of `base`
+    #[track_caller]
+    pub fn build_inbounds_gep(
+        &self,
+        source_ty: impl Into<Type<'ctx>>,
+        base: impl Into<Value<'ctx>>,
+        indices: &[Value<'ctx>],
+    ) -> Value<'ctx> {
+        unwrap_or_panic(self.try_build_inbounds_gep(source_ty, base, indices))
+    }
+
+    pub fn try_build_inbounds_gep(
+        &self,
+        source_ty: impl Into<Type<'ctx>>,
+        base: impl Into<Value<'ctx>>,
+        indices: &[Value<'ctx>],
+    ) -> Result<Value<'ctx>, Error> {
+        self.gep(source_ty.into(), base.into(), indices, true)
+    }
+
+    fn gep(
+        &self,
+        source_ty: Type<'ctx>,
+        base: Value<'ctx>,
+        indices: &[Value<'ctx>],
+        inbounds: bool,
+    ) -> Result<Value<'ctx>, Error> {
+        if base.is_constant() && indices.iter().all(|index| index.is_constant()) {
+            return self.ctx.value().try_const_gep(
+                self.ctx.alloc(),
+                source_ty,
+                base,
+                indices,
+                inbounds,
+            );
+        }
+
+        let operands = core::iter::once(base)
+            .chain(indices.iter().copied())
+            .collect::<Vec<_>>();
+
+        let opcode = Opcode::GetElementPtr {
+            source_ty,
+            inbounds,
+        };
+        Ok(self.try_build_instruction(opcode, &operands, &[])?.erase())
+    }
+
+    /// Call `function` with `arguments`
+    ///
+    /// # Panics
+    ///
+    /// If the arguments don't match the parameters of `function`
+    #[track_caller]
+    pub fn build_call(
+        &self,
+        function: Function<'ctx>,
+        arguments: &[Value<'ctx>],
+    ) -> Instruction<'ctx> {
+        self.build_indirect_call(function.function_ty(), function, arguments)
+    }
+
+    pub fn try_build_call(
+        &self,
+        function: Function<'ctx>,
+        arguments: &[Value<'ctx>],
+    ) -> Result<Instruction<'ctx>, Error> {
+        self.try_build_indirect_call(function.function_ty(), function, arguments)
+    }
+
+    /// Call the function `callee` points to, which must have the type `function_ty`
+    ///
+    /// # Panics
+    ///
+    /// If `callee` isn't a pointer, or the arguments don't match the parameters of `function_ty`
+    #[track_caller]
+    pub fn build_indirect_call(
+        &self,
+        function_ty: types::FunctionTy<'ctx>,
+        callee: impl Into<Value<'ctx>>,
+        arguments: &[Value<'ctx>],
+    ) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_indirect_call(function_ty, callee, arguments))
+    }
+
+    pub fn try_build_indirect_call(
+        &self,
+        function_ty: types::FunctionTy<'ctx>,
+        callee: impl Into<Value<'ctx>>,
+        arguments: &[Value<'ctx>],
+    ) -> Result<Instruction<'ctx>, Error> {
+        let operands = core::iter::once(callee.into())
+            .chain(arguments.iter().copied())
+            .collect::<Vec<_>>();
+
+        self.try_build_instruction(Opcode::Call { function_ty }, &operands, &[])
+    }
+
+    /// `if_true` if `condition` is true, otherwise `if_false`, which is folded
+    /// if `condition` is a constant
+    ///
+    /// # Panics
+    ///
+    /// If `if_true` and `if_false` have different types, or `condition` isn't an
+    /// `i1` (or a vector of `i1` with as many lanes)
+    #[track_caller]
+    pub fn build_select(
+        &self,
+        condition: impl Into<Value<'ctx>>,
+        if_true: impl Into<Value<'ctx>>,
+        if_false: impl Into<Value<'ctx>>,
+    ) -> Value<'ctx> {
+        unwrap_or_panic(self.try_build_select(condition, if_true, if_false))
+    }
+
+    pub fn try_build_select(
+        &self,
+        condition: impl Into<Value<'ctx>>,
+        if_true: impl Into<Value<'ctx>>,
+        if_false: impl Into<Value<'ctx>>,
+    ) -> Result<Value<'ctx>, Error> {
+        let (condition, if_true, if_false) = (condition.into(), if_true.into(), if_false.into());
+
+        if let UnpackedValue::ConstInt(condition) = condition.unpack() {
+            check_ty(self.ctx.i1().erase(), condition.ty())?;
+            check_ty(if_true.ty(), if_false.ty())?;
+            return Ok(if condition.is_zero() {
+                if_false
+            } else {
+                if_true
+            });
+        }
+
+        let operands = [condition, if_true, if_false];
+        Ok(self
+            .try_build_instruction(Opcode::Select, &operands, &[])?
+            .erase())
+    }
+
+    /// A phi without any incoming values, which can be added with
+    /// [`Context::add_phi_incoming`]
+    ///
+    /// # Panics
+    ///
+    /// If `ty` isn't a first class type
+    #[track_caller]
+    pub fn build_phi(&self, ty: impl Into<Type<'ctx>>) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_phi(ty))
+    }
+
+    pub fn try_build_phi(&self, ty: impl Into<Type<'ctx>>) -> Result<Instruction<'ctx>, Error> {
+        self.try_build_instruction(Opcode::Phi { ty: ty.into() }, &[], &[])
+    }
+
+    #[track_caller]
+    pub fn build_br(&self, target: BasicBlock<'ctx>) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_br(target))
+    }
+
+    pub fn try_build_br(&self, target: BasicBlock<'ctx>) -> Result<Instruction<'ctx>, Error> {
+        self.try_build_instruction(Opcode::Br, &[], &[target])
+    }
+
+    /// # Panics
+    ///
+    /// If `condition` isn't an `i1`
+    #[track_caller]
+    pub fn build_cond_br(
+        &self,
+        condition: impl Into<Value<'ctx>>,
+        if_true: BasicBlock<'ctx>,
+        if_false: BasicBlock<'ctx>,
+    ) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_cond_br(condition, if_true, if_false))
+    }
+
+    pub fn try_build_cond_br(
+        &self,
+        condition: impl Into<Value<'ctx>>,
+        if_true: BasicBlock<'ctx>,
+        if_false: BasicBlock<'ctx>,
+    ) -> Result<Instruction<'ctx>, Error> {
+        self.try_build_instruction(Opcode::CondBr, &[condition.into()], &[if_true, if_false])
+    }
+
+    /// A switch without any cases, which can be added with [`Context::add_switch_case`]
+    ///
+    /// # Panics
+    ///
+    /// If `value` isn't an integer
+    #[track_caller]
+    pub fn build_switch(
+        &self,
+        value: impl Into<Value<'ctx>>,
+        default: BasicBlock<'ctx>,
+    ) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_switch(value, default))
+    }
+
+    pub fn try_build_switch(
+        &self,
+        value: impl Into<Value<'ctx>>,
+        default: BasicBlock<'ctx>,
+    ) -> Result<Instruction<'ctx>, Error> {
+        self.try_build_instruction(Opcode::Switch, &[value.into()], &[default])
+    }
+
+    /// # Panics
+    ///
+    /// If `value` doesn't have the output type of the function
+    #[track_caller]
+    pub fn build_ret(&self, value: impl Into<Value<'ctx>>) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_ret(value))
+    }
+
+    pub fn try_build_ret(&self, value: impl Into<Value<'ctx>>) -> Result<Instruction<'ctx>, Error> {
+        self.try_build_instruction(Opcode::Ret, &[value.into()], &[])
+    }
+
+    /// # Panics
+    ///
+    /// If the function returns a value
+    #[track_caller]
+    pub fn build_ret_void(&self) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_ret_void())
+    }
+
+    pub fn try_build_ret_void(&self) -> Result<Instruction<'ctx>, Error> {
+        self.try_build_instruction(Opcode::Ret, &[], &[])
+    }
+
+    #[track_caller]
+    pub fn build_unreachable(&self) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_build_unreachable())
+    }
+
+    pub fn try_build_unreachable(&self) -> Result<Instruction<'ctx>, Error> {
+        self.try_build_instruction(Opcode::Unreachable, &[], &[])
+    }
+}
+
+#[test]
+fn test_builder() {
+    let target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+
+    Context::with(target, |ctx| {
+        let mut module = crate::Module::new(ctx, "test");
+        let i32 = ctx.i32().erase();
+        let abs = module.add_function("abs", ctx.function_ty(i32, &[i32]));
+        let x = abs.arguments()[0];
+
+        let entry = ctx.append_basic_block(abs, "entry");
+        let negate = ctx.append_basic_block(abs, "negate");
+        let exit = ctx.append_basic_block(abs, "exit");
+
+        let mut builder = IrBuilder::new(ctx);
+        assert!(matches!(
+            builder.try_build_ret(x),
+            Err(Error::NoInsertionPoint)
+        ));
+
+        // constants are folded without inserting anything
+        builder.position_at_end(entry);
+        let two = ctx.const_u32(ctx.i32(), 2).unwrap();
+        let three = ctx.const_u32(ctx.i32(), 3).unwrap();
+        let five = ctx.const_u32(ctx.i32(), 5).unwrap();
+        assert_eq!(builder.build_add(two, three), five.erase());
+        assert_eq!(
+            builder.build_icmp(IntPredicate::Ult, two, three),
+            ctx.const_u32(ctx.i1(), 1).unwrap().erase()
+        );
+        assert_eq!(
+            builder.build_select(ctx.const_u32(ctx.i1(), 0).unwrap(), two, three),
+            three.erase()
+        );
+        assert_eq!(builder.build_zext(two, ctx.i64()).ty(), ctx.i64().erase());
+        assert_eq!(entry.first_instruction(), None);
+
+        let zero = ctx.const_u32(ctx.i32(), 0).unwrap();
+        let negative = builder.build_icmp(IntPredicate::Slt, x, zero);
+        assert_eq!(negative.ty(), ctx.i1().erase());
+        builder.build_cond_br(negative, negate, exit);
+
+        builder.position_at_end(negate);
+        let negated = builder.build_sub(zero, x);
+        builder.build_br(exit);
+
+        builder.position_at_end(exit);
+        let phi = builder.build_phi(i32);
+        ctx.add_phi_incoming(phi, x, entry);
+        ctx.add_phi_incoming(phi, negated, negate);
+        let ret = builder.build_ret(phi);
+        assert!(builder.try_build_ret_void().is_err());
+
+        // insert in the middle of a block
+        builder.position_before(ret);
+        let slot = builder.build_alloca(i32);
+        builder.build_store(phi, slot);
+        let loaded = builder.build_load(i32, slot);
+        assert_eq!(loaded.ty(), i32);
+        let call = builder.build_call(abs, &[loaded.erase()]);
+        assert_eq!(call.ty(), i32);
+        assert!(builder.try_build_call(abs, &[]).is_err());
+        assert_eq!(exit.instructions().count(), 6);
+        assert_eq!(exit.last_instruction(), Some(ret));
+
+        let array_ty = ctx.array_ty(4, i32);
+        let one = ctx.const_u32(ctx.i32(), 1).unwrap().erase();
+        let element = builder.build_gep(array_ty, slot, &[zero.erase(), one]);
+        assert_eq!(element.ty(), ctx.ptr_ty().erase());
+        assert!(!element.is_constant());
+        assert!(builder
+            .try_build_add(x, ctx.const_f32(ctx.f32(), 1.0))
+            .is_err());
+
+        // the operands must come from the function being built
+        let other = module.add_function("other", ctx.function_ty(i32, &[i32]));
+        let y = other.arguments()[0];
+        assert!(matches!(
+            builder.try_build_add(x, y),
+            Err(Error::CrossFunction { .. })
+        ));
+        assert!(!ctx.has_uses(y));
+        assert_eq!(exit.instructions().count(), 7);
+    });
+}
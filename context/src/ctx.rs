@@ -5,13 +5,15 @@ use init::Ctor;
 mod alloc_ctx;
 pub use alloc_ctx::AllocContext;
 
+mod instruction;
+
 mod types_ctx;
 pub use types_ctx::TypeContext;
 
 mod value_ctx;
 pub use value_ctx::ValueContext;
 
-use crate::{types, value};
+use crate::{layout, types, value, Error, Target};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 struct Invariant<'a>(PhantomData<*mut &'a ()>);
@@ -47,6 +49,11 @@ impl<'ctx> Context<'ctx> {
         })
     }
 
+    /// Run `f` with a context for the built-in preset of `triple`, see [`Target::from_triple`]
+    pub fn with_triple<R>(triple: &str, f: impl FnOnce(Context<'_>) -> R) -> Option<R> {
+        Some(Self::with(Target::from_triple(triple)?, f))
+    }
+
     #[inline]
     #[must_use]
     pub fn alloc(&self) -> AllocContext<'ctx> {
@@ -108,16 +115,54 @@ impl<'ctx> Context<'ctx> {
         self.ty().int(self.alloc(), bits)
     }
 
+    /// # Panics
+    ///
+    /// If `bits` is zero
     #[inline]
     pub fn int_ty_lit(self, bits: u16) -> types::IntegerTy<'ctx> {
         self.int_ty(NonZeroU16::new(bits).unwrap())
     }
 
+    #[inline]
+    pub fn try_int_ty(self, bits: u16) -> Result<types::IntegerTy<'ctx>, Error> {
+        self.ty().try_int(self.alloc(), bits)
+    }
+
+    #[inline]
+    pub fn float_ty(self, kind: types::FloatKind) -> types::FloatTy<'ctx> {
+        self.ty().float(self.alloc(), kind)
+    }
+
+    #[inline]
+    pub fn f128(self) -> types::FloatTy<'ctx> {
+        self.ty().f128(self.alloc())
+    }
+
+    #[inline]
+    pub fn bf16(self) -> types::FloatTy<'ctx> {
+        self.ty().bf16(self.alloc())
+    }
+
+    #[inline]
+    pub fn x86_fp80(self) -> types::FloatTy<'ctx> {
+        self.ty().x86_fp80(self.alloc())
+    }
+
+    #[inline]
+    pub fn ppc_fp128(self) -> types::FloatTy<'ctx> {
+        self.ty().ppc_fp128(self.alloc())
+    }
+
     #[inline]
     pub fn ptr_ty_at(self, address_space: types::AddressSpace) -> types::PointerTy<'ctx> {
         self.ty().ptr_at(self.alloc(), address_space)
     }
 
+    #[inline]
+    pub fn try_ptr_ty_at(self, address_space: u32) -> Result<types::PointerTy<'ctx>, Error> {
+        self.ty().try_ptr_at(self.alloc(), address_space)
+    }
+
     #[inline]
     pub fn function_ty(
         self,
@@ -134,47 +179,535 @@ impl<'ctx> Context<'ctx> {
     }
 
     #[inline]
-    pub fn struct_ty<I: IntoIterator>(
+    #[track_caller]
+    pub fn vector_ty(
+        self,
+        len: u32,
+        item_ty: impl Into<types::Type<'ctx>>,
+    ) -> types::VectorTy<'ctx> {
+        self.ty().vector(self.alloc(), len, item_ty.into())
+    }
+
+    #[inline]
+    pub fn try_vector_ty(
+        self,
+        len: u32,
+        item_ty: impl Into<types::Type<'ctx>>,
+    ) -> Result<types::VectorTy<'ctx>, Error> {
+        self.ty().try_vector(self.alloc(), len, item_ty.into())
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn scalable_vector_ty(
+        self,
+        min_len: u32,
+        item_ty: impl Into<types::Type<'ctx>>,
+    ) -> types::VectorTy<'ctx> {
+        self.ty()
+            .scalable_vector(self.alloc(), min_len, item_ty.into())
+    }
+
+    #[inline]
+    pub fn try_scalable_vector_ty(
+        self,
+        min_len: u32,
+        item_ty: impl Into<types::Type<'ctx>>,
+    ) -> Result<types::VectorTy<'ctx>, Error> {
+        self.ty()
+            .try_scalable_vector(self.alloc(), min_len, item_ty.into())
+    }
+
+    #[inline]
+    pub fn struct_ty(
         self,
         name: impl crate::name::Name,
         flags: types::StructFlags,
         field_tys: &[types::Type<'ctx>],
-    ) -> types::StructTy<'ctx>
-    where
-        I::Item: Into<types::Type<'ctx>>,
-        I::IntoIter: ExactSizeIterator,
-    {
+    ) -> types::StructTy<'ctx> {
         self.ty()
             .struct_ty(self.alloc(), name.to_name(), flags, field_tys)
     }
+
+    #[inline]
+    pub fn identified_struct_ty(self, name: impl crate::name::Name) -> types::StructTy<'ctx> {
+        self.ty().identified_struct(self.alloc(), name.to_name())
+    }
+
+    #[inline]
+    #[track_caller]
+    pub fn set_struct_body(
+        self,
+        ty: types::StructTy<'ctx>,
+        field_tys: &[types::Type<'ctx>],
+        packed: bool,
+    ) {
+        ty.set_body(self.alloc(), field_tys, packed)
+    }
+
+    #[inline]
+    pub fn try_set_struct_body(
+        self,
+        ty: types::StructTy<'ctx>,
+        field_tys: &[types::Type<'ctx>],
+        packed: bool,
+    ) -> Result<(), Error> {
+        ty.try_set_body(self.alloc(), field_tys, packed)
+    }
+}
+
+macro_rules! const_ints {
+    ($($name:ident $try_name:ident: $ty:ty => $intern:ident)*) => {$(
+        /// Returns `None` if `value` is out of range for `ty`, see [`Context::const_int`]
+        pub fn $name(self, ty: types::IntegerTy<'ctx>, value: $ty) -> Option<value::ConstInt<'ctx>> {
+            self.$try_name(ty, value).ok()
+        }
+
+        pub fn $try_name(
+            self,
+            ty: types::IntegerTy<'ctx>,
+            value: $ty,
+        ) -> Result<value::ConstInt<'ctx>, Error> {
+            self.try_const_int(ty, self.value().$intern(value))
+        }
+    )*};
 }
 
 impl<'ctx> Context<'ctx> {
+    /// An integer given either as an unsigned or a two's complement signed value,
+    /// so `-1` and `255` are the same `i8`
+    ///
+    /// Returns `None` if `value` isn't in `-2^(bits-1)..2^bits`
     pub fn const_int(
         self,
         ty: types::IntegerTy<'ctx>,
         value: &'ctx rug::Integer,
-        signed: bool,
     ) -> Option<value::ConstInt<'ctx>> {
-        self.value().const_int(self.alloc(), ty, value, signed)
+        self.try_const_int(ty, value).ok()
+    }
+
+    pub fn try_const_int(
+        self,
+        ty: types::IntegerTy<'ctx>,
+        value: &'ctx rug::Integer,
+    ) -> Result<value::ConstInt<'ctx>, Error> {
+        self.value().try_const_int(self.alloc(), ty, value)
+    }
+
+    const_ints! {
+        const_u32 try_const_u32: u32 => intern_u32
+        const_i32 try_const_i32: i32 => intern_i32
+        const_u64 try_const_u64: u64 => intern_u64
+        const_i64 try_const_i64: i64 => intern_i64
+        const_u128 try_const_u128: u128 => intern_u128
+        const_i128 try_const_i128: i128 => intern_i128
+        const_usize try_const_usize: usize => intern_usize
+    }
+
+    /// Parse an integer, see [`ValueContext::intern_str`] for the accepted syntax
+    ///
+    /// Returns `None` if `text` isn't a valid integer or is out of range for `ty`
+    pub fn const_int_from_str(
+        self,
+        ty: types::IntegerTy<'ctx>,
+        text: &str,
+    ) -> Option<value::ConstInt<'ctx>> {
+        self.try_const_int_from_str(ty, text).ok()
+    }
+
+    pub fn try_const_int_from_str(
+        self,
+        ty: types::IntegerTy<'ctx>,
+        text: &str,
+    ) -> Result<value::ConstInt<'ctx>, Error> {
+        self.try_const_int(ty, self.value().try_intern_str(text)?)
+    }
+
+    /// An integer with the bit pattern of `value` truncated to the width of `ty`
+    pub fn const_int_wrapping(
+        self,
+        ty: types::IntegerTy<'ctx>,
+        value: &rug::Integer,
+    ) -> value::ConstInt<'ctx> {
+        self.value().const_int_wrapping(self.alloc(), ty, value)
+    }
+
+    /// The float nearest to `value` (rounding ties to even)
+    pub fn const_float(
+        self,
+        ty: types::FloatTy<'ctx>,
+        value: &rug::Float,
+    ) -> value::ConstFloat<'ctx> {
+        self.value().const_float(self.alloc(), ty, value)
+    }
+
+    pub fn const_f32(self, ty: types::FloatTy<'ctx>, value: f32) -> value::ConstFloat<'ctx> {
+        self.value().const_f32(self.alloc(), ty, value)
+    }
+
+    pub fn const_f64(self, ty: types::FloatTy<'ctx>, value: f64) -> value::ConstFloat<'ctx> {
+        self.value().const_f64(self.alloc(), ty, value)
+    }
+
+    /// Parse a decimal or hexadecimal (`0x1.8p3`) float
+    pub fn const_float_from_str(
+        self,
+        ty: types::FloatTy<'ctx>,
+        text: &str,
+    ) -> Option<value::ConstFloat<'ctx>> {
+        self.try_const_float_from_str(ty, text).ok()
+    }
+
+    pub fn try_const_float_from_str(
+        self,
+        ty: types::FloatTy<'ctx>,
+        text: &str,
+    ) -> Result<value::ConstFloat<'ctx>, Error> {
+        self.value()
+            .try_const_float_from_str(self.alloc(), ty, text)
+    }
+
+    pub fn const_float_from_bits(
+        self,
+        ty: types::FloatTy<'ctx>,
+        bits: u128,
+    ) -> value::ConstFloat<'ctx> {
+        self.value().const_float_from_bits(self.alloc(), ty, bits)
+    }
+
+    pub fn const_nan(
+        self,
+        ty: types::FloatTy<'ctx>,
+        negative: bool,
+        payload: u128,
+    ) -> value::ConstFloat<'ctx> {
+        self.value().const_nan(self.alloc(), ty, negative, payload)
+    }
+
+    /// An array of `items`, zero arrays are created as [`ConstAggrZero`](value::ConstAggrZero)
+    ///
+    /// Returns `None` if the items don't match the length or item type of `ty`
+    pub fn const_array(
+        self,
+        ty: types::ArrayTy<'ctx>,
+        items: &[value::Value<'ctx>],
+    ) -> Option<value::Value<'ctx>> {
+        self.try_const_array(ty, items).ok()
+    }
+
+    pub fn try_const_array(
+        self,
+        ty: types::ArrayTy<'ctx>,
+        items: &[value::Value<'ctx>],
+    ) -> Result<value::Value<'ctx>, Error> {
+        self.value().try_const_array(self.alloc(), ty, items)
+    }
+
+    /// A struct of `fields`, zero structs are created as [`ConstAggrZero`](value::ConstAggrZero)
+    ///
+    /// Returns `None` if `ty` is opaque or the fields don't match its field types
+    pub fn const_struct(
+        self,
+        ty: types::StructTy<'ctx>,
+        fields: &[value::Value<'ctx>],
+    ) -> Option<value::Value<'ctx>> {
+        self.try_const_struct(ty, fields).ok()
+    }
+
+    pub fn try_const_struct(
+        self,
+        ty: types::StructTy<'ctx>,
+        fields: &[value::Value<'ctx>],
+    ) -> Result<value::Value<'ctx>, Error> {
+        self.value().try_const_struct(self.alloc(), ty, fields)
+    }
+
+    /// A vector of `items`, zero vectors are created as [`ConstAggrZero`](value::ConstAggrZero)
+    ///
+    /// Returns `None` if `ty` is scalable or the items don't match its length or item type
+    pub fn const_vector(
+        self,
+        ty: types::VectorTy<'ctx>,
+        items: &[value::Value<'ctx>],
+    ) -> Option<value::Value<'ctx>> {
+        self.try_const_vector(ty, items).ok()
+    }
+
+    pub fn try_const_vector(
+        self,
+        ty: types::VectorTy<'ctx>,
+        items: &[value::Value<'ctx>],
+    ) -> Result<value::Value<'ctx>, Error> {
+        self.value().try_const_vector(self.alloc(), ty, items)
+    }
+
+    /// Returns `None` if `ty` isn't an array, struct or vector type
+    pub fn const_aggr_zero(
+        self,
+        ty: impl Into<types::Type<'ctx>>,
+    ) -> Option<value::ConstAggrZero<'ctx>> {
+        self.try_const_aggr_zero(ty).ok()
+    }
+
+    pub fn try_const_aggr_zero(
+        self,
+        ty: impl Into<types::Type<'ctx>>,
+    ) -> Result<value::ConstAggrZero<'ctx>, Error> {
+        self.value().try_const_aggr_zero(self.alloc(), ty.into())
+    }
+
+    pub fn const_null(self, ty: types::PointerTy<'ctx>) -> value::ConstNull<'ctx> {
+        self.value().const_null(self.alloc(), ty)
+    }
+
+    /// Returns `None` if `ty` isn't a first class type
+    pub fn undef(self, ty: impl Into<types::Type<'ctx>>) -> Option<value::Undef<'ctx>> {
+        self.try_undef(ty).ok()
+    }
+
+    pub fn try_undef(self, ty: impl Into<types::Type<'ctx>>) -> Result<value::Undef<'ctx>, Error> {
+        self.value().try_undef(self.alloc(), ty.into())
+    }
+
+    /// Returns `None` if `ty` isn't a first class type
+    pub fn poison(self, ty: impl Into<types::Type<'ctx>>) -> Option<value::Poison<'ctx>> {
+        self.try_poison(ty).ok()
+    }
+
+    pub fn try_poison(
+        self,
+        ty: impl Into<types::Type<'ctx>>,
+    ) -> Result<value::Poison<'ctx>, Error> {
+        self.value().try_poison(self.alloc(), ty.into())
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Target {
-    pub ptr_diff_bits: PtrBits,
-    pub ptr_size_bits: PtrBits,
+macro_rules! const_casts {
+    ($($(#[$meta:meta])* $name:ident $try_name:ident: $opcode:ident -> $ty:ident)*) => {$(
+        $(#[$meta])*
+        pub fn $name(
+            self,
+            value: impl Into<value::Value<'ctx>>,
+            ty: types::$ty<'ctx>,
+        ) -> Option<value::Value<'ctx>> {
+            self.$try_name(value, ty).ok()
+        }
+
+        pub fn $try_name(
+            self,
+            value: impl Into<value::Value<'ctx>>,
+            ty: types::$ty<'ctx>,
+        ) -> Result<value::Value<'ctx>, Error> {
+            self.value()
+                .try_const_cast(self.alloc(), value::ConstOpcode::$opcode, value.into(), ty.erase())
+        }
+    )*};
+}
+
+impl<'ctx> Context<'ctx> {
+    const_casts! {
+        /// Returns `None` unless `value` is an integer wider than `ty`
+        const_trunc try_const_trunc: Trunc -> IntegerTy
+        /// Returns `None` unless `value` is an integer narrower than `ty`
+        const_zext try_const_zext: ZExt -> IntegerTy
+        /// Returns `None` unless `value` is an integer narrower than `ty`
+        const_sext try_const_sext: SExt -> IntegerTy
+        /// Returns `None` unless `value` is a pointer
+        const_ptr_to_int try_const_ptr_to_int: PtrToInt -> IntegerTy
+        /// Returns `None` unless `value` is an integer
+        const_int_to_ptr try_const_int_to_ptr: IntToPtr -> PointerTy
+    }
+
+    /// Reinterpret the bits of `value` as `ty`
+    ///
+    /// Returns `None` unless both types are integers, floats or vectors of those
+    /// with the same size, or both are the same type
+    pub fn const_bitcast(
+        self,
+        value: impl Into<value::Value<'ctx>>,
+        ty: impl Into<types::Type<'ctx>>,
+    ) -> Option<value::Value<'ctx>> {
+        self.try_const_bitcast(value, ty).ok()
+    }
+
+    pub fn try_const_bitcast(
+        self,
+        value: impl Into<value::Value<'ctx>>,
+        ty: impl Into<types::Type<'ctx>>,
+    ) -> Result<value::Value<'ctx>, Error> {
+        let opcode = value::ConstOpcode::BitCast;
+        self.value()
+            .try_const_cast(self.alloc(), opcode, value.into(), ty.into())
+    }
+
+    /// Wrapping addition, returns `None` unless both are integers of the same type
+    pub fn const_add(
+        self,
+        lhs: impl Into<value::Value<'ctx>>,
+        rhs: impl Into<value::Value<'ctx>>,
+    ) -> Option<value::Value<'ctx>> {
+        self.try_const_add(lhs, rhs).ok()
+    }
+
+    pub fn try_const_add(
+        self,
+        lhs: impl Into<value::Value<'ctx>>,
+        rhs: impl Into<value::Value<'ctx>>,
+    ) -> Result<value::Value<'ctx>, Error> {
+        let opcode = value::ConstOpcode::Add;
+        self.value()
+            .try_const_binary(self.alloc(), opcode, lhs.into(), rhs.into())
+    }
+
+    /// Wrapping subtraction, returns `None` unless both are integers of the same type
+    pub fn const_sub(
+        self,
+        lhs: impl Into<value::Value<'ctx>>,
+        rhs: impl Into<value::Value<'ctx>>,
+    ) -> Option<value::Value<'ctx>> {
+        self.try_const_sub(lhs, rhs).ok()
+    }
+
+    pub fn try_const_sub(
+        self,
+        lhs: impl Into<value::Value<'ctx>>,
+        rhs: impl Into<value::Value<'ctx>>,
+    ) -> Result<value::Value<'ctx>, Error> {
+        let opcode = value::ConstOpcode::Sub;
+        self.value()
+            .try_const_binary(self.alloc(), opcode, lhs.into(), rhs.into())
+    }
+
+    /// Fold `lhs op rhs` at the width of their type, see [`value::fold::fold_int_binop`]
+    ///
+    /// The result has the type of the operands, or is poison if the operation overflows
+    /// or is undefined under `flags`.
+    /// Returns `None` if `lhs` and `rhs` have different types
+    pub fn const_int_binop(
+        self,
+        op: value::IntBinOp,
+        flags: value::IntFlags,
+        lhs: value::ConstInt<'ctx>,
+        rhs: value::ConstInt<'ctx>,
+    ) -> Option<value::Value<'ctx>> {
+        self.try_const_int_binop(op, flags, lhs, rhs).ok()
+    }
+
+    pub fn try_const_int_binop(
+        self,
+        op: value::IntBinOp,
+        flags: value::IntFlags,
+        lhs: value::ConstInt<'ctx>,
+        rhs: value::ConstInt<'ctx>,
+    ) -> Result<value::Value<'ctx>, Error> {
+        self.value()
+            .try_const_int_binop(self.alloc(), op, flags, lhs, rhs)
+    }
+
+    /// Compare two integers, returns `None` if they have different types
+    pub fn const_icmp(
+        self,
+        pred: value::IntPredicate,
+        lhs: value::ConstInt<'ctx>,
+        rhs: value::ConstInt<'ctx>,
+    ) -> Option<value::ConstInt<'ctx>> {
+        self.try_const_icmp(pred, lhs, rhs).ok()
+    }
+
+    pub fn try_const_icmp(
+        self,
+        pred: value::IntPredicate,
+        lhs: value::ConstInt<'ctx>,
+        rhs: value::ConstInt<'ctx>,
+    ) -> Result<value::ConstInt<'ctx>, Error> {
+        let value = if self.value().try_const_icmp(pred, lhs, rhs)? {
+            self.value().one_value()
+        } else {
+            self.value().zero_value()
+        };
+
+        self.try_const_int(self.i1(), value)
+    }
+
+    /// The address of the element of `source_ty` selected by `indices`, starting at `base`
+    pub fn const_gep(
+        self,
+        source_ty: impl Into<types::Type<'ctx>>,
+        base: impl Into<value::Value<'ctx>>,
+        indices: &[value::Value<'ctx>],
+    ) -> Option<value::Value<'ctx>> {
+        self.try_const_gep(source_ty, base, indices).ok()
+    }
+
+    pub fn try_const_gep(
+        self,
+        source_ty: impl Into<types::Type<'ctx>>,
+        base: impl Into<value::Value<'ctx>>,
+        indices: &[value::Value<'ctx>],
+    ) -> Result<value::Value<'ctx>, Error> {
+        self.value()
+            .try_const_gep(self.alloc(), source_ty.into(), base.into(), indices, false)
+    }
+
+    /// Like [`Context::const_gep`], but the address must be inside the allocation of `base`
+    pub fn const_inbounds_gep(
+        self,
+        source_ty: impl Into<types::Type<'ctx>>,
+        base: impl Into<value::Value<'ctx>>,
+        indices: &[value::Value<'ctx>],
+    ) -> Option<value::Value<'ctx>> {
+        self.try_const_inbounds_gep(source_ty, base, indices).ok()
+    }
+
+    pub fn try_const_inbounds_gep(
+        self,
+        source_ty: impl Into<types::Type<'ctx>>,
+        base: impl Into<value::Value<'ctx>>,
+        indices: &[value::Value<'ctx>],
+    ) -> Result<value::Value<'ctx>, Error> {
+        self.value()
+            .try_const_gep(self.alloc(), source_ty.into(), base.into(), indices, true)
+    }
 }
 
-#[repr(u16)]
-#[non_exhaustive]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum PtrBits {
-    _8 = 8,
-    _16 = 16,
-    _32 = 32,
-    _64 = 64,
-    _128 = 128,
+impl<'ctx> Context<'ctx> {
+    #[inline]
+    pub fn target(self) -> &'ctx Target {
+        &self.info.target
+    }
+
+    #[inline]
+    pub fn data_layout(self) -> layout::DataLayout<'ctx> {
+        layout::DataLayout::new(self.target())
+    }
+
+    /// The size in bits of a primitive type (integers, floats, pointers and vectors of them)
+    ///
+    /// This doesn't include any padding required for alignment,
+    /// and is `None` for all other types
+    pub fn primitive_size_bits(self, ty: impl Into<types::Type<'ctx>>) -> Option<types::TypeSize> {
+        use types::{TypeSize, UnpackedType};
+
+        let size = match ty.into().unpack() {
+            UnpackedType::Integer(ty) => TypeSize::fixed(ty.bits().get().into()),
+            UnpackedType::Float(ty) => TypeSize::fixed(ty.storage_bits().into()),
+            UnpackedType::Pointer(ty) => TypeSize::fixed(
+                self.data_layout()
+                    .pointer_size_bits(ty.address_space())
+                    .into(),
+            ),
+            UnpackedType::Vector(ty) => {
+                let item_size = self.primitive_size_bits(ty.item_ty())?.unwrap_fixed();
+                TypeSize::new(item_size * u64::from(ty.len()), ty.is_scalable())
+            }
+            UnpackedType::Unit(_)
+            | UnpackedType::Function(_)
+            | UnpackedType::Array(_)
+            | UnpackedType::Struct(_) => return None,
+        };
+
+        Some(size)
+    }
 }
 
 struct ContextBuilder {
@@ -201,10 +734,7 @@ impl<'ctx> Ctor<ContextBuilder> for ContextInfo<'ctx> {
 
 #[test]
 fn test() {
-    let target = Target {
-        ptr_diff_bits: PtrBits::_32,
-        ptr_size_bits: PtrBits::_32,
-    };
+    let target = Target::new(crate::PtrBits::_32, crate::PtrBits::_32);
 
     Context::with(target, |ctx| {
         let _ = ctx.ty().unit();
@@ -212,13 +742,254 @@ fn test() {
         assert_eq!(ctx.int_ty_lit(9), ctx.int_ty_lit(9));
         assert_ne!(ctx.int_ty_lit(9), ctx.int_ty_lit(10));
 
+        assert_eq!(ctx.f32().erase().try_cast(), Some(ctx.f32()));
+        assert!(ctx.f32().erase().try_cast::<types::IntegerInfo>().is_none());
+        assert_eq!(ctx.float_ty(types::FloatKind::Ieee64Bit), ctx.f64());
+        assert_ne!(ctx.f16().erase(), ctx.i16().erase());
+        assert_eq!(ctx.bf16(), ctx.float_ty(types::FloatKind::BFloat16));
+        assert_ne!(ctx.bf16(), ctx.f16());
+        assert_eq!(ctx.x86_fp80().storage_bits(), 80);
+
+        assert_eq!(ctx.vector_ty(4, ctx.i32()), ctx.vector_ty(4, ctx.i32()));
+        assert_ne!(ctx.vector_ty(4, ctx.i32()), ctx.vector_ty(8, ctx.i32()));
+        assert_ne!(
+            ctx.vector_ty(4, ctx.i32()).erase(),
+            ctx.array_ty(4, ctx.i32()).erase()
+        );
+        assert_eq!(format!("{:?}", ctx.vector_ty(8, ctx.f16())), "<f16\u{d7}8>");
+
+        let nxv4i32 = ctx.scalable_vector_ty(4, ctx.i32());
+        assert_eq!(nxv4i32, ctx.scalable_vector_ty(4, ctx.i32()));
+        assert_ne!(nxv4i32, ctx.vector_ty(4, ctx.i32()));
+        assert_eq!(
+            ctx.primitive_size_bits(nxv4i32),
+            Some(types::TypeSize::scalable(128))
+        );
+        assert_eq!(
+            ctx.primitive_size_bits(ctx.vector_ty(4, ctx.ptr_ty())),
+            Some(types::TypeSize::fixed(128))
+        );
+
+        let node = ctx.identified_struct_ty("Node");
+        let other = ctx.identified_struct_ty("Node");
+        assert_ne!(node, other);
+        assert_eq!(other.name(), Some(istr::IStr::new("Node.0")));
+        assert!(node.is_opaque());
+        ctx.set_struct_body(node, &[ctx.ptr_ty().erase(), ctx.i32().erase()], false);
+        ctx.set_struct_body(other, &[ctx.ptr_ty().erase(), ctx.i32().erase()], false);
+        assert!(!node.is_opaque());
+        assert_eq!(node.field_tys(), other.field_tys());
+        assert_ne!(node, other);
+        assert_eq!(
+            ctx.ty().get_identified_struct(istr::IStr::new("Node")),
+            Some(node)
+        );
+
+        let layout = ctx.data_layout();
+        let fields = [ctx.i8().erase(), ctx.i32().erase(), ctx.i64().erase()];
+        let unpacked = layout.struct_layout(ctx.struct_ty((), Default::default(), &fields));
+        assert_eq!(unpacked.field_offsets(), [0, 4, 8]);
+        assert_eq!(unpacked.size(), types::TypeSize::fixed(16));
+        let packed = layout.struct_layout(ctx.struct_ty((), types::StructTy::PACKED, &fields));
+        assert_eq!(packed.field_offsets(), [0, 1, 5]);
+        assert_eq!(packed.size(), types::TypeSize::fixed(13));
+        assert_eq!(layout.alloc_size(node), types::TypeSize::fixed(8));
+        assert_eq!(layout.abi_align(ctx.x86_fp80()).bytes(), 16);
+        assert_eq!(layout.store_size(nxv4i32), types::TypeSize::scalable(16));
+
         assert_eq!(
             ctx.function_ty(ctx.iptr(), &[ctx.unit().erase()]),
             ctx.function_ty(ctx.i32(), &[ctx.unit().erase()]),
         );
 
         let four = ctx.value().intern_i32(4);
-        ctx.const_int(ctx.int_ty_lit(3), four, false).unwrap();
-        assert!(ctx.const_int(ctx.int_ty_lit(3), four, true).is_none());
+        let i3 = ctx.int_ty_lit(3);
+        let four = ctx.const_int(i3, four).unwrap();
+        let minus_four = ctx.const_int(i3, ctx.value().intern_i32(-4)).unwrap();
+        assert_eq!(four, minus_four);
+        assert_eq!(*four.as_unsigned(), 4);
+        assert_eq!(four.as_signed(), -4);
+        assert!(ctx.const_int(i3, ctx.value().intern_i32(8)).is_none());
+        assert!(ctx.const_int(i3, ctx.value().intern_i32(-5)).is_none());
+        let wrapped = ctx.const_int_wrapping(i3, &rug::Integer::from(-5));
+        assert_eq!(*wrapped.as_unsigned(), 3);
+
+        assert_eq!(ctx.const_u64(i3, 7), ctx.const_i64(i3, -1));
+        assert!(ctx
+            .const_u128(ctx.i64(), u128::from(u64::MAX) + 1)
+            .is_none());
+        assert_eq!(
+            ctx.const_int_from_str(ctx.i8(), "-0x80"),
+            ctx.const_u32(ctx.i8(), 0x80)
+        );
+        assert_eq!(
+            ctx.const_int_from_str(ctx.i8(), "0b1010_1010"),
+            ctx.const_u32(ctx.i8(), 0xaa)
+        );
+        assert_eq!(
+            ctx.value().intern_str("0o17"),
+            Some(ctx.value().intern_u32(15))
+        );
+        assert!(ctx.value().intern_str("0x").is_none());
+        assert!(ctx.value().intern_str("--1").is_none());
+        assert!(ctx.value().intern_str("0x-1").is_none());
+        assert!(ctx.value().intern_str("1 2").is_none());
+        assert!(ctx.value().intern_str(" 12").is_none());
+        assert!(ctx.value().intern_str("0x_ff").is_none());
+        assert!(ctx.value().intern_str("1__000").is_none());
+        assert!(ctx.value().intern_str("1000_").is_none());
+        assert_eq!(
+            ctx.value().intern_str("1_000"),
+            Some(ctx.value().intern_u32(1000))
+        );
+
+        let f32 = ctx.f32();
+        assert_eq!(ctx.const_f32(f32, 3.5).bits(), 0x4060_0000);
+        assert_eq!(ctx.const_f64(f32, 3.5), ctx.const_f32(f32, 3.5));
+        assert_ne!(ctx.const_f32(f32, 0.0), ctx.const_f32(f32, -0.0));
+        assert_eq!(
+            ctx.const_float_from_str(f32, "0x1.8p1"),
+            Some(ctx.const_f32(f32, 3.0))
+        );
+        assert_eq!(
+            ctx.const_float_from_str(f32, "0.1").unwrap().bits(),
+            0x3dcc_cccd
+        );
+        assert_eq!(ctx.const_f64(ctx.f16(), 1e10).bits(), 0x7c00);
+        assert_eq!(ctx.const_f64(ctx.f16(), 2f64.powi(-24)).bits(), 0x0001);
+        assert_ne!(ctx.const_nan(f32, false, 1), ctx.const_nan(f32, false, 2));
+        assert!(ctx.const_nan(f32, false, 1).is_nan());
+
+        let ptr1 = ctx.ptr_ty_at(types::AddressSpace::new(1));
+        assert_eq!(ctx.const_null(ptr1).address_space(), ptr1.address_space());
+        assert_ne!(
+            ctx.const_null(ptr1).erase(),
+            ctx.const_null(ctx.ptr_ty()).erase()
+        );
+        assert_eq!(ctx.undef(ctx.i32()), ctx.undef(ctx.i32()));
+        assert_ne!(
+            ctx.undef(ctx.i32()).unwrap().erase(),
+            ctx.poison(ctx.i32()).unwrap().erase()
+        );
+        assert!(ctx.undef(ctx.unit()).is_none());
+        assert!(ctx.poison(ctx.function_ty(ctx.unit(), &[])).is_none());
+        assert_eq!(
+            format!("{:?}", ctx.poison(ctx.i8()).unwrap().erase()),
+            "poison"
+        );
+
+        let byte = |x| ctx.const_i32(ctx.i8(), x).unwrap();
+        let flags = value::IntFlags::default();
+        let sum = ctx.const_int_binop(value::IntBinOp::Add, flags, byte(200), byte(100));
+        assert_eq!(sum, Some(byte(44).erase()));
+        let div = ctx.const_int_binop(value::IntBinOp::UDiv, flags, byte(1), byte(0));
+        assert_eq!(div, Some(ctx.poison(ctx.i8()).unwrap().erase()));
+        let less = ctx
+            .const_icmp(value::IntPredicate::Slt, byte(-1), byte(1))
+            .unwrap();
+        assert_eq!(
+            less,
+            ctx.const_int(ctx.i1(), ctx.value().one_value()).unwrap()
+        );
+
+        let array = ctx.array_ty(4, ctx.i32());
+        let zero = ctx.const_aggr_zero(array).unwrap();
+        assert!(core::ptr::eq(
+            zero.info(),
+            ctx.const_aggr_zero(array).unwrap().info()
+        ));
+        assert!(ctx.const_aggr_zero(ctx.i32()).is_none());
+        assert!(ctx.const_aggr_zero(ctx.unit()).is_none());
+        assert!(ctx
+            .const_aggr_zero(ctx.function_ty(ctx.unit(), &[]))
+            .is_none());
+        assert!(ctx
+            .const_aggr_zero(ctx.identified_struct_ty("Opaque"))
+            .is_none());
+
+        assert_eq!(ctx.try_int_ty(0), Err(Error::ZeroWidthInteger));
+        assert_eq!(
+            ctx.try_ptr_ty_at(1 << 24),
+            Err(Error::InvalidAddressSpace {
+                address_space: 1 << 24
+            })
+        );
+        assert_eq!(ctx.try_ptr_ty_at(1), Ok(ptr1));
+        assert_eq!(
+            ctx.try_vector_ty(0, ctx.i32()),
+            Err(Error::ZeroLengthVector)
+        );
+        assert!(matches!(
+            ctx.try_scalable_vector_ty(4, array),
+            Err(Error::InvalidVectorItem { .. })
+        ));
+        assert_eq!(
+            ctx.try_const_i32(i3, 8),
+            Err(Error::IntOutOfRange {
+                value: "8".to_owned(),
+                bits: 3
+            })
+        );
+        assert_eq!(
+            ctx.try_const_int_from_str(ctx.i8(), "0x")
+                .unwrap_err()
+                .to_string(),
+            "`0x` is not a valid literal"
+        );
+        assert!(matches!(
+            ctx.try_const_array(array, &[byte(1).erase()]),
+            Err(Error::LengthMismatch {
+                expected: 4,
+                found: 1
+            })
+        ));
+        assert!(matches!(
+            ctx.try_const_add(byte(1), ctx.const_i32(ctx.i32(), 1).unwrap()),
+            Err(Error::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            ctx.try_const_trunc(byte(1), ctx.i32()),
+            Err(Error::InvalidCast {
+                opcode: "trunc",
+                ..
+            })
+        ));
+        assert!(matches!(
+            ctx.try_poison(ctx.unit()),
+            Err(Error::NotFirstClass { .. })
+        ));
+        assert!(matches!(
+            ctx.try_set_struct_body(node, &[], false),
+            Err(Error::StructBodyAlreadySet { .. })
+        ));
+    });
+}
+
+#[test]
+fn test_address_space() {
+    let mut target = Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+    target.layout.pointers.insert(
+        1,
+        layout::PointerSpec {
+            size_bits: 32,
+            align: layout::AlignSpec::natural(4),
+            index_bits: 32,
+        },
+    );
+
+    Context::with(target, |ctx| {
+        let global = types::AddressSpace::new(1);
+        assert_eq!(global.get(), 1);
+        assert_eq!(types::AddressSpace::new(0x12_3456).get(), 0x12_3456);
+        assert!(!global.is_default());
+
+        let layout = ctx.data_layout();
+        assert_eq!(layout.pointer_size_bits(global), 32);
+        assert_eq!(layout.pointer_size(global), 4);
+        assert_eq!(layout.pointer_size_bits(types::AddressSpace::new(2)), 64);
+        assert_eq!(
+            ctx.primitive_size_bits(ctx.ptr_ty_at(global)),
+            Some(types::TypeSize::fixed(32))
+        );
     });
 }
@@ -31,6 +31,24 @@ impl<'ctx> AllocContext<'ctx> {
         Ok(init.into_mut())
     }
 
+    pub(crate) fn alloc_slice_copy<T: Copy>(self, items: &[T]) -> &'ctx [T] {
+        self.info.alloc.alloc_slice_copy(items)
+    }
+
+    /// `T` is never dropped
+    pub(crate) fn alloc_slice_fill_iter<T, I>(self, items: I) -> &'ctx [T]
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.info.alloc.alloc_slice_fill_iter(items)
+    }
+
+    /// `T` is never dropped
+    pub(crate) fn alloc<T>(self, value: T) -> &'ctx T {
+        self.info.alloc.alloc(value)
+    }
+
     pub(crate) fn ctx_ref(self) -> ContextRef<'ctx> {
         self.info.ctx_ref
     }
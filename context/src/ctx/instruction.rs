@@ -0,0 +1,958 @@
+use crate::{
+    error::{check_block, check_function, check_ty, describe, unwrap_or_panic},
+    name::Name,
+    types::{self, Type, UnpackedType},
+    value::{
+        BasicBlock, CastOp, ConstInt, Function, Instruction, Opcode, UnpackedValue, Use, Users,
+        Value,
+    },
+    Error,
+};
+
+use super::{
+    value_ctx::{bitcast_width, check_gep},
+    Context,
+};
+
+fn check_operand_count(opcode: Opcode<'_>, expected: usize, found: usize) -> Result<(), Error> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(Error::OperandCount {
+            opcode: opcode.name(),
+            expected,
+            found,
+        })
+    }
+}
+
+fn check_block_count(opcode: Opcode<'_>, expected: usize, found: usize) -> Result<(), Error> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(Error::BlockCount {
+            opcode: opcode.name(),
+            expected,
+            found,
+        })
+    }
+}
+
+/// The item type of vectors, and the type itself for everything else
+fn scalar_ty(ty: Type<'_>) -> Type<'_> {
+    match ty.unpack() {
+        UnpackedType::Vector(ty) => ty.item_ty(),
+        _ => ty,
+    }
+}
+
+/// Whether `a` and `b` are both scalars, or vectors with the same number of lanes
+fn same_shape(a: Type<'_>, b: Type<'_>) -> bool {
+    match (a.unpack(), b.unpack()) {
+        (UnpackedType::Vector(a), UnpackedType::Vector(b)) => {
+            a.len() == b.len() && a.is_scalable() == b.is_scalable()
+        }
+        (UnpackedType::Vector(_), _) | (_, UnpackedType::Vector(_)) => false,
+        _ => true,
+    }
+}
+
+fn is_valid_cast(op: CastOp, from: Type<'_>, to: Type<'_>) -> bool {
+    use types::TypeTag::{Float, Integer, Pointer};
+
+    if op == CastOp::BitCast {
+        return from == to
+            || matches!((bitcast_width(from), bitcast_width(to)), (Some(a), Some(b)) if a == b);
+    }
+
+    if !same_shape(from, to) {
+        return false;
+    }
+
+    let (from, to) = (scalar_ty(from), scalar_ty(to));
+    let int_bits = |ty: Type<'_>| {
+        ty.try_cast::<types::IntegerInfo>()
+            .map(|ty| ty.bits().get())
+    };
+    let float_bits = |ty: Type<'_>| {
+        ty.try_cast::<types::FloatInfo>()
+            .map(|ty| ty.storage_bits())
+    };
+
+    match op {
+        CastOp::Trunc => matches!((int_bits(from), int_bits(to)), (Some(a), Some(b)) if a > b),
+        CastOp::ZExt | CastOp::SExt => {
+            matches!((int_bits(from), int_bits(to)), (Some(a), Some(b)) if a < b)
+        }
+        CastOp::FPTrunc => {
+            matches!((float_bits(from), float_bits(to)), (Some(a), Some(b)) if a > b)
+        }
+        CastOp::FPExt => {
+            matches!((float_bits(from), float_bits(to)), (Some(a), Some(b)) if a < b)
+        }
+        CastOp::FPToUI | CastOp::FPToSI => from.tag() == Float && to.tag() == Integer,
+        CastOp::UIToFP | CastOp::SIToFP => from.tag() == Integer && to.tag() == Float,
+        CastOp::PtrToInt => from.tag() == Pointer && to.tag() == Integer,
+        CastOp::IntToPtr => from.tag() == Integer && to.tag() == Pointer,
+        CastOp::AddrSpaceCast => {
+            match (
+                from.try_cast::<types::PointerInfo>(),
+                to.try_cast::<types::PointerInfo>(),
+            ) {
+                (Some(a), Some(b)) => a.address_space() != b.address_space(),
+                _ => false,
+            }
+        }
+        CastOp::BitCast => unreachable!(),
+    }
+}
+
+/// Check that the cases of a switch are distinct integer constants
+fn check_switch_cases(cases: &[Value<'_>]) -> Result<(), Error> {
+    for (i, &case) in cases.iter().enumerate() {
+        if !matches!(case.unpack(), UnpackedValue::ConstInt(_)) {
+            return Err(Error::NotConstant {
+                value: describe(case),
+            });
+        }
+
+        if cases[..i].contains(&case) {
+            return Err(Error::DuplicateCase {
+                value: describe(case),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Check the operands which must be particular values, not only have a particular type
+fn check_operands<'ctx>(opcode: Opcode<'ctx>, operands: &[Value<'ctx>]) -> Result<(), Error> {
+    match opcode {
+        Opcode::Switch => check_switch_cases(&operands[1..]),
+        Opcode::GetElementPtr { source_ty, .. } => {
+            check_gep(source_ty, operands[0], &operands[1..])
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Check that `value` can become an operand of `user`, which is already created
+fn check_user<'ctx>(user: Instruction<'ctx>, value: Value<'ctx>) -> Result<(), Error> {
+    if value == user.erase() && !matches!(user.opcode(), Opcode::Phi { .. }) {
+        return Err(Error::SelfReference {
+            value: describe(value),
+        });
+    }
+
+    match user.function() {
+        Some(function) => check_function(function, value),
+        None => Ok(()),
+    }
+}
+
+impl<'ctx> Context<'ctx> {
+    /// Create an instruction which isn't in a block yet, see [`Opcode`] for the
+    /// operands of each opcode
+    ///
+    /// # Panics
+    ///
+    /// If the operands or blocks don't match the opcode
+    #[track_caller]
+    pub fn instruction(
+        self,
+        opcode: Opcode<'ctx>,
+        operands: &[Value<'ctx>],
+        blocks: &[BasicBlock<'ctx>],
+    ) -> Instruction<'ctx> {
+        unwrap_or_panic(self.try_instruction(opcode, operands, blocks))
+    }
+
+    pub fn try_instruction(
+        self,
+        opcode: Opcode<'ctx>,
+        operands: &[Value<'ctx>],
+        blocks: &[BasicBlock<'ctx>],
+    ) -> Result<Instruction<'ctx>, Error> {
+        let ty = self.result_ty(opcode, operands, blocks)?;
+        let inst = Instruction::new(self.alloc(), ty, opcode, operands, blocks);
+        for (index, &operand) in operands.iter().enumerate() {
+            self.value().add_use(operand, Use::new(inst, index));
+        }
+        Ok(inst)
+    }
+
+    /// Add an empty block to the end of `function`
+    pub fn append_basic_block(self, function: Function<'ctx>, name: impl Name) -> BasicBlock<'ctx> {
+        BasicBlock::new(self.alloc(), function, name.to_name())
+    }
+
+    /// Add the value `phi` has when control comes from `block`
+    ///
+    /// # Panics
+    ///
+    /// If `phi` isn't a phi, `value` doesn't have its type, or `value` or
+    /// `block` belong to another function than `phi`
+    #[track_caller]
+    pub fn add_phi_incoming(
+        self,
+        phi: Instruction<'ctx>,
+        value: impl Into<Value<'ctx>>,
+        block: BasicBlock<'ctx>,
+    ) {
+        unwrap_or_panic(self.try_add_phi_incoming(phi, value, block))
+    }
+
+    pub fn try_add_phi_incoming(
+        self,
+        phi: Instruction<'ctx>,
+        value: impl Into<Value<'ctx>>,
+        block: BasicBlock<'ctx>,
+    ) -> Result<(), Error> {
+        let value = value.into();
+
+        let Opcode::Phi { ty } = phi.opcode() else {
+            return Err(Error::InvalidOperands {
+                opcode: phi.opcode().name(),
+                ty: describe(phi.ty()),
+            });
+        };
+
+        check_ty(ty, value.ty())?;
+        self.push_operand(phi, value, block)
+    }
+
+    /// Jump to `target` when the value of `switch` is `value`
+    ///
+    /// # Panics
+    ///
+    /// If `switch` isn't a switch, `value` doesn't have the type of its
+    /// value, there is already a case for `value`, or `target` belongs to
+    /// another function than `switch`
+    #[track_caller]
+    pub fn add_switch_case(
+        self,
+        switch: Instruction<'ctx>,
+        value: ConstInt<'ctx>,
+        target: BasicBlock<'ctx>,
+    ) {
+        unwrap_or_panic(self.try_add_switch_case(switch, value, target))
+    }
+
+    pub fn try_add_switch_case(
+        self,
+        switch: Instruction<'ctx>,
+        value: ConstInt<'ctx>,
+        target: BasicBlock<'ctx>,
+    ) -> Result<(), Error> {
+        if switch.opcode() != Opcode::Switch {
+            return Err(Error::InvalidOperands {
+                opcode: switch.opcode().name(),
+                ty: describe(value.ty()),
+            });
+        }
+
+        check_ty(switch.operand(0).ty(), value.ty())?;
+        if switch.operands().skip(1).any(|case| case == value.erase()) {
+            return Err(Error::DuplicateCase {
+                value: describe(value),
+            });
+        }
+
+        self.push_operand(switch, value.erase(), target)
+    }
+
+    fn push_operand(
+        self,
+        inst: Instruction<'ctx>,
+        value: Value<'ctx>,
+        block: BasicBlock<'ctx>,
+    ) -> Result<(), Error> {
+        check_user(inst, value)?;
+        if let Some(function) = inst.function() {
+            check_block(function, block)?;
+        }
+
+        inst.push_operand(self.alloc(), value, block);
+        let index = inst.num_operands() - 1;
+        self.value().add_use(value, Use::new(inst, index));
+        Ok(())
+    }
+
+    /// The instruction operands `value` is used as, in the order they were added
+    ///
+    /// Only the operands of instructions are tracked, not the items of
+    /// constants or the initializers of globals, see [`Context::has_const_users`]
+    pub fn uses(self, value: impl Into<Value<'ctx>>) -> Vec<Use<'ctx>> {
+        self.value().uses(value.into())
+    }
+
+    /// The instructions which use `value`, see [`Context::uses`]
+    pub fn users(self, value: impl Into<Value<'ctx>>) -> Users<'ctx> {
+        Users {
+            uses: self.uses(value).into_iter(),
+        }
+    }
+
+    pub fn has_uses(self, value: impl Into<Value<'ctx>>) -> bool {
+        self.value().num_uses(value.into()) != 0
+    }
+
+    /// Whether a constant or the initializer of a global uses `value`
+    ///
+    /// Constants are interned and never rewritten, so once a constant has been
+    /// created with `value` as an operand this stays true
+    pub fn has_const_users(self, value: impl Into<Value<'ctx>>) -> bool {
+        self.value().has_const_users(value.into())
+    }
+
+    /// Replace the operand of `inst` at `index` with `value`
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds, or `value` can't be used in place of the
+    /// operand: it must have the same type, switch cases must stay distinct
+    /// integer constants, getelementptr indices must still select an element,
+    /// only phis can use themselves, and instructions and arguments can't be
+    /// used by another function
+    #[track_caller]
+    pub fn set_operand(self, inst: Instruction<'ctx>, index: usize, value: impl Into<Value<'ctx>>) {
+        unwrap_or_panic(self.try_set_operand(inst, index, value))
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is out of bounds
+    pub fn try_set_operand(
+        self,
+        inst: Instruction<'ctx>,
+        index: usize,
+        value: impl Into<Value<'ctx>>,
+    ) -> Result<(), Error> {
+        let value = value.into();
+        let old = inst.operand(index);
+        check_ty(old.ty(), value.ty())?;
+        if old == value {
+            return Ok(());
+        }
+
+        check_user(inst, value)?;
+        let mut operands = inst.operands().collect::<Vec<_>>();
+        operands[index] = value;
+        check_operands(inst.opcode(), &operands)?;
+
+        self.replace_operand(inst, index, value);
+        Ok(())
+    }
+
+    /// Make every instruction which uses `old` use `new` instead
+    ///
+    /// # Panics
+    ///
+    /// * if `old` and `new` have different types, or `new` can't be used in place
+    ///   of `old` by one of the users, see [`Context::set_operand`]
+    /// * if `old` is used by a constant or the initializer of a global,
+    ///   see [`Context::has_const_users`]
+    #[track_caller]
+    pub fn replace_all_uses_with(self, old: impl Into<Value<'ctx>>, new: impl Into<Value<'ctx>>) {
+        unwrap_or_panic(self.try_replace_all_uses_with(old, new))
+    }
+
+    pub fn try_replace_all_uses_with(
+        self,
+        old: impl Into<Value<'ctx>>,
+        new: impl Into<Value<'ctx>>,
+    ) -> Result<(), Error> {
+        let (old, new) = (old.into(), new.into());
+        check_ty(old.ty(), new.ty())?;
+        if old == new {
+            return Ok(());
+        }
+
+        if self.value().has_const_users(old) {
+            return Err(Error::UsedByConstant {
+                value: describe(old),
+            });
+        }
+
+        let uses = self.value().uses(old);
+
+        // check every user before changing any of them
+        for operand in &uses {
+            let inst = operand.user();
+            check_user(inst, new)?;
+            let operands = inst
+                .operands()
+                .map(|value| if value == old { new } else { value })
+                .collect::<Vec<_>>();
+            check_operands(inst.opcode(), &operands)?;
+        }
+
+        for operand in uses {
+            self.replace_operand(operand.user(), operand.operand_index(), new);
+        }
+        Ok(())
+    }
+
+    fn replace_operand(self, inst: Instruction<'ctx>, index: usize, value: Value<'ctx>) {
+        let operand = Use::new(inst, index);
+        let cell = &inst.operand_cells()[index];
+        self.value().remove_use(cell.get(), operand);
+        cell.set(value);
+        self.value().add_use(value, operand);
+    }
+
+    /// Remove `inst` from its block and drop its operands,
+    /// an erased instruction can't be inserted again
+    ///
+    /// # Panics
+    ///
+    /// If `inst` is still used by any instruction
+    #[track_caller]
+    pub fn erase_instruction(self, inst: Instruction<'ctx>) {
+        unwrap_or_panic(self.try_erase_instruction(inst))
+    }
+
+    pub fn try_erase_instruction(self, inst: Instruction<'ctx>) -> Result<(), Error> {
+        let uses = self.value().num_uses(inst.erase());
+        if uses != 0 {
+            return Err(Error::HasUses {
+                value: describe(inst),
+                uses,
+            });
+        }
+
+        for (index, operand) in inst.operands().enumerate() {
+            self.value().remove_use(operand, Use::new(inst, index));
+        }
+        inst.erase_unchecked();
+        Ok(())
+    }
+
+    /// An `i1`, or a vector of `i1` with the same number of lanes as `ty`
+    fn bool_ty_like(self, ty: Type<'ctx>) -> Result<Type<'ctx>, Error> {
+        let i1 = self.i1().erase();
+        match ty.unpack() {
+            UnpackedType::Vector(ty) if ty.is_scalable() => {
+                Ok(self.try_scalable_vector_ty(ty.len(), i1)?.erase())
+            }
+            UnpackedType::Vector(ty) => Ok(self.try_vector_ty(ty.len(), i1)?.erase()),
+            _ => Ok(i1),
+        }
+    }
+
+    /// Check the operands and blocks of `opcode`, and compute the type of its result
+    fn result_ty(
+        self,
+        opcode: Opcode<'ctx>,
+        operands: &[Value<'ctx>],
+        blocks: &[BasicBlock<'ctx>],
+    ) -> Result<Type<'ctx>, Error> {
+        use types::TypeTag;
+
+        let invalid_operands = |ty: Type<'ctx>| Error::InvalidOperands {
+            opcode: opcode.name(),
+            ty: describe(ty),
+        };
+
+        let unit = self.unit().erase();
+
+        if !matches!(
+            opcode,
+            Opcode::Phi { .. } | Opcode::Br | Opcode::CondBr | Opcode::Switch
+        ) {
+            check_block_count(opcode, 0, blocks.len())?;
+        }
+
+        let ty = match opcode {
+            Opcode::IntBinary { .. }
+            | Opcode::FloatBinary(_)
+            | Opcode::ICmp(_)
+            | Opcode::FCmp(_) => {
+                check_operand_count(opcode, 2, operands.len())?;
+                let ty = operands[0].ty();
+                check_ty(ty, operands[1].ty())?;
+
+                let valid = match opcode {
+                    Opcode::IntBinary { .. } => scalar_ty(ty).tag() == TypeTag::Integer,
+                    Opcode::ICmp(_) => {
+                        matches!(scalar_ty(ty).tag(), TypeTag::Integer | TypeTag::Pointer)
+                    }
+                    _ => scalar_ty(ty).tag() == TypeTag::Float,
+                };
+                if !valid {
+                    return Err(invalid_operands(ty));
+                }
+
+                match opcode {
+                    Opcode::ICmp(_) | Opcode::FCmp(_) => self.bool_ty_like(ty)?,
+                    _ => ty,
+                }
+            }
+            Opcode::FNeg => {
+                check_operand_count(opcode, 1, operands.len())?;
+                let ty = operands[0].ty();
+                if scalar_ty(ty).tag() != TypeTag::Float {
+                    return Err(invalid_operands(ty));
+                }
+                ty
+            }
+            Opcode::Cast { op, ty } => {
+                check_operand_count(opcode, 1, operands.len())?;
+                let from = operands[0].ty();
+                if !is_valid_cast(op, from, ty) {
+                    return Err(Error::InvalidCast {
+                        opcode: op.name(),
+                        from: describe(from),
+                        to: describe(ty),
+                    });
+                }
+                ty
+            }
+            Opcode::Alloca { allocated_ty, .. } => {
+                check_operand_count(opcode, 1, operands.len())?;
+                if !allocated_ty.is_first_class() || !self.data_layout().is_sized(allocated_ty) {
+                    return Err(Error::NotFirstClass {
+                        ty: describe(allocated_ty),
+                    });
+                }
+
+                let len_ty = operands[0].ty();
+                if len_ty.tag() != TypeTag::Integer {
+                    return Err(invalid_operands(len_ty));
+                }
+
+                let address_space = self.target().layout.alloca_address_space;
+                self.ptr_ty_at(types::AddressSpace::try_new(address_space)?)
+                    .erase()
+            }
+            Opcode::Load { ty, .. } => {
+                check_operand_count(opcode, 1, operands.len())?;
+                if !ty.is_first_class() || !self.data_layout().is_sized(ty) {
+                    return Err(Error::NotFirstClass { ty: describe(ty) });
+                }
+
+                let ptr_ty = operands[0].ty();
+                if ptr_ty.tag() != TypeTag::Pointer {
+                    return Err(invalid_operands(ptr_ty));
+                }
+                ty
+            }
+            Opcode::Store { .. } => {
+                check_operand_count(opcode, 2, operands.len())?;
+                let value_ty = operands[0].ty();
+                if !value_ty.is_first_class() || !self.data_layout().is_sized(value_ty) {
+                    return Err(Error::NotFirstClass {
+                        ty: describe(value_ty),
+                    });
+                }
+
+                let ptr_ty = operands[1].ty();
+                if ptr_ty.tag() != TypeTag::Pointer {
+                    return Err(invalid_operands(ptr_ty));
+                }
+                unit
+            }
+            Opcode::GetElementPtr { source_ty, .. } => {
+                let Some((&base, indices)) = operands.split_first() else {
+                    return Err(Error::OperandCount {
+                        opcode: opcode.name(),
+                        expected: 1,
+                        found: 0,
+                    });
+                };
+
+                check_gep(source_ty, base, indices)?;
+                base.ty()
+            }
+            Opcode::Call { function_ty } => {
+                let argument_tys = function_ty.arguments_tys();
+                check_operand_count(opcode, argument_tys.len() + 1, operands.len())?;
+
+                let callee_ty = operands[0].ty();
+                if callee_ty.tag() != TypeTag::Pointer {
+                    return Err(invalid_operands(callee_ty));
+                }
+
+                for (&ty, argument) in argument_tys.iter().zip(&operands[1..]) {
+                    check_ty(ty, argument.ty())?;
+                }
+                function_ty.output_ty()
+            }
+            Opcode::Select => {
+                check_operand_count(opcode, 3, operands.len())?;
+                let ty = operands[1].ty();
+                check_ty(ty, operands[2].ty())?;
+
+                let condition_ty = operands[0].ty();
+                if condition_ty != self.i1().erase() {
+                    check_ty(self.bool_ty_like(ty)?, condition_ty)?;
+                }
+                ty
+            }
+            Opcode::Phi { ty } => {
+                check_block_count(opcode, operands.len(), blocks.len())?;
+                if !ty.is_first_class() {
+                    return Err(Error::NotFirstClass { ty: describe(ty) });
+                }
+
+                for value in operands {
+                    check_ty(ty, value.ty())?;
+                }
+                ty
+            }
+            Opcode::Br => {
+                check_operand_count(opcode, 0, operands.len())?;
+                check_block_count(opcode, 1, blocks.len())?;
+                unit
+            }
+            Opcode::CondBr => {
+                check_operand_count(opcode, 1, operands.len())?;
+                check_block_count(opcode, 2, blocks.len())?;
+                check_ty(self.i1().erase(), operands[0].ty())?;
+                unit
+            }
+            Opcode::Switch => {
+                let Some((&value, cases)) = operands.split_first() else {
+                    return Err(Error::OperandCount {
+                        opcode: opcode.name(),
+                        expected: 1,
+                        found: 0,
+                    });
+                };
+                check_block_count(opcode, operands.len(), blocks.len())?;
+
+                if value.ty().tag() != TypeTag::Integer {
+                    return Err(invalid_operands(value.ty()));
+                }
+
+                for case in cases {
+                    check_ty(value.ty(), case.ty())?;
+                }
+                check_switch_cases(cases)?;
+                unit
+            }
+            Opcode::Ret => {
+                if let [value] = operands {
+                    if !value.ty().is_first_class() {
+                        return Err(Error::NotFirstClass {
+                            ty: describe(value.ty()),
+                        });
+                    }
+                } else {
+                    check_operand_count(opcode, 0, operands.len())?;
+                }
+                unit
+            }
+            Opcode::Unreachable => {
+                check_operand_count(opcode, 0, operands.len())?;
+                unit
+            }
+        };
+
+        Ok(ty)
+    }
+}
+
+#[test]
+fn test_instructions() {
+    use crate::value::{IntBinOp, IntFlags, IntPredicate};
+
+    let target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+
+    Context::with(target, |ctx| {
+        let mut module = crate::Module::new(ctx, "test");
+        let i32 = ctx.i32().erase();
+        let function_ty = ctx.function_ty(i32, &[i32, i32]);
+        let max = module.add_function("max", function_ty);
+        assert!(max.is_declaration());
+
+        let [a, b] = [max.arguments()[0].erase(), max.arguments()[1].erase()];
+        assert!(!a.is_constant());
+        assert_eq!(max.arguments()[1].index(), 1);
+
+        let entry = ctx.append_basic_block(max, "entry");
+        let then = ctx.append_basic_block(max, "then");
+        let exit = ctx.append_basic_block(max, "exit");
+        assert!(entry.is_entry());
+        assert_eq!(max.blocks().collect::<Vec<_>>(), [entry, then, exit]);
+
+        let cmp = ctx.instruction(Opcode::ICmp(IntPredicate::Sgt), &[a, b], &[]);
+        assert_eq!(cmp.ty(), ctx.i1().erase());
+        entry.append(cmp);
+        let br = ctx.instruction(Opcode::CondBr, &[cmp.erase()], &[exit, then]);
+        entry.append(br);
+        assert_eq!(entry.terminator(), Some(br));
+        assert_eq!(br.successors().collect::<Vec<_>>(), [exit, then]);
+
+        let sum = ctx.instruction(
+            Opcode::IntBinary {
+                op: IntBinOp::Add,
+                flags: IntFlags::NONE,
+            },
+            &[a, b],
+            &[],
+        );
+        let jump = ctx.instruction(Opcode::Br, &[], &[exit]);
+        then.append(jump);
+        sum.insert_before(jump);
+        assert_eq!(then.instructions().collect::<Vec<_>>(), [sum, jump]);
+        assert_eq!(then.terminator(), Some(jump));
+
+        let phi = ctx.instruction(Opcode::Phi { ty: i32 }, &[a], &[entry]);
+        ctx.add_phi_incoming(phi, b, then);
+        assert_eq!(phi.incoming().collect::<Vec<_>>(), [(a, entry), (b, then)]);
+        assert!(ctx.try_add_phi_incoming(phi, cmp, then).is_err());
+        exit.append(phi);
+
+        let f32_ret = ctx.instruction(Opcode::Ret, &[ctx.const_f32(ctx.f32(), 0.0).erase()], &[]);
+        assert!(matches!(
+            exit.try_append(f32_ret),
+            Err(Error::TypeMismatch { .. })
+        ));
+        let ret = ctx.instruction(Opcode::Ret, &[phi.erase()], &[]);
+        exit.append(ret);
+        assert!(matches!(
+            exit.try_append(ret),
+            Err(Error::AlreadyInserted { .. })
+        ));
+        assert_eq!(ret.function(), Some(max));
+        assert!(!max.is_declaration());
+
+        // operands are checked against the opcode
+        let float = ctx.const_f32(ctx.f32(), 1.0).erase();
+        assert!(matches!(
+            ctx.try_instruction(Opcode::ICmp(IntPredicate::Eq), &[a, float], &[]),
+            Err(Error::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            ctx.try_instruction(Opcode::FNeg, &[a], &[]),
+            Err(Error::InvalidOperands { .. })
+        ));
+        assert!(matches!(
+            ctx.try_instruction(Opcode::Br, &[], &[]),
+            Err(Error::BlockCount { .. })
+        ));
+        let sext = Opcode::Cast {
+            op: CastOp::SExt,
+            ty: ctx.i64().erase(),
+        };
+        assert_eq!(ctx.instruction(sext, &[a], &[]).ty(), ctx.i64().erase());
+        let trunc = Opcode::Cast {
+            op: CastOp::Trunc,
+            ty: ctx.i64().erase(),
+        };
+        assert!(ctx.try_instruction(trunc, &[a], &[]).is_err());
+
+        let one = ctx.const_u32(ctx.i32(), 1).unwrap().erase();
+        let alloca = Opcode::Alloca {
+            allocated_ty: i32,
+            align: None,
+        };
+        let slot = ctx.instruction(alloca, &[one], &[]);
+        assert_eq!(slot.ty(), ctx.ptr_ty().erase());
+        let store = ctx.instruction(Opcode::Store { align: None }, &[a, slot.erase()], &[]);
+        assert_eq!(store.ty(), ctx.unit().erase());
+        let load = Opcode::Load {
+            ty: i32,
+            align: None,
+        };
+        assert_eq!(ctx.instruction(load, &[slot.erase()], &[]).ty(), i32);
+
+        let call = Opcode::Call { function_ty };
+        assert_eq!(ctx.instruction(call, &[max.erase(), a, b], &[]).ty(), i32);
+        assert!(matches!(
+            ctx.try_instruction(call, &[max.erase(), a], &[]),
+            Err(Error::OperandCount { .. })
+        ));
+
+        let switch = ctx.instruction(Opcode::Switch, &[a], &[exit]);
+        let one = ctx.const_u32(ctx.i32(), 1).unwrap();
+        ctx.add_switch_case(switch, one, then);
+        assert!(matches!(
+            ctx.try_add_switch_case(switch, one, entry),
+            Err(Error::DuplicateCase { .. })
+        ));
+        for i in 2..20 {
+            ctx.add_switch_case(switch, ctx.const_u32(ctx.i32(), i).unwrap(), entry);
+        }
+        assert_eq!(switch.num_operands(), 20);
+        assert_eq!(switch.blocks().len(), 20);
+        assert_eq!(switch.operand(1), one.erase());
+        assert_eq!(
+            switch.operand(19),
+            ctx.const_u32(ctx.i32(), 19).unwrap().erase()
+        );
+        assert_eq!(switch.blocks().last(), Some(entry));
+
+        // instructions and arguments aren't constants
+        let array_ty = ctx.array_ty(1, i32);
+        assert!(matches!(
+            ctx.try_const_array(array_ty, &[a]),
+            Err(Error::NotConstant { .. })
+        ));
+        assert!(ctx.try_const_add(a, b).is_err());
+    });
+}
+
+#[test]
+fn test_uses() {
+    use crate::value::{IntBinOp, IntFlags};
+
+    let target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+
+    Context::with(target, |ctx| {
+        let mut module = crate::Module::new(ctx, "test");
+        let i32 = ctx.i32().erase();
+        let function = module.add_function("f", ctx.function_ty(i32, &[i32, i32]));
+        let [a, b] = [function.arguments()[0], function.arguments()[1]];
+        let entry = ctx.append_basic_block(function, "entry");
+
+        let add = Opcode::IntBinary {
+            op: IntBinOp::Add,
+            flags: IntFlags::NONE,
+        };
+        let sum = ctx.instruction(add, &[a.erase(), a.erase()], &[]);
+        entry.append(sum);
+        let ret = ctx.instruction(Opcode::Ret, &[sum.erase()], &[]);
+        entry.append(ret);
+
+        assert_eq!(ctx.uses(a), [Use::new(sum, 0), Use::new(sum, 1)]);
+        assert_eq!(ctx.users(sum).collect::<Vec<_>>(), [ret]);
+        assert!(!ctx.has_uses(b));
+
+        ctx.set_operand(sum, 1, b);
+        assert_eq!(sum.operand(1), b.erase());
+        assert_eq!(ctx.users(b).collect::<Vec<_>>(), [sum]);
+        assert_eq!(ctx.uses(a).len(), 1);
+
+        // the replacement must have the same type
+        let wide = ctx.const_u64(ctx.i64(), 1).unwrap();
+        assert!(matches!(
+            ctx.try_replace_all_uses_with(a, wide),
+            Err(Error::TypeMismatch { .. })
+        ));
+
+        ctx.replace_all_uses_with(a, b);
+        assert!(!ctx.has_uses(a));
+        assert_eq!(sum.operands().collect::<Vec<_>>(), [b.erase(), b.erase()]);
+
+        // constants have uses too
+        let one = ctx.const_u32(ctx.i32(), 1).unwrap();
+        ctx.replace_all_uses_with(b, one);
+        assert_eq!(ctx.users(one).count(), 2);
+
+        // switch cases must stay distinct constants
+        let switch = ctx.instruction(Opcode::Switch, &[sum.erase()], &[entry]);
+        ctx.add_switch_case(switch, one, entry);
+        assert!(matches!(
+            ctx.try_set_operand(switch, 1, sum),
+            Err(Error::NotConstant { .. })
+        ));
+
+        // instructions can only be erased once they aren't used
+        assert!(matches!(
+            ctx.try_erase_instruction(sum),
+            Err(Error::HasUses { uses: 2, .. })
+        ));
+        ctx.erase_instruction(ret);
+        ctx.erase_instruction(switch);
+        ctx.erase_instruction(sum);
+        assert!(sum.is_erased());
+        assert_eq!(entry.first_instruction(), None);
+        assert!(!ctx.has_uses(one));
+        assert!(matches!(entry.try_append(sum), Err(Error::Erased { .. })));
+
+        // instructions can't use themselves, except for phis
+        let product = ctx.instruction(add, &[a.erase(), b.erase()], &[]);
+        entry.append(product);
+        let twice = ctx.instruction(add, &[product.erase(), product.erase()], &[]);
+        entry.append(twice);
+        assert!(matches!(
+            ctx.try_replace_all_uses_with(product, twice),
+            Err(Error::SelfReference { .. })
+        ));
+        let phi = ctx.instruction(Opcode::Phi { ty: i32 }, &[a.erase()], &[entry]);
+        let loop_block = ctx.append_basic_block(function, "loop");
+        loop_block.append(phi);
+        ctx.set_operand(phi, 0, phi);
+        assert_eq!(ctx.users(phi).collect::<Vec<_>>(), [phi]);
+
+        // or the values of other functions
+        let other = module.add_function("g", ctx.function_ty(i32, &[i32]));
+        let other_entry = ctx.append_basic_block(other, "entry");
+        let other_ret = ctx.instruction(Opcode::Ret, &[other.arguments()[0].erase()], &[]);
+        other_entry.append(other_ret);
+        assert!(matches!(
+            ctx.try_set_operand(other_ret, 0, product),
+            Err(Error::CrossFunction { .. })
+        ));
+        assert!(matches!(
+            ctx.try_replace_all_uses_with(other.arguments()[0], a),
+            Err(Error::CrossFunction { .. })
+        ));
+
+        // whether by inserting an instruction or adding to a phi
+        let stray = ctx.instruction(add, &[a.erase(), a.erase()], &[]);
+        assert!(matches!(
+            other_entry.try_append(stray),
+            Err(Error::CrossFunction { .. })
+        ));
+        assert_eq!(stray.parent(), None);
+        let stray_phi = ctx.instruction(Opcode::Phi { ty: i32 }, &[a.erase()], &[other_entry]);
+        assert!(matches!(
+            loop_block.try_append(stray_phi),
+            Err(Error::CrossFunction { .. })
+        ));
+        assert!(matches!(
+            ctx.try_add_phi_incoming(phi, other.arguments()[0], entry),
+            Err(Error::CrossFunction { .. })
+        ));
+        assert!(matches!(
+            ctx.try_add_phi_incoming(phi, a, other_entry),
+            Err(Error::CrossFunction { .. })
+        ));
+        assert_eq!(phi.num_operands(), 1);
+
+        // the users in constants and initializers can't be rewritten
+        let counter = module.add_global("counter", i32, types::AddressSpace::DEFAULT);
+        let alias = module.add_global("alias", ctx.ptr_ty(), types::AddressSpace::DEFAULT);
+        let spare = module.add_global("spare", i32, types::AddressSpace::DEFAULT);
+        assert!(!ctx.has_const_users(counter));
+        alias.set_initializer(Some(counter.erase()));
+        assert!(ctx.has_const_users(counter));
+        assert!(matches!(
+            ctx.try_replace_all_uses_with(counter, spare),
+            Err(Error::UsedByConstant { .. })
+        ));
+        alias.set_initializer(None);
+        assert!(!ctx.has_const_users(counter));
+
+        let offset = ctx.const_u64(ctx.i64(), 4).unwrap();
+        ctx.const_gep(i32, counter, &[offset.erase()]).unwrap();
+        assert!(ctx.has_const_users(counter));
+        assert!(matches!(
+            ctx.try_replace_all_uses_with(counter, spare),
+            Err(Error::UsedByConstant { .. })
+        ));
+    });
+}
+
+#[test]
+fn test_invalid_alloca_address_space() {
+    let mut target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+    target.layout.alloca_address_space = 1 << 24;
+
+    Context::with(target, |ctx| {
+        let i32 = ctx.i32().erase();
+        let one = ctx.const_u32(ctx.i32(), 1).unwrap().erase();
+        let alloca = Opcode::Alloca {
+            allocated_ty: i32,
+            align: None,
+        };
+        assert!(matches!(
+            ctx.try_instruction(alloca, &[one], &[]),
+            Err(Error::InvalidAddressSpace { .. })
+        ));
+    });
+}
@@ -1,14 +1,14 @@
-use std::num::NonZeroU16;
+use std::{cell::RefCell, num::NonZeroU16};
 
+use hashbrown::HashMap;
 use init::Ctor;
 
 use crate::{
+    error::{describe, unwrap_or_panic},
     types::{self, TypeCache},
-    AllocContext,
+    AllocContext, Error, Target,
 };
 
-use super::Target;
-
 pub(crate) struct TypeContextInfo<'ctx> {
     unit: types::UnitTy<'ctx>,
 
@@ -28,10 +28,14 @@ pub(crate) struct TypeContextInfo<'ctx> {
     ptr_ty: types::PointerTy<'ctx>,
 
     int_cache: TypeCache<'ctx, types::IntegerInfo>,
+    float_cache: TypeCache<'ctx, types::FloatInfo>,
     ptr_cache: TypeCache<'ctx, types::PointerInfo>,
     function_cache: TypeCache<'ctx, types::FunctionInfo<'ctx>>,
     struct_cache: TypeCache<'ctx, types::StructInfo<'ctx>>,
     array_cache: TypeCache<'ctx, types::ArrayInfo<'ctx>>,
+    vector_cache: TypeCache<'ctx, types::VectorInfo<'ctx>>,
+
+    identified_structs: RefCell<HashMap<istr::IStr, types::StructTy<'ctx>>>,
 }
 
 #[repr(transparent)]
@@ -70,12 +74,12 @@ impl<'ctx> Ctor<TypeContextBuilder<'ctx, '_>> for TypeContextInfo<'ctx> {
         let i64 = types::IntegerTy::create(alloc, nz!(64));
         let i128 = types::IntegerTy::create(alloc, nz!(128));
 
-        let get = |bits: super::PtrBits| match bits {
-            super::PtrBits::_8 => i8,
-            super::PtrBits::_16 => i16,
-            super::PtrBits::_32 => i32,
-            super::PtrBits::_64 => i64,
-            super::PtrBits::_128 => i128,
+        let get = |bits: crate::PtrBits| match bits {
+            crate::PtrBits::_8 => i8,
+            crate::PtrBits::_16 => i16,
+            crate::PtrBits::_32 => i32,
+            crate::PtrBits::_64 => i64,
+            crate::PtrBits::_128 => i128,
         };
 
         uninit.write(TypeContextInfo {
@@ -97,10 +101,14 @@ impl<'ctx> Ctor<TypeContextBuilder<'ctx, '_>> for TypeContextInfo<'ctx> {
             ptr_ty: types::PointerTy::create(alloc, types::AddressSpace::DEFAULT),
 
             int_cache: TypeCache::new(),
+            float_cache: TypeCache::new(),
             ptr_cache: TypeCache::new(),
             function_cache: TypeCache::new(),
             struct_cache: TypeCache::new(),
             array_cache: TypeCache::new(),
+            vector_cache: TypeCache::new(),
+
+            identified_structs: RefCell::new(HashMap::new()),
         })
     }
 }
@@ -151,6 +159,46 @@ impl<'ctx> TypeContext<'ctx> {
         self.info.int_cache.get_or_create(alloc, bits)
     }
 
+    #[inline]
+    pub fn try_int(
+        self,
+        alloc: AllocContext<'ctx>,
+        bits: u16,
+    ) -> Result<types::IntegerTy<'ctx>, Error> {
+        let bits = NonZeroU16::new(bits).ok_or(Error::ZeroWidthInteger)?;
+        Ok(self.int(alloc, bits))
+    }
+
+    #[inline]
+    pub fn float(self, alloc: AllocContext<'ctx>, kind: types::FloatKind) -> types::FloatTy<'ctx> {
+        match kind {
+            types::FloatKind::Ieee16Bit => self.info.f16,
+            types::FloatKind::Ieee32Bit => self.info.f32,
+            types::FloatKind::Ieee64Bit => self.info.f64,
+            _ => self.info.float_cache.get_or_create(alloc, kind),
+        }
+    }
+
+    #[inline]
+    pub fn f128(self, alloc: AllocContext<'ctx>) -> types::FloatTy<'ctx> {
+        self.float(alloc, types::FloatKind::Ieee128Bit)
+    }
+
+    #[inline]
+    pub fn bf16(self, alloc: AllocContext<'ctx>) -> types::FloatTy<'ctx> {
+        self.float(alloc, types::FloatKind::BFloat16)
+    }
+
+    #[inline]
+    pub fn x86_fp80(self, alloc: AllocContext<'ctx>) -> types::FloatTy<'ctx> {
+        self.float(alloc, types::FloatKind::X86Fp80)
+    }
+
+    #[inline]
+    pub fn ppc_fp128(self, alloc: AllocContext<'ctx>) -> types::FloatTy<'ctx> {
+        self.float(alloc, types::FloatKind::PpcFp128)
+    }
+
     #[inline]
     pub fn ptr_at(
         self,
@@ -164,6 +212,15 @@ impl<'ctx> TypeContext<'ctx> {
         }
     }
 
+    #[inline]
+    pub fn try_ptr_at(
+        self,
+        alloc: AllocContext<'ctx>,
+        address_space: u32,
+    ) -> Result<types::PointerTy<'ctx>, Error> {
+        Ok(self.ptr_at(alloc, types::AddressSpace::try_new(address_space)?))
+    }
+
     pub fn function(
         self,
         alloc: AllocContext<'ctx>,
@@ -190,6 +247,79 @@ impl<'ctx> TypeContext<'ctx> {
             .get_or_create(alloc, types::ArrayInit { len, item_ty })
     }
 
+    /// # Panics
+    ///
+    /// If `len` is zero, or `item_ty` isn't an integer, float or pointer type
+    #[track_caller]
+    pub fn vector(
+        self,
+        alloc: AllocContext<'ctx>,
+        len: u32,
+        item_ty: types::Type<'ctx>,
+    ) -> types::VectorTy<'ctx> {
+        unwrap_or_panic(self.try_vector(alloc, len, item_ty))
+    }
+
+    pub fn try_vector(
+        self,
+        alloc: AllocContext<'ctx>,
+        len: u32,
+        item_ty: types::Type<'ctx>,
+    ) -> Result<types::VectorTy<'ctx>, Error> {
+        self.vector_ty(alloc, len, item_ty, false)
+    }
+
+    /// A vector with `vscale * min_len` lanes, where `vscale` is only known at runtime
+    ///
+    /// # Panics
+    ///
+    /// If `min_len` is zero, or `item_ty` isn't an integer, float or pointer type
+    #[track_caller]
+    pub fn scalable_vector(
+        self,
+        alloc: AllocContext<'ctx>,
+        min_len: u32,
+        item_ty: types::Type<'ctx>,
+    ) -> types::VectorTy<'ctx> {
+        unwrap_or_panic(self.try_scalable_vector(alloc, min_len, item_ty))
+    }
+
+    pub fn try_scalable_vector(
+        self,
+        alloc: AllocContext<'ctx>,
+        min_len: u32,
+        item_ty: types::Type<'ctx>,
+    ) -> Result<types::VectorTy<'ctx>, Error> {
+        self.vector_ty(alloc, min_len, item_ty, true)
+    }
+
+    fn vector_ty(
+        self,
+        alloc: AllocContext<'ctx>,
+        len: u32,
+        item_ty: types::Type<'ctx>,
+        scalable: bool,
+    ) -> Result<types::VectorTy<'ctx>, Error> {
+        if len == 0 {
+            return Err(Error::ZeroLengthVector);
+        }
+
+        if !types::VectorTy::is_valid_item_ty(item_ty) {
+            return Err(Error::InvalidVectorItem {
+                ty: describe(item_ty),
+            });
+        }
+
+        Ok(self.info.vector_cache.get_or_create(
+            alloc,
+            types::VectorInit {
+                item_ty,
+                len,
+                scalable,
+            },
+        ))
+    }
+
     pub fn struct_ty(
         self,
         alloc: AllocContext<'ctx>,
@@ -204,8 +334,44 @@ impl<'ctx> TypeContext<'ctx> {
                 types::StructInit {
                     name,
                     fields: field_tys,
+                    identified: false,
                 },
             ),
         )
     }
+
+    /// Create a new opaque identified struct, which is distinct from all other structs
+    ///
+    /// If the name is already taken by another identified struct, a unique
+    /// name is created by appending a suffix (`Node` then `Node.0`, `Node.1`, ...).
+    /// The body can be set later with [`StructTy::set_body`](types::StructTy::set_body)
+    pub fn identified_struct(
+        self,
+        alloc: AllocContext<'ctx>,
+        name: Option<istr::IStr>,
+    ) -> types::StructTy<'ctx> {
+        let Some(name) = name else {
+            return types::StructTy::create_identified(alloc, None);
+        };
+
+        let mut structs = self.info.identified_structs.borrow_mut();
+
+        let name = if structs.contains_key(&name) {
+            (0..)
+                .map(|i| istr::IStr::new(&format!("{name}.{i}")))
+                .find(|name| !structs.contains_key(name))
+                .unwrap()
+        } else {
+            name
+        };
+
+        let ty = types::StructTy::create_identified(alloc, Some(name));
+        structs.insert(name, ty);
+        ty
+    }
+
+    /// Find the identified struct with the given name
+    pub fn get_identified_struct(self, name: istr::IStr) -> Option<types::StructTy<'ctx>> {
+        self.info.identified_structs.borrow().get(&name).copied()
+    }
 }
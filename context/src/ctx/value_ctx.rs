@@ -3,15 +3,42 @@ use std::{
     hash::{Hash, Hasher},
 };
 
-use hashbrown::{raw, HashSet};
+use hashbrown::{raw, HashMap, HashSet};
 use init::Ctor;
+use rug::float::Round;
 
-use crate::{types, value, AllocContext};
+mod const_expr;
+mod fold;
+mod uses;
+
+pub(crate) use const_expr::{bitcast_width, check_gep};
+
+use crate::{
+    error::{check_constant, check_ty, describe},
+    types::{self, FloatKind},
+    value::{self, float_bits},
+    AllocContext, Error,
+};
 
 pub(crate) struct ValueContextInfo<'ctx> {
     int_one: rug::Integer,
     int_table: RefCell<HashSet<rug::Integer>>,
     const_integers: RefCell<raw::RawTable<value::ConstInt<'ctx>>>,
+    // the values of all float constants, which are borrowed by `const_floats`
+    float_values: RefCell<Vec<rug::Float>>,
+    const_floats: RefCell<raw::RawTable<value::ConstFloat<'ctx>>>,
+    const_aggregates: RefCell<raw::RawTable<value::Value<'ctx>>>,
+    const_aggr_zeros: RefCell<HashMap<types::Type<'ctx>, value::ConstAggrZero<'ctx>>>,
+    const_nulls: RefCell<HashMap<types::PointerTy<'ctx>, value::ConstNull<'ctx>>>,
+    undefs: RefCell<HashMap<types::Type<'ctx>, value::Undef<'ctx>>>,
+    poisons: RefCell<HashMap<types::Type<'ctx>, value::Poison<'ctx>>>,
+    const_exprs: RefCell<raw::RawTable<value::ConstExpr<'ctx>>>,
+    // the instruction operands each value is used as
+    uses: RefCell<HashMap<value::Value<'ctx>, Vec<value::Use<'ctx>>>>,
+    // the items and operands of every aggregate constant and constant expression
+    const_operands: RefCell<HashSet<value::Value<'ctx>>>,
+    // every global, to find the values used by initializers
+    globals: RefCell<Vec<value::GlobalVariable<'ctx>>>,
 }
 
 #[repr(transparent)]
@@ -26,12 +53,31 @@ fn borrow(x: &rug::Integer) -> rug::integer::BorrowInteger<'_> {
     unsafe { rug::integer::BorrowInteger::from_raw(*raw) }
 }
 
+macro_rules! intern_ints {
+    ($($name:ident: $ty:ty)*) => {$(
+        pub fn $name(self, x: $ty) -> &'ctx rug::Integer {
+            self.intern_integer_value(rug::Integer::from(x))
+        }
+    )*};
+}
+
 fn hash_one<T: Hash>(value: T) -> u64 {
     let mut hasher = rustc_hash::FxHasher::default();
     value.hash(&mut hasher);
     hasher.finish()
 }
 
+fn check_len(expected: u64, found: usize) -> Result<(), Error> {
+    if expected == found as u64 {
+        Ok(())
+    } else {
+        Err(Error::LengthMismatch {
+            expected,
+            found: found as u64,
+        })
+    }
+}
+
 impl<'ctx> ValueContext<'ctx> {
     pub fn zero_value(self) -> &'ctx rug::Integer {
         static ZERO: rug::Integer = rug::Integer::ZERO;
@@ -42,26 +88,57 @@ impl<'ctx> ValueContext<'ctx> {
         &self.info.int_one
     }
 
-    pub fn intern_u32(self, x: u32) -> &'ctx rug::Integer {
-        match x {
-            0 => self.zero_value(),
-            1 => self.one_value(),
-            x => {
-                let x = rug::Integer::from_f64(x.into()).unwrap();
-                self.intern_integer_value(x)
-            }
-        }
+    intern_ints! {
+        intern_u32: u32
+        intern_i32: i32
+        intern_u64: u64
+        intern_i64: i64
+        intern_u128: u128
+        intern_i128: i128
+        intern_usize: usize
     }
 
-    pub fn intern_i32(self, x: i32) -> &'ctx rug::Integer {
-        match x {
-            0 => self.zero_value(),
-            1 => self.one_value(),
-            x => {
-                let x = rug::Integer::from_f64(x.into()).unwrap();
-                self.intern_integer_value(x)
-            }
+    /// Intern an integer written in decimal, or in hexadecimal, octal or
+    /// binary with a `0x`, `0o` or `0b` prefix, with an optional sign before
+    /// the prefix and `_` separators between digits
+    pub fn intern_str(self, text: &str) -> Option<&'ctx rug::Integer> {
+        self.try_intern_str(text).ok()
+    }
+
+    /// Intern an integer, see [`ValueContext::intern_str`] for the accepted syntax
+    pub fn try_intern_str(self, text: &str) -> Result<&'ctx rug::Integer, Error> {
+        let invalid = || Error::InvalidLiteral {
+            text: text.to_owned(),
+        };
+
+        let (negative, digits) = match text.as_bytes().first().ok_or_else(invalid)? {
+            b'-' => (true, &text[1..]),
+            b'+' => (false, &text[1..]),
+            _ => (false, text),
+        };
+
+        let (radix, digits) = match digits.get(..2) {
+            Some("0x" | "0X") => (16, &digits[2..]),
+            Some("0o" | "0O") => (8, &digits[2..]),
+            Some("0b" | "0B") => (2, &digits[2..]),
+            _ => (10, digits),
+        };
+
+        // rug skips whitespace and any underscores, so only allow single
+        // separators between digits
+        let is_digits =
+            |part: &str| !part.is_empty() && part.bytes().all(|byte| byte.is_ascii_alphanumeric());
+        if !digits.split('_').all(is_digits) {
+            return Err(invalid());
+        }
+
+        let parsed = rug::Integer::parse_radix(digits, radix).map_err(|_| invalid())?;
+        let mut value = rug::Integer::from(parsed);
+        if negative {
+            value = -value;
         }
+
+        Ok(self.intern_integer_value(value))
     }
 
     pub fn intern_integer_value(self, x: rug::Integer) -> &'ctx rug::Integer {
@@ -77,51 +154,392 @@ impl<'ctx> ValueContext<'ctx> {
         }
     }
 
-    pub(crate) fn const_int(
+    /// An integer with the given value, which may be given either as an unsigned
+    /// or a two's complement signed integer (so `-1` and `255` are the same `i8`)
+    ///
+    /// Fails if `value` isn't in `-2^(bits-1)..2^bits`
+    pub(crate) fn try_const_int(
         self,
         alloc: AllocContext<'ctx>,
         ty: types::IntegerTy<'ctx>,
         value: &'ctx rug::Integer,
-        signed: bool,
-    ) -> Option<value::ConstInt<'ctx>> {
-        if !signed && value < self.zero_value() {
-            return None;
-        }
+    ) -> Result<value::ConstInt<'ctx>, Error> {
+        let bits = u32::from(ty.bits().get());
 
-        let bits = if signed {
-            value.signed_bits()
+        let fits = if *value >= 0 {
+            value.significant_bits() <= bits
         } else {
-            value.significant_bits()
+            value.signed_bits() <= bits
         };
 
-        if bits > u32::from(ty.bits().get()) {
-            return None;
+        if !fits {
+            return Err(Error::IntOutOfRange {
+                value: value.to_string(),
+                bits,
+            });
         }
 
+        if *value >= 0 {
+            Ok(self.const_int_bit_pattern(alloc, ty, value))
+        } else {
+            Ok(self.const_int_wrapping(alloc, ty, value))
+        }
+    }
+
+    /// An integer with the bit pattern of `value` truncated to the width of `ty`
+    pub(crate) fn const_int_wrapping(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::IntegerTy<'ctx>,
+        value: &rug::Integer,
+    ) -> value::ConstInt<'ctx> {
+        let bits = u32::from(ty.bits().get());
+        let pattern = self.intern_integer_value(rug::Integer::from(value.keep_bits_ref(bits)));
+        self.const_int_bit_pattern(alloc, ty, pattern)
+    }
+
+    /// `pattern` must be in `0..2^bits`
+    fn const_int_bit_pattern(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::IntegerTy<'ctx>,
+        pattern: &'ctx rug::Integer,
+    ) -> value::ConstInt<'ctx> {
         let table = &mut *self.info.const_integers.borrow_mut();
 
-        let hash = hash_one((ty, signed, value));
+        let hash = hash_one((ty.erase(), pattern));
 
         if let Some(&value) = table.get(hash, |x| {
-            x.ty() == ty.erase() && x.is_signed() == signed && *x.value() == *value
+            x.ty() == ty.erase() && *x.as_unsigned() == *pattern
         }) {
-            return Some(value);
+            return value;
+        }
+
+        let value = value::ConstInt::new(alloc, ty, borrow(pattern));
+
+        table.insert(hash, value, |x| hash_one((x.ty(), &*x.as_unsigned())));
+
+        value
+    }
+
+    /// The float with the given bit pattern, ignoring bits outside of the storage size of the type
+    pub(crate) fn const_float_from_bits(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::FloatTy<'ctx>,
+        bits: u128,
+    ) -> value::ConstFloat<'ctx> {
+        let bits = bits & float_bits::storage_mask(ty.kind());
+        let table = &mut *self.info.const_floats.borrow_mut();
+
+        let hash = hash_one((ty.erase(), bits));
+
+        if let Some(&value) = table.get(hash, |x| x.ty() == ty.erase() && x.bits() == bits) {
+            return value;
+        }
+
+        let float = float_bits::from_bits(ty.kind(), bits);
+        let value = {
+            let mut float_values = self.info.float_values.borrow_mut();
+            float_values.push(float);
+            // SAFETY: the float is never mutated or dropped until the context is dropped,
+            // and moving a `rug::Float` doesn't move it's limbs
+            unsafe { rug::float::BorrowFloat::from_raw(*float_values.last().unwrap().as_raw()) }
+        };
+
+        let value = value::ConstFloat::new(alloc, ty, bits, value);
+
+        table.insert(hash, value, |x| hash_one((x.ty(), x.bits())));
+
+        value
+    }
+
+    /// The float nearest to `value` (rounding ties to even)
+    ///
+    /// A NaN becomes the default quiet NaN
+    pub(crate) fn const_float(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::FloatTy<'ctx>,
+        value: &rug::Float,
+    ) -> value::ConstFloat<'ctx> {
+        let kind = ty.kind();
+
+        if value.is_nan() {
+            return self.const_nan(alloc, ty, value.is_sign_negative(), 0);
+        }
+
+        let value = float_bits::round_to_kind(
+            kind,
+            rug::Float::with_val_round(kind.precision(), value, Round::Nearest),
+        );
+
+        self.const_float_from_bits(alloc, ty, float_bits::to_bits(kind, &value))
+    }
+
+    /// A NaN with the given payload (the mantissa bits including the quiet bit),
+    /// a payload of zero is replaced by the default quiet NaN
+    pub(crate) fn const_nan(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::FloatTy<'ctx>,
+        negative: bool,
+        payload: u128,
+    ) -> value::ConstFloat<'ctx> {
+        let bits = float_bits::nan_bits(ty.kind(), negative, payload);
+        self.const_float_from_bits(alloc, ty, bits)
+    }
+
+    /// The float nearest to `value`, NaN payloads are kept
+    pub(crate) fn const_f64(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::FloatTy<'ctx>,
+        value: f64,
+    ) -> value::ConstFloat<'ctx> {
+        if value.is_nan() {
+            let payload = u128::from(value.to_bits()) & ((1 << 52) - 1);
+            let payload = float_bits::convert_nan_payload(FloatKind::Ieee64Bit, ty.kind(), payload);
+            return self.const_nan(alloc, ty, value.is_sign_negative(), payload);
+        }
+
+        self.const_float(alloc, ty, &rug::Float::with_val(53, value))
+    }
+
+    /// The float nearest to `value`, NaN payloads are kept
+    pub(crate) fn const_f32(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::FloatTy<'ctx>,
+        value: f32,
+    ) -> value::ConstFloat<'ctx> {
+        if value.is_nan() {
+            let payload = u128::from(value.to_bits()) & ((1 << 23) - 1);
+            let payload = float_bits::convert_nan_payload(FloatKind::Ieee32Bit, ty.kind(), payload);
+            return self.const_nan(alloc, ty, value.is_sign_negative(), payload);
+        }
+
+        self.const_float(alloc, ty, &rug::Float::with_val(24, value))
+    }
+
+    /// Parse a decimal (`-1.5e3`, `inf`, `nan`) or hexadecimal (`0x1.8p3`) float,
+    /// rounding it to the nearest value of the type
+    pub(crate) fn try_const_float_from_str(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::FloatTy<'ctx>,
+        text: &str,
+    ) -> Result<value::ConstFloat<'ctx>, Error> {
+        let kind = ty.kind();
+        let unsigned = text.trim_start_matches(['+', '-']);
+        let invalid = || Error::InvalidLiteral {
+            text: text.to_owned(),
+        };
+
+        if unsigned.starts_with("0x") || unsigned.starts_with("0X") {
+            let value = float_bits::parse_hex(kind, text).ok_or_else(invalid)?;
+            return Ok(self.const_float_from_bits(alloc, ty, float_bits::to_bits(kind, &value)));
         }
 
-        let value = value::ConstInt::new(alloc, ty, borrow(value), signed);
+        let parsed = rug::Float::parse(text).map_err(|_| invalid())?;
+        let value = float_bits::round_to_kind(
+            kind,
+            rug::Float::with_val_round(kind.precision(), parsed, Round::Nearest),
+        );
+
+        if value.is_nan() {
+            return Ok(self.const_nan(alloc, ty, text.starts_with('-'), 0));
+        }
 
-        table.insert(hash, value, |x| hash_one((x.ty(), signed, &*x.value())));
+        Ok(self.const_float_from_bits(alloc, ty, float_bits::to_bits(kind, &value)))
+    }
+
+    /// A constant array, fails if the items don't match the length and item type of `ty`
+    pub(crate) fn try_const_array(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::ArrayTy<'ctx>,
+        items: &[value::Value<'ctx>],
+    ) -> Result<value::Value<'ctx>, Error> {
+        check_len(ty.len(), items.len())?;
+        for &item in items {
+            check_ty(ty.item_ty(), item.ty())?;
+            check_constant(item)?;
+        }
+
+        Ok(self.const_aggregate(alloc, ty.erase(), items, |alloc| {
+            value::ConstArray::new(alloc, ty, items).erase()
+        }))
+    }
+
+    /// A constant struct, fails if `ty` is opaque or the fields don't match
+    /// the field types of `ty`
+    pub(crate) fn try_const_struct(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::StructTy<'ctx>,
+        fields: &[value::Value<'ctx>],
+    ) -> Result<value::Value<'ctx>, Error> {
+        if ty.is_opaque() {
+            return Err(Error::OpaqueStruct { ty: describe(ty) });
+        }
+
+        let field_tys = ty.field_tys();
+        check_len(field_tys.len() as u64, fields.len())?;
+        for (&field, &field_ty) in fields.iter().zip(field_tys) {
+            check_ty(field_ty, field.ty())?;
+            check_constant(field)?;
+        }
+
+        Ok(self.const_aggregate(alloc, ty.erase(), fields, |alloc| {
+            value::ConstStruct::new(alloc, ty, fields).erase()
+        }))
+    }
+
+    /// A constant vector, fails if `ty` is scalable or the items don't match
+    /// the length and item type of `ty`
+    pub(crate) fn try_const_vector(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::VectorTy<'ctx>,
+        items: &[value::Value<'ctx>],
+    ) -> Result<value::Value<'ctx>, Error> {
+        if ty.is_scalable() {
+            return Err(Error::ScalableVector { ty: describe(ty) });
+        }
+
+        check_len(ty.len().into(), items.len())?;
+        for &item in items {
+            check_ty(ty.item_ty(), item.ty())?;
+            check_constant(item)?;
+        }
+
+        Ok(self.const_aggregate(alloc, ty.erase(), items, |alloc| {
+            value::ConstVector::new(alloc, ty, items).erase()
+        }))
+    }
+
+    fn const_aggregate(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::Type<'ctx>,
+        items: &[value::Value<'ctx>],
+        create: impl FnOnce(AllocContext<'ctx>) -> value::Value<'ctx>,
+    ) -> value::Value<'ctx> {
+        if items.iter().all(|item| item.is_zero()) {
+            return self.try_const_aggr_zero(alloc, ty).unwrap().erase();
+        }
+
+        let table = &mut *self.info.const_aggregates.borrow_mut();
+
+        let hash = hash_one((ty, items));
+
+        if let Some(&value) =
+            table.get(hash, |x| x.ty() == ty && x.aggregate_items() == Some(items))
+        {
+            return value;
+        }
+
+        let value = create(alloc);
+        self.add_const_operands(items);
+
+        table.insert(hash, value, |x| {
+            hash_one((x.ty(), x.aggregate_items().unwrap_or_default()))
+        });
+
+        value
+    }
+
+    /// The all zero value of an array, struct or vector type,
+    /// fails if `ty` isn't one of those (or is an opaque struct)
+    pub(crate) fn try_const_aggr_zero(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::Type<'ctx>,
+    ) -> Result<value::ConstAggrZero<'ctx>, Error> {
+        if !value::ConstAggrZero::is_valid_ty(ty) {
+            return Err(match ty.try_cast::<types::StructInfo>() {
+                Some(ty) => Error::OpaqueStruct { ty: describe(ty) },
+                None => Error::NotAnAggregate { ty: describe(ty) },
+            });
+        }
+
+        Ok(*self
+            .info
+            .const_aggr_zeros
+            .borrow_mut()
+            .entry(ty)
+            .or_insert_with(|| value::ConstAggrZero::new(alloc, ty)))
+    }
+
+    /// The null pointer in the address space of `ty`
+    pub(crate) fn const_null(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::PointerTy<'ctx>,
+    ) -> value::ConstNull<'ctx> {
+        *self
+            .info
+            .const_nulls
+            .borrow_mut()
+            .entry(ty)
+            .or_insert_with(|| value::ConstNull::new(alloc, ty))
+    }
+
+    /// An undefined value, fails if `ty` isn't a first class type
+    pub(crate) fn try_undef(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::Type<'ctx>,
+    ) -> Result<value::Undef<'ctx>, Error> {
+        if !ty.is_first_class() {
+            return Err(Error::NotFirstClass { ty: describe(ty) });
+        }
+
+        Ok(*self
+            .info
+            .undefs
+            .borrow_mut()
+            .entry(ty)
+            .or_insert_with(|| value::Undef::new(alloc, ty)))
+    }
+
+    /// A poison value, fails if `ty` isn't a first class type
+    pub(crate) fn try_poison(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: types::Type<'ctx>,
+    ) -> Result<value::Poison<'ctx>, Error> {
+        if !ty.is_first_class() {
+            return Err(Error::NotFirstClass { ty: describe(ty) });
+        }
 
-        Some(value)
+        Ok(*self
+            .info
+            .poisons
+            .borrow_mut()
+            .entry(ty)
+            .or_insert_with(|| value::Poison::new(alloc, ty)))
     }
 }
 
 impl<'ctx> Ctor for ValueContextInfo<'ctx> {
     fn init(uninit: init::Uninit<'_, Self>, (): ()) -> init::Init<'_, Self> {
         uninit.write(Self {
-            int_one: rug::Integer::from_f32(1.0).expect("One is a value integer"),
+            int_one: rug::Integer::from(1),
             int_table: RefCell::new(HashSet::new()),
             const_integers: RefCell::new(raw::RawTable::new()),
+            float_values: RefCell::new(Vec::new()),
+            const_floats: RefCell::new(raw::RawTable::new()),
+            const_aggregates: RefCell::new(raw::RawTable::new()),
+            const_aggr_zeros: RefCell::new(HashMap::new()),
+            const_nulls: RefCell::new(HashMap::new()),
+            undefs: RefCell::new(HashMap::new()),
+            poisons: RefCell::new(HashMap::new()),
+            const_exprs: RefCell::new(raw::RawTable::new()),
+            uses: RefCell::new(HashMap::new()),
+            const_operands: RefCell::new(HashSet::new()),
+            globals: RefCell::new(Vec::new()),
         })
     }
 }
@@ -0,0 +1,346 @@
+use crate::{
+    error::{check_constant, check_ty, describe},
+    types::{self, Type, UnpackedType},
+    value::{
+        self,
+        fold::{IntBinOp, IntFlags},
+        ConstOpcode, UnpackedValue, Value,
+    },
+    AllocContext, Error,
+};
+
+use super::{hash_one, ValueContext};
+
+/// The number of bits in a type which can be bitcast, pointers can only
+/// be bitcast to themselves so they aren't included
+pub(crate) fn bitcast_width(ty: Type<'_>) -> Option<types::TypeSize> {
+    match ty.unpack() {
+        UnpackedType::Integer(ty) => Some(types::TypeSize::fixed(ty.bits().get().into())),
+        UnpackedType::Float(ty) => Some(types::TypeSize::fixed(ty.storage_bits().into())),
+        UnpackedType::Vector(ty) => {
+            let item = bitcast_width(ty.item_ty())?.unwrap_fixed();
+            Some(types::TypeSize::new(
+                item * u64::from(ty.len()),
+                ty.is_scalable(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+fn is_valid_cast(opcode: ConstOpcode<'_>, from: Type<'_>, to: Type<'_>) -> bool {
+    let int_bits = |ty: Type<'_>| {
+        ty.try_cast::<types::IntegerInfo>()
+            .map(|ty| ty.bits().get())
+    };
+
+    match opcode {
+        ConstOpcode::Trunc => matches!((int_bits(from), int_bits(to)), (Some(a), Some(b)) if a > b),
+        ConstOpcode::ZExt | ConstOpcode::SExt => {
+            matches!((int_bits(from), int_bits(to)), (Some(a), Some(b)) if a < b)
+        }
+        ConstOpcode::PtrToInt => {
+            from.tag() == types::TypeTag::Pointer && to.tag() == types::TypeTag::Integer
+        }
+        ConstOpcode::IntToPtr => {
+            from.tag() == types::TypeTag::Integer && to.tag() == types::TypeTag::Pointer
+        }
+        ConstOpcode::BitCast => {
+            from == to
+                || matches!((bitcast_width(from), bitcast_width(to)), (Some(a), Some(b)) if a == b)
+        }
+        ConstOpcode::Add | ConstOpcode::Sub | ConstOpcode::GetElementPtr { .. } => false,
+    }
+}
+
+/// Check that `base` is a pointer and `indices` select an element of `source_ty`
+pub(crate) fn check_gep<'ctx>(
+    source_ty: Type<'ctx>,
+    base: Value<'ctx>,
+    indices: &[Value<'ctx>],
+) -> Result<(), Error> {
+    let invalid_operand = |value: Value<'ctx>| Error::InvalidOperands {
+        opcode: "getelementptr",
+        ty: describe(value.ty()),
+    };
+
+    if base.ty().tag() != types::TypeTag::Pointer {
+        return Err(invalid_operand(base));
+    }
+
+    if !source_ty.is_first_class() {
+        return Err(Error::NotFirstClass {
+            ty: describe(source_ty),
+        });
+    }
+
+    if let Some(&index) = indices
+        .iter()
+        .find(|index| index.ty().tag() != types::TypeTag::Integer)
+    {
+        return Err(invalid_operand(index));
+    }
+
+    let mut ty = source_ty;
+    for &index in indices.iter().skip(1) {
+        let invalid_index = || Error::InvalidIndex {
+            ty: describe(ty),
+            index: describe(index),
+        };
+
+        ty = match ty.unpack() {
+            UnpackedType::Array(ty) => ty.item_ty(),
+            UnpackedType::Vector(ty) => ty.item_ty(),
+            UnpackedType::Struct(struct_ty) => {
+                let UnpackedValue::ConstInt(field) = index.unpack() else {
+                    return Err(invalid_index());
+                };
+                field
+                    .as_unsigned()
+                    .to_usize()
+                    .and_then(|field| struct_ty.field_tys().get(field).copied())
+                    .ok_or_else(invalid_index)?
+            }
+            _ => return Err(invalid_index()),
+        };
+    }
+
+    Ok(())
+}
+
+impl<'ctx> ValueContext<'ctx> {
+    /// Cast a constant to `ty`, folding it if possible
+    ///
+    /// Fails if `opcode` isn't a cast, or the cast isn't valid between
+    /// the type of `value` and `ty`
+    pub(crate) fn try_const_cast(
+        self,
+        alloc: AllocContext<'ctx>,
+        opcode: ConstOpcode<'ctx>,
+        value: Value<'ctx>,
+        ty: Type<'ctx>,
+    ) -> Result<Value<'ctx>, Error> {
+        check_constant(value)?;
+
+        if !is_valid_cast(opcode, value.ty(), ty) {
+            return Err(Error::InvalidCast {
+                opcode: opcode.name(),
+                from: describe(value.ty()),
+                to: describe(ty),
+            });
+        }
+
+        if let Some(folded) = self.fold_cast(alloc, opcode, value, ty) {
+            return Ok(folded);
+        }
+
+        Ok(self.const_expr(alloc, ty, opcode, &[value]).erase())
+    }
+
+    /// Add or subtract two integer constants of the same type, folding it if possible
+    pub(crate) fn try_const_binary(
+        self,
+        alloc: AllocContext<'ctx>,
+        opcode: ConstOpcode<'ctx>,
+        lhs: Value<'ctx>,
+        rhs: Value<'ctx>,
+    ) -> Result<Value<'ctx>, Error> {
+        if !matches!(opcode, ConstOpcode::Add | ConstOpcode::Sub)
+            || lhs.ty().tag() != types::TypeTag::Integer
+        {
+            return Err(Error::InvalidOperands {
+                opcode: opcode.name(),
+                ty: describe(lhs.ty()),
+            });
+        }
+
+        check_ty(lhs.ty(), rhs.ty())?;
+        check_constant(lhs)?;
+        check_constant(rhs)?;
+
+        if let (UnpackedValue::ConstInt(a), UnpackedValue::ConstInt(b)) =
+            (lhs.unpack(), rhs.unpack())
+        {
+            let op = match opcode {
+                ConstOpcode::Add => IntBinOp::Add,
+                _ => IntBinOp::Sub,
+            };
+            return self.try_const_int_binop(alloc, op, IntFlags::NONE, a, b);
+        }
+
+        Ok(self
+            .const_expr(alloc, lhs.ty(), opcode, &[lhs, rhs])
+            .erase())
+    }
+
+    /// The address of an element of `source_ty` starting at `base`
+    ///
+    /// Fails if `base` isn't a pointer, any index isn't an integer,
+    /// or the indices don't select an element of `source_ty` (struct
+    /// fields must be selected with a constant integer)
+    pub(crate) fn try_const_gep(
+        self,
+        alloc: AllocContext<'ctx>,
+        source_ty: Type<'ctx>,
+        base: Value<'ctx>,
+        indices: &[Value<'ctx>],
+        inbounds: bool,
+    ) -> Result<Value<'ctx>, Error> {
+        let opcode = ConstOpcode::GetElementPtr {
+            source_ty,
+            inbounds,
+        };
+
+        check_gep(source_ty, base, indices)?;
+
+        check_constant(base)?;
+        for &index in indices {
+            check_constant(index)?;
+        }
+
+        if indices.iter().all(|index| index.is_zero()) {
+            return Ok(base);
+        }
+
+        let operands = core::iter::once(base)
+            .chain(indices.iter().copied())
+            .collect::<Vec<_>>();
+
+        Ok(self.const_expr(alloc, base.ty(), opcode, &operands).erase())
+    }
+
+    fn fold_cast(
+        self,
+        alloc: AllocContext<'ctx>,
+        opcode: ConstOpcode<'ctx>,
+        value: Value<'ctx>,
+        ty: Type<'ctx>,
+    ) -> Option<Value<'ctx>> {
+        if value.ty() == ty {
+            return Some(value);
+        }
+
+        match (opcode, value.unpack()) {
+            (
+                ConstOpcode::Trunc | ConstOpcode::ZExt | ConstOpcode::SExt,
+                UnpackedValue::ConstInt(x),
+            ) => {
+                let ty = ty.cast::<types::IntegerInfo>();
+                let result = match opcode {
+                    ConstOpcode::SExt => x.as_signed(),
+                    _ => rug::Integer::from(&*x.as_unsigned()),
+                };
+
+                Some(self.const_int_wrapping(alloc, ty, &result).erase())
+            }
+            (ConstOpcode::BitCast, UnpackedValue::ConstInt(x)) => {
+                let bits = x.as_unsigned().to_u128()?;
+
+                match ty.unpack() {
+                    UnpackedType::Float(ty) => {
+                        Some(self.const_float_from_bits(alloc, ty, bits).erase())
+                    }
+                    _ => None,
+                }
+            }
+            (ConstOpcode::BitCast, UnpackedValue::ConstFloat(x)) => match ty.unpack() {
+                UnpackedType::Integer(ty) => {
+                    let bits = rug::Integer::from(x.bits());
+                    Some(self.const_int_wrapping(alloc, ty, &bits).erase())
+                }
+                UnpackedType::Float(ty) => {
+                    Some(self.const_float_from_bits(alloc, ty, x.bits()).erase())
+                }
+                _ => None,
+            },
+            (ConstOpcode::PtrToInt, UnpackedValue::ConstNull(_)) => {
+                let ty = ty.cast::<types::IntegerInfo>();
+                let zero = self.try_const_int(alloc, ty, self.zero_value()).ok()?;
+                Some(zero.erase())
+            }
+            (ConstOpcode::IntToPtr, UnpackedValue::ConstInt(x)) if x.is_zero() => {
+                let ty = ty.cast::<types::PointerInfo>();
+                Some(self.const_null(alloc, ty).erase())
+            }
+            _ => None,
+        }
+    }
+
+    fn const_expr(
+        self,
+        alloc: AllocContext<'ctx>,
+        ty: Type<'ctx>,
+        opcode: ConstOpcode<'ctx>,
+        operands: &[Value<'ctx>],
+    ) -> value::ConstExpr<'ctx> {
+        let table = &mut *self.info.const_exprs.borrow_mut();
+
+        let hash = hash_one((ty, opcode, operands));
+
+        if let Some(&value) = table.get(hash, |x| {
+            x.ty() == ty && x.opcode() == opcode && x.operands() == operands
+        }) {
+            return value;
+        }
+
+        let value = value::ConstExpr::new(alloc, ty, opcode, operands);
+        self.add_const_operands(operands);
+
+        table.insert(hash, value, |x| {
+            hash_one((x.ty(), x.opcode(), x.operands()))
+        });
+
+        value
+    }
+}
+
+#[test]
+fn test_const_expr() {
+    let target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+
+    crate::Context::with(target, |ctx| {
+        let uint = |ty, x| ctx.const_u64(ty, x).unwrap().erase();
+
+        let minus_two = ctx.const_i32(ctx.i8(), -2).unwrap();
+
+        let zext = ctx.const_zext(minus_two, ctx.i32()).unwrap();
+        assert_eq!(zext, uint(ctx.i32(), 254));
+        let sext = ctx.const_sext(minus_two, ctx.i32()).unwrap();
+        assert_eq!(sext, ctx.const_i32(ctx.i32(), -2).unwrap().erase());
+        assert!(ctx.const_trunc(minus_two, ctx.i32()).is_none());
+
+        let one = ctx.const_f32(ctx.f32(), 1.0);
+        let bits = ctx.const_bitcast(one, ctx.i32()).unwrap();
+        assert_eq!(bits, uint(ctx.i32(), 0x3f80_0000));
+        assert_eq!(ctx.const_bitcast(bits, ctx.f32()), Some(one.erase()));
+        assert!(ctx.const_bitcast(one, ctx.i64()).is_none());
+
+        let null = ctx.const_null(ctx.ptr_ty());
+        let array = ctx.array_ty(4, ctx.i32());
+        let three = uint(ctx.i64(), 3);
+        let zero = uint(ctx.i64(), 0);
+        assert_eq!(
+            ctx.const_gep(array, null, &[zero, zero]),
+            Some(null.erase())
+        );
+
+        let gep = ctx.const_gep(array, null, &[zero, three]).unwrap();
+        assert_eq!(gep.ty(), ctx.ptr_ty().erase());
+        assert_eq!(ctx.const_gep(array, null, &[zero, three]), Some(gep));
+
+        let addr = ctx.const_ptr_to_int(gep, ctx.i64()).unwrap();
+        let UnpackedValue::ConstExpr(addr) = addr.unpack() else {
+            panic!("{addr:?} should not have been folded")
+        };
+        assert_eq!(addr.opcode(), ConstOpcode::PtrToInt);
+        assert_eq!(addr.operands(), &[gep]);
+
+        let sum = ctx.const_add(addr, three).unwrap();
+        assert_eq!(sum.tag(), crate::value::ValueTag::ConstExpr);
+        assert!(ctx.const_add(addr, bits).is_none());
+        assert_eq!(ctx.const_add(three, three), Some(uint(ctx.i64(), 6)));
+
+        let wrapped = ctx.const_sub(zero, three).unwrap();
+        assert_eq!(wrapped, uint(ctx.i64(), u64::MAX - 2));
+    });
+}
@@ -0,0 +1,60 @@
+use crate::{
+    error::check_ty,
+    types,
+    value::{
+        fold::{self, IntBinOp, IntFlags, IntPredicate},
+        ConstInt, Value,
+    },
+    AllocContext, Error,
+};
+
+use super::ValueContext;
+
+impl<'ctx> ValueContext<'ctx> {
+    /// Fold `lhs op rhs`, the result is poison if the operation is, see [`fold::fold_int_binop`]
+    ///
+    /// Fails if `lhs` and `rhs` have different types
+    pub(crate) fn try_const_int_binop(
+        self,
+        alloc: AllocContext<'ctx>,
+        op: IntBinOp,
+        flags: IntFlags,
+        lhs: ConstInt<'ctx>,
+        rhs: ConstInt<'ctx>,
+    ) -> Result<Value<'ctx>, Error> {
+        check_ty(lhs.ty(), rhs.ty())?;
+
+        let ty = lhs.ty().cast::<types::IntegerInfo>();
+        let result = fold::fold_int_binop(
+            op,
+            flags,
+            lhs.bits(),
+            &lhs.as_unsigned(),
+            &rhs.as_unsigned(),
+        );
+
+        match result {
+            Some(result) => Ok(self.const_int_wrapping(alloc, ty, &result).erase()),
+            None => self.try_poison(alloc, ty.erase()).map(Value::from),
+        }
+    }
+
+    /// Compare two integer constants, see [`fold::fold_icmp`]
+    ///
+    /// Fails if `lhs` and `rhs` have different types
+    pub(crate) fn try_const_icmp(
+        self,
+        pred: IntPredicate,
+        lhs: ConstInt<'ctx>,
+        rhs: ConstInt<'ctx>,
+    ) -> Result<bool, Error> {
+        check_ty(lhs.ty(), rhs.ty())?;
+
+        Ok(fold::fold_icmp(
+            pred,
+            lhs.bits(),
+            &lhs.as_unsigned(),
+            &rhs.as_unsigned(),
+        ))
+    }
+}
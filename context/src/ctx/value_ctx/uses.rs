@@ -0,0 +1,62 @@
+use crate::value::{GlobalVariable, Use, Value};
+
+use super::ValueContext;
+
+impl<'ctx> ValueContext<'ctx> {
+    pub(crate) fn add_use(self, value: Value<'ctx>, operand: Use<'ctx>) {
+        self.info
+            .uses
+            .borrow_mut()
+            .entry(value)
+            .or_default()
+            .push(operand);
+    }
+
+    pub(crate) fn remove_use(self, value: Value<'ctx>, operand: Use<'ctx>) {
+        let mut uses = self.info.uses.borrow_mut();
+        if let Some(list) = uses.get_mut(&value) {
+            list.retain(|&x| x != operand);
+            if list.is_empty() {
+                uses.remove(&value);
+            }
+        }
+    }
+
+    /// The uses of `value`, in the order they were added
+    pub(crate) fn uses(self, value: Value<'ctx>) -> Vec<Use<'ctx>> {
+        self.info
+            .uses
+            .borrow()
+            .get(&value)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn num_uses(self, value: Value<'ctx>) -> usize {
+        self.info.uses.borrow().get(&value).map_or(0, Vec::len)
+    }
+
+    /// Record the operands of a new constant, constants are never
+    /// rewritten so they stay users for the lifetime of the context
+    pub(crate) fn add_const_operands(self, operands: &[Value<'ctx>]) {
+        self.info
+            .const_operands
+            .borrow_mut()
+            .extend(operands.iter().copied());
+    }
+
+    pub(crate) fn add_global(self, global: GlobalVariable<'ctx>) {
+        self.info.globals.borrow_mut().push(global);
+    }
+
+    /// Whether a constant or the initializer of a global uses `value`
+    pub(crate) fn has_const_users(self, value: Value<'ctx>) -> bool {
+        self.info.const_operands.borrow().contains(&value)
+            || self
+                .info
+                .globals
+                .borrow()
+                .iter()
+                .any(|global| global.initializer() == Some(value))
+    }
+}
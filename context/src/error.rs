@@ -0,0 +1,295 @@
+use core::fmt;
+
+use crate::{
+    types::Type,
+    value::{BasicBlock, Function, UnpackedValue, Value},
+};
+
+/// An error from constructing a type or a value
+///
+/// Types and values are described by their `Debug` representation, so that
+/// errors can outlive the context they were created in
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// An integer constant doesn't fit in its type, either as
+    /// an unsigned or a two's complement signed integer
+    IntOutOfRange {
+        value: String,
+        bits: u32,
+    },
+    /// The text isn't a valid integer or float literal
+    InvalidLiteral {
+        text: String,
+    },
+    /// The address space doesn't fit in 24 bits
+    InvalidAddressSpace {
+        address_space: u32,
+    },
+    ZeroWidthInteger,
+    ZeroLengthVector,
+    /// Vectors can only contain integers, floats and pointers
+    InvalidVectorItem {
+        ty: String,
+    },
+    /// A value or type didn't have the type required by its position
+    TypeMismatch {
+        expected: String,
+        found: String,
+    },
+    /// The number of items of an aggregate doesn't match its type
+    LengthMismatch {
+        expected: u64,
+        found: u64,
+    },
+    /// The struct has no body
+    OpaqueStruct {
+        ty: String,
+    },
+    /// Only arrays, structs and vectors can be zero initialized
+    NotAnAggregate {
+        ty: String,
+    },
+    /// The type can't be the type of an instruction, such as the unit type or a function type
+    NotFirstClass {
+        ty: String,
+    },
+    /// The items of a scalable vector can't be listed
+    ScalableVector {
+        ty: String,
+    },
+    /// The operation isn't valid between these types
+    InvalidCast {
+        opcode: &'static str,
+        from: String,
+        to: String,
+    },
+    /// The operation isn't valid for operands of these types
+    InvalidOperands {
+        opcode: &'static str,
+        ty: String,
+    },
+    /// A `getelementptr` index doesn't select an element of the type
+    InvalidIndex {
+        ty: String,
+        index: String,
+    },
+    /// Only identified structs can have their body set
+    LiteralStructBody {
+        ty: String,
+    },
+    StructBodyAlreadySet {
+        ty: String,
+    },
+    /// A struct can only contain itself through a pointer
+    RecursiveStruct {
+        ty: String,
+    },
+    /// Globals and functions in a module must have distinct names
+    DuplicateSymbol {
+        name: String,
+    },
+    /// The instruction isn't in a basic block
+    NotInserted {
+        value: String,
+    },
+    /// The instruction is already in a basic block
+    AlreadyInserted {
+        value: String,
+    },
+    /// The instruction was given the wrong number of operands
+    OperandCount {
+        opcode: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// The instruction was given the wrong number of successor blocks
+    BlockCount {
+        opcode: &'static str,
+        expected: usize,
+        found: usize,
+    },
+    /// Only constants can be used here, not instructions or arguments
+    NotConstant {
+        value: String,
+    },
+    /// A `switch` has two cases with the same value
+    DuplicateCase {
+        value: String,
+    },
+    /// The builder isn't positioned in a block
+    NoInsertionPoint,
+    /// The instruction can't be erased while other instructions use it
+    HasUses {
+        value: String,
+        uses: usize,
+    },
+    /// The instruction was erased, so it can't be inserted again
+    Erased {
+        value: String,
+    },
+    /// The value is used by a constant or the initializer of a global,
+    /// which can't be rewritten
+    UsedByConstant {
+        value: String,
+    },
+    /// Only phis can use their own result
+    SelfReference {
+        value: String,
+    },
+    /// Instructions can only use instructions, arguments and blocks of their own function
+    CrossFunction {
+        value: String,
+    },
+    /// The text isn't valid IR, the line and column start at 1
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IntOutOfRange { value, bits } => {
+                write!(f, "{value} does not fit in a {bits} bit integer")
+            }
+            Self::InvalidLiteral { text } => write!(f, "`{text}` is not a valid literal"),
+            Self::InvalidAddressSpace { address_space } => {
+                write!(f, "address space {address_space} does not fit in 24 bits")
+            }
+            Self::ZeroWidthInteger => write!(f, "integers must have at least one bit"),
+            Self::ZeroLengthVector => write!(f, "vectors must have at least one lane"),
+            Self::InvalidVectorItem { ty } => write!(f, "invalid vector item type: {ty}"),
+            Self::TypeMismatch { expected, found } => {
+                write!(f, "expected a value of type {expected}, but found {found}")
+            }
+            Self::LengthMismatch { expected, found } => {
+                write!(f, "expected {expected} items, but found {found}")
+            }
+            Self::OpaqueStruct { ty } => write!(f, "{ty} is opaque"),
+            Self::NotAnAggregate { ty } => write!(f, "{ty} is not an aggregate type"),
+            Self::NotFirstClass { ty } => write!(f, "{ty} is not a first class type"),
+            Self::ScalableVector { ty } => {
+                write!(f, "the items of the scalable vector {ty} cannot be listed")
+            }
+            Self::InvalidCast { opcode, from, to } => {
+                write!(f, "invalid {opcode} from {from} to {to}")
+            }
+            Self::InvalidOperands { opcode, ty } => {
+                write!(f, "invalid operands of type {ty} for {opcode}")
+            }
+            Self::InvalidIndex { ty, index } => write!(f, "{index} is not a valid index into {ty}"),
+            Self::LiteralStructBody { ty } => {
+                write!(f, "cannot set the body of the literal struct {ty}")
+            }
+            Self::StructBodyAlreadySet { ty } => write!(f, "the body of {ty} was already set"),
+            Self::RecursiveStruct { ty } => write!(f, "{ty} cannot contain itself"),
+            Self::DuplicateSymbol { name } => write!(f, "`@{name}` is already defined"),
+            Self::NotInserted { value } => write!(f, "{value} is not in a basic block"),
+            Self::AlreadyInserted { value } => write!(f, "{value} is already in a basic block"),
+            Self::OperandCount {
+                opcode,
+                expected,
+                found,
+            } => write!(f, "{opcode} expects {expected} operands, but found {found}"),
+            Self::BlockCount {
+                opcode,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{opcode} expects {expected} successors, but found {found}"
+            ),
+            Self::NotConstant { value } => write!(f, "{value} is not a constant"),
+            Self::DuplicateCase { value } => write!(f, "duplicate switch case {value}"),
+            Self::NoInsertionPoint => write!(f, "the builder is not positioned in a block"),
+            Self::HasUses { value, uses } => write!(f, "{value} is still used {uses} times"),
+            Self::Erased { value } => write!(f, "{value} was erased"),
+            Self::UsedByConstant { value } => {
+                write!(f, "{value} is used by a constant or a global initializer")
+            }
+            Self::SelfReference { value } => write!(f, "{value} cannot use itself"),
+            Self::CrossFunction { value } => {
+                write!(f, "{value} cannot be used outside of its function")
+            }
+            Self::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "{line}:{column}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// Describe a type or value for an [`Error`]
+pub(crate) fn describe(x: impl fmt::Debug) -> String {
+    format!("{x:?}")
+}
+
+/// Check that a value of type `found` can be used where `expected` is required
+pub(crate) fn check_ty(expected: Type<'_>, found: Type<'_>) -> Result<(), Error> {
+    if expected == found {
+        Ok(())
+    } else {
+        Err(Error::TypeMismatch {
+            expected: describe(expected),
+            found: describe(found),
+        })
+    }
+}
+
+/// Check that `value` can be used where a constant is required
+pub(crate) fn check_constant(value: Value<'_>) -> Result<(), Error> {
+    if value.is_constant() {
+        Ok(())
+    } else {
+        Err(Error::NotConstant {
+            value: describe(value),
+        })
+    }
+}
+
+/// Check that an instruction in `function` can use `value`
+pub(crate) fn check_function<'ctx>(
+    function: Function<'ctx>,
+    value: Value<'ctx>,
+) -> Result<(), Error> {
+    let parent = match value.unpack() {
+        UnpackedValue::Instruction(inst) => inst.function(),
+        UnpackedValue::Argument(arg) => Some(arg.parent()),
+        _ => None,
+    };
+    match parent {
+        Some(parent) if parent != function => Err(Error::CrossFunction {
+            value: describe(value),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Check that an instruction in `function` can refer to `block`
+pub(crate) fn check_block<'ctx>(
+    function: Function<'ctx>,
+    block: BasicBlock<'ctx>,
+) -> Result<(), Error> {
+    if block.parent() == function {
+        Ok(())
+    } else {
+        Err(Error::CrossFunction {
+            value: describe(block),
+        })
+    }
+}
+
+/// The result of a `try_` method, for its panicking sibling
+#[track_caller]
+pub(crate) fn unwrap_or_panic<T>(result: Result<T, Error>) -> T {
+    match result {
+        Ok(x) => x,
+        Err(err) => panic!("{err}"),
+    }
+}
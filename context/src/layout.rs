@@ -0,0 +1,7 @@
+mod align;
+mod data_layout;
+mod spec;
+
+pub use align::{Align, AlignSpec};
+pub use data_layout::{DataLayout, StructLayout};
+pub use spec::{FunctionPtrAlign, LayoutSpec, Mangling, PointerSpec};
@@ -0,0 +1,105 @@
+use core::fmt;
+
+/// A power of two alignment in bytes
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Align {
+    log2: u8,
+}
+
+impl fmt::Debug for Align {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Align({})", self.bytes())
+    }
+}
+
+impl Align {
+    pub const ONE: Self = Self { log2: 0 };
+
+    #[inline]
+    pub const fn from_bytes(bytes: u64) -> Option<Self> {
+        if bytes.is_power_of_two() {
+            Some(Self {
+                log2: bytes.trailing_zeros() as u8,
+            })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub const fn from_bits(bits: u64) -> Option<Self> {
+        if bits % 8 == 0 {
+            Self::from_bytes(bits / 8)
+        } else {
+            None
+        }
+    }
+
+    /// The smallest alignment that is at least as large as `bytes`
+    #[inline]
+    pub const fn natural(bytes: u64) -> Self {
+        if bytes <= 1 {
+            Self::ONE
+        } else {
+            Self {
+                log2: (u64::BITS - (bytes - 1).leading_zeros()) as u8,
+            }
+        }
+    }
+
+    #[inline]
+    pub const fn log2(self) -> u8 {
+        self.log2
+    }
+
+    #[inline]
+    pub const fn bytes(self) -> u64 {
+        1 << self.log2
+    }
+
+    #[inline]
+    pub const fn bits(self) -> u64 {
+        self.bytes() * 8
+    }
+
+    /// Round `offset` up to the next multiple of this alignment
+    #[inline]
+    pub const fn align_to(self, offset: u64) -> u64 {
+        let mask = self.bytes() - 1;
+        (offset + mask) & !mask
+    }
+}
+
+/// The ABI (required) and preferred alignment of a type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AlignSpec {
+    pub abi: Align,
+    pub pref: Align,
+}
+
+impl AlignSpec {
+    /// # Panics
+    ///
+    /// If the preferred alignment is less than the ABI alignment
+    pub const fn new(abi: Align, pref: Align) -> Self {
+        assert!(
+            abi.log2 <= pref.log2,
+            "the preferred alignment must be at least the ABI alignment"
+        );
+        Self { abi, pref }
+    }
+
+    #[inline]
+    pub const fn splat(align: Align) -> Self {
+        Self {
+            abi: align,
+            pref: align,
+        }
+    }
+
+    /// Both the ABI and preferred alignment are the natural alignment of `bytes`
+    #[inline]
+    pub const fn natural(bytes: u64) -> Self {
+        Self::splat(Align::natural(bytes))
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{
+    types::{AddressSpace, StructTy, Type, TypeSize, UnpackedType},
+    Endianness, Target,
+};
+
+use super::{Align, AlignSpec, LayoutSpec};
+
+/// Answers size and alignment questions about types for a [`Target`]
+///
+/// All sizes are in bytes unless the method name says otherwise
+#[derive(Clone, Copy)]
+pub struct DataLayout<'a> {
+    target: &'a Target,
+}
+
+/// The layout of the fields of a struct
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructLayout {
+    size: TypeSize,
+    align: Align,
+    field_offsets: Vec<u64>,
+}
+
+impl StructLayout {
+    /// The size of the struct including any tail padding
+    #[inline]
+    pub fn size(&self) -> TypeSize {
+        self.size
+    }
+
+    #[inline]
+    pub fn align(&self) -> Align {
+        self.align
+    }
+
+    #[inline]
+    pub fn field_offsets(&self) -> &[u64] {
+        &self.field_offsets
+    }
+
+    #[inline]
+    pub fn field_offset(&self, index: usize) -> u64 {
+        self.field_offsets[index]
+    }
+
+    /// The index of the field that contains the byte at `offset`
+    ///
+    /// zero-sized fields are skipped over, and padding bytes are considered
+    /// part of the preceding field
+    pub fn field_at_offset(&self, offset: u64) -> Option<usize> {
+        if offset >= self.size.known_min() {
+            return None;
+        }
+
+        self.field_offsets
+            .partition_point(|&field_offset| field_offset <= offset)
+            .checked_sub(1)
+    }
+}
+
+#[cold]
+#[inline(never)]
+fn unsized_type(ty: Type<'_>) -> ! {
+    panic!("Cannot compute the layout of the unsized type {ty:?}")
+}
+
+impl<'a> DataLayout<'a> {
+    #[inline]
+    pub fn new(target: &'a Target) -> Self {
+        Self { target }
+    }
+
+    #[inline]
+    pub fn target(self) -> &'a Target {
+        self.target
+    }
+
+    #[inline]
+    pub fn spec(self) -> &'a LayoutSpec {
+        &self.target.layout
+    }
+
+    #[inline]
+    pub fn endianness(self) -> Endianness {
+        self.target.endianness
+    }
+
+    #[inline]
+    pub fn pointer_size_bits(self, address_space: AddressSpace) -> u32 {
+        self.spec().pointer(address_space.get()).size_bits
+    }
+
+    #[inline]
+    pub fn pointer_size(self, address_space: AddressSpace) -> u64 {
+        u64::from(self.pointer_size_bits(address_space)).div_ceil(8)
+    }
+
+    #[inline]
+    pub fn index_size_bits(self, address_space: AddressSpace) -> u32 {
+        self.spec().pointer(address_space.get()).index_bits
+    }
+
+    /// Does this type have a size, function types and opaque structs don't
+    pub fn is_sized(self, ty: impl Into<Type<'a>>) -> bool {
+        match ty.into().unpack() {
+            UnpackedType::Unit(_)
+            | UnpackedType::Integer(_)
+            | UnpackedType::Float(_)
+            | UnpackedType::Pointer(_)
+            | UnpackedType::Vector(_) => true,
+            UnpackedType::Function(_) => false,
+            UnpackedType::Array(ty) => self.is_sized(ty.item_ty()),
+            UnpackedType::Struct(ty) => {
+                !ty.is_opaque() && ty.field_tys().iter().all(|&ty| self.is_sized(ty))
+            }
+        }
+    }
+
+    /// The number of bits needed to hold a value of this type, without any padding
+    ///
+    /// # Panics
+    ///
+    /// If the type is unsized (see [`DataLayout::is_sized`])
+    pub fn type_size_bits(self, ty: impl Into<Type<'a>>) -> TypeSize {
+        let ty = ty.into();
+        match ty.unpack() {
+            UnpackedType::Unit(_) => TypeSize::ZERO,
+            UnpackedType::Integer(ty) => TypeSize::fixed(ty.bits().get().into()),
+            UnpackedType::Float(ty) => TypeSize::fixed(ty.storage_bits().into()),
+            UnpackedType::Pointer(ty) => {
+                TypeSize::fixed(self.pointer_size_bits(ty.address_space()).into())
+            }
+            UnpackedType::Vector(ty) => {
+                let item_bits = self.type_size_bits(ty.item_ty()).unwrap_fixed();
+                TypeSize::new(item_bits * u64::from(ty.len()), ty.is_scalable())
+            }
+            UnpackedType::Array(array) => {
+                let len = array.len();
+                self.alloc_size(array.item_ty()).map(|size| size * len * 8)
+            }
+            UnpackedType::Struct(ty) => self.struct_layout(ty).size.map(|size| size * 8),
+            UnpackedType::Function(_) => unsized_type(ty),
+        }
+    }
+
+    /// The number of bytes written by a store of this type
+    ///
+    /// # Panics
+    ///
+    /// If the type is unsized (see [`DataLayout::is_sized`])
+    pub fn store_size(self, ty: impl Into<Type<'a>>) -> TypeSize {
+        self.type_size_bits(ty).map(|bits| bits.div_ceil(8))
+    }
+
+    /// The offset between consecutive values of this type in an array, including padding
+    ///
+    /// # Panics
+    ///
+    /// If the type is unsized (see [`DataLayout::is_sized`])
+    pub fn alloc_size(self, ty: impl Into<Type<'a>>) -> TypeSize {
+        let ty = ty.into();
+        let align = self.abi_align(ty);
+        self.store_size(ty).map(|size| align.align_to(size))
+    }
+
+    /// The alignment required by the ABI
+    ///
+    /// # Panics
+    ///
+    /// If the type is unsized (see [`DataLayout::is_sized`])
+    pub fn abi_align(self, ty: impl Into<Type<'a>>) -> Align {
+        self.align_spec(ty.into(), true)
+    }
+
+    /// The preferred alignment, which is at least the ABI alignment
+    ///
+    /// # Panics
+    ///
+    /// If the type is unsized (see [`DataLayout::is_sized`])
+    pub fn pref_align(self, ty: impl Into<Type<'a>>) -> Align {
+        self.align_spec(ty.into(), false)
+    }
+
+    fn align_spec(self, ty: Type<'a>, abi: bool) -> Align {
+        let pick = |spec: AlignSpec| if abi { spec.abi } else { spec.pref };
+        let spec = self.spec();
+
+        match ty.unpack() {
+            UnpackedType::Unit(_) => Align::ONE,
+            UnpackedType::Integer(ty) => pick(spec.int_align(ty.bits().get().into())),
+            UnpackedType::Float(ty) => pick(spec.float_align(ty.storage_bits())),
+            UnpackedType::Pointer(ty) => pick(spec.pointer(ty.address_space().get()).align),
+            UnpackedType::Vector(vector) => {
+                let bits = self.type_size_bits(vector).known_min();
+                pick(spec.vector_align(bits))
+            }
+            UnpackedType::Array(ty) => self.align_spec(ty.item_ty(), abi),
+            UnpackedType::Struct(ty) => {
+                // packed structs always have an ABI alignment of one
+                if ty.is_packed() && abi {
+                    return Align::ONE;
+                }
+
+                pick(spec.aggregate).max(self.struct_layout(ty).align)
+            }
+            UnpackedType::Function(_) => unsized_type(ty),
+        }
+    }
+
+    /// The offsets of each field of a struct
+    ///
+    /// Structs containing scalable vectors have a scalable size, the offsets
+    /// of fields after a scalable vector are only the known minimum offset
+    ///
+    /// # Panics
+    ///
+    /// If the struct is unsized (see [`DataLayout::is_sized`])
+    pub fn struct_layout(self, ty: StructTy<'a>) -> StructLayout {
+        if ty.is_opaque() {
+            unsized_type(ty.erase())
+        }
+
+        let packed = ty.is_packed();
+        let mut offset = 0;
+        let mut scalable = false;
+        let mut align = Align::ONE;
+
+        let field_offsets = ty
+            .field_tys()
+            .iter()
+            .map(|&field| {
+                let field_align = if packed {
+                    Align::ONE
+                } else {
+                    self.abi_align(field)
+                };
+
+                align = align.max(field_align);
+                offset = field_align.align_to(offset);
+                let field_offset = offset;
+
+                let size = self.alloc_size(field);
+                scalable |= size.is_scalable();
+                offset += size.known_min();
+
+                field_offset
+            })
+            .collect();
+
+        StructLayout {
+            size: TypeSize::new(align.align_to(offset), scalable),
+            align,
+            field_offsets,
+        }
+    }
+}
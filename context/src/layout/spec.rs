@@ -0,0 +1,177 @@
+use std::collections::BTreeMap;
+
+use super::{Align, AlignSpec};
+
+/// The size and alignment of pointers in an address space
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PointerSpec {
+    pub size_bits: u32,
+    pub align: AlignSpec,
+    /// The width of the integers used for address calculations
+    pub index_bits: u32,
+}
+
+/// The alignment rules of a target
+///
+/// The alignment tables are keyed by bit width. Lookups for a width that isn't
+/// in a table fall back the same way LLVM does: integers use the next larger
+/// entry (or the largest entry), floats and vectors use their natural alignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutSpec {
+    /// keyed by address space, always contains the default address space (`0`)
+    pub pointers: BTreeMap<u32, PointerSpec>,
+    pub ints: BTreeMap<u32, AlignSpec>,
+    pub floats: BTreeMap<u32, AlignSpec>,
+    pub vectors: BTreeMap<u32, AlignSpec>,
+    pub aggregate: AlignSpec,
+    /// The integer widths that the target supports natively, in bits
+    pub native_ints: Vec<u32>,
+    pub stack_align: Option<Align>,
+    pub function_ptr_align: Option<FunctionPtrAlign>,
+    /// Address spaces whose pointers don't have a stable integer representation
+    pub non_integral_address_spaces: Vec<u32>,
+    pub alloca_address_space: u32,
+    pub program_address_space: u32,
+    pub global_address_space: u32,
+    pub mangling: Option<Mangling>,
+}
+
+/// The alignment of function pointers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FunctionPtrAlign {
+    pub align: Align,
+    /// If true the alignment is independent of the alignment of functions,
+    /// otherwise it is a multiple of the function's alignment
+    pub independent: bool,
+}
+
+/// How symbol names are mangled in the object file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mangling {
+    Elf,
+    Goff,
+    MachO,
+    Mips,
+    WinCoff,
+    WinCoffX86,
+    XCoff,
+}
+
+impl Mangling {
+    pub const fn from_code(code: char) -> Option<Self> {
+        Some(match code {
+            'e' => Self::Elf,
+            'l' => Self::Goff,
+            'o' => Self::MachO,
+            'm' => Self::Mips,
+            'w' => Self::WinCoff,
+            'x' => Self::WinCoffX86,
+            'a' => Self::XCoff,
+            _ => return None,
+        })
+    }
+
+    /// The code used for this mangling in data layout strings
+    pub const fn code(self) -> char {
+        match self {
+            Self::Elf => 'e',
+            Self::Goff => 'l',
+            Self::MachO => 'o',
+            Self::Mips => 'm',
+            Self::WinCoff => 'w',
+            Self::WinCoffX86 => 'x',
+            Self::XCoff => 'a',
+        }
+    }
+}
+
+const fn align(bytes: u64) -> Align {
+    match Align::from_bytes(bytes) {
+        Some(align) => align,
+        None => panic!("alignment must be a power of two"),
+    }
+}
+
+impl LayoutSpec {
+    /// The default layout rules (the same as LLVM's defaults) for the given pointer size
+    pub fn new(ptr_size_bits: u32, index_bits: u32) -> Self {
+        let ptr_align = AlignSpec::natural(u64::from(ptr_size_bits).div_ceil(8));
+
+        Self {
+            pointers: BTreeMap::from([(
+                0,
+                PointerSpec {
+                    size_bits: ptr_size_bits,
+                    align: ptr_align,
+                    index_bits,
+                },
+            )]),
+            ints: BTreeMap::from([
+                (1, AlignSpec::splat(align(1))),
+                (8, AlignSpec::splat(align(1))),
+                (16, AlignSpec::splat(align(2))),
+                (32, AlignSpec::splat(align(4))),
+                (64, AlignSpec::new(align(4), align(8))),
+            ]),
+            floats: BTreeMap::from([
+                (16, AlignSpec::splat(align(2))),
+                (32, AlignSpec::splat(align(4))),
+                (64, AlignSpec::splat(align(8))),
+                (128, AlignSpec::splat(align(16))),
+            ]),
+            vectors: BTreeMap::from([
+                (64, AlignSpec::splat(align(8))),
+                (128, AlignSpec::splat(align(16))),
+            ]),
+            aggregate: AlignSpec::new(align(1), align(8)),
+            native_ints: Vec::new(),
+            stack_align: None,
+            function_ptr_align: None,
+            non_integral_address_spaces: Vec::new(),
+            alloca_address_space: 0,
+            program_address_space: 0,
+            global_address_space: 0,
+            mangling: None,
+        }
+    }
+
+    /// The pointer spec for the given address space, falling back to the default address space
+    pub fn pointer(&self, address_space: u32) -> PointerSpec {
+        match self.pointers.get(&address_space) {
+            Some(&spec) => spec,
+            None => self.pointers[&0],
+        }
+    }
+
+    pub fn int_align(&self, bits: u32) -> AlignSpec {
+        match self.ints.range(bits..).next() {
+            Some((_, &spec)) => spec,
+            None => match self.ints.last_key_value() {
+                Some((_, &spec)) => spec,
+                None => AlignSpec::natural(u64::from(bits).div_ceil(8)),
+            },
+        }
+    }
+
+    pub fn float_align(&self, bits: u32) -> AlignSpec {
+        match self.floats.get(&bits) {
+            Some(&spec) => spec,
+            None => AlignSpec::natural(u64::from(bits).div_ceil(8)),
+        }
+    }
+
+    pub fn vector_align(&self, bits: u64) -> AlignSpec {
+        match u32::try_from(bits)
+            .ok()
+            .and_then(|bits| self.vectors.get(&bits))
+        {
+            Some(&spec) => spec,
+            None => AlignSpec::natural(bits.div_ceil(8)),
+        }
+    }
+
+    /// Is `bits` a native integer width of the target
+    pub fn is_native_int(&self, bits: u32) -> bool {
+        self.native_ints.contains(&bits)
+    }
+}
@@ -1,10 +1,22 @@
 #![feature(rustc_attrs)]
 #![feature(ptr_metadata, type_name_of_val)]
 
+mod builder;
 mod ctx;
+mod error;
+mod module;
+mod parse;
+mod print;
+mod target;
 
-pub use ctx::{AllocContext, Context, TypeContext};
+pub use builder::IrBuilder;
+pub use ctx::{AllocContext, Context, TypeContext, ValueContext};
+pub use error::Error;
+pub use module::Module;
+pub use print::DisplayLlvm;
+pub use target::{DataLayoutError, Endianness, PtrBits, Target};
 
+pub mod layout;
 pub mod name;
 pub mod types;
 pub mod value;
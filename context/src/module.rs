@@ -0,0 +1,255 @@
+use hashbrown::HashMap;
+
+use crate::{
+    error::{describe, unwrap_or_panic},
+    name::Name,
+    types::{self, AddressSpace},
+    value::{self, Function, GlobalVariable},
+    Context, Error,
+};
+
+/// A global variable or a function, which share the names of a module
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Symbol<'ctx> {
+    Global(GlobalVariable<'ctx>),
+    Function(Function<'ctx>),
+}
+
+/// A unit of code: the global variables and functions which are compiled together
+///
+/// Globals and functions are kept in the order they were added, and named ones can be
+/// looked up by name. Unnamed ones can only be reached through the iterators
+pub struct Module<'ctx> {
+    ctx: Context<'ctx>,
+    name: Option<istr::IStr>,
+    globals: Vec<GlobalVariable<'ctx>>,
+    functions: Vec<Function<'ctx>>,
+    symbols: HashMap<istr::IStr, Symbol<'ctx>>,
+}
+
+impl<'ctx> Module<'ctx> {
+    pub fn new(ctx: Context<'ctx>, name: impl Name) -> Self {
+        Self {
+            ctx,
+            name: name.to_name(),
+            globals: Vec::new(),
+            functions: Vec::new(),
+            symbols: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn ctx(&self) -> Context<'ctx> {
+        self.ctx
+    }
+
+    #[inline]
+    pub fn name(&self) -> Option<istr::IStr> {
+        self.name
+    }
+
+    /// Add a global without an initializer, whose address is in `address_space`
+    ///
+    /// # Panics
+    ///
+    /// If the name is already taken, or `value_ty` isn't a first class type
+    #[track_caller]
+    pub fn add_global(
+        &mut self,
+        name: impl Name,
+        value_ty: impl Into<types::Type<'ctx>>,
+        address_space: AddressSpace,
+    ) -> GlobalVariable<'ctx> {
+        unwrap_or_panic(self.try_add_global(name, value_ty, address_space))
+    }
+
+    pub fn try_add_global(
+        &mut self,
+        name: impl Name,
+        value_ty: impl Into<types::Type<'ctx>>,
+        address_space: AddressSpace,
+    ) -> Result<GlobalVariable<'ctx>, Error> {
+        let name = name.to_name();
+        let value_ty = value_ty.into();
+
+        if !value_ty.is_first_class() {
+            return Err(Error::NotFirstClass {
+                ty: describe(value_ty),
+            });
+        }
+
+        self.check_name(name)?;
+
+        let ty = self.ctx.ptr_ty_at(address_space);
+        let global = GlobalVariable::new(self.ctx.alloc(), ty, name, value_ty);
+        self.ctx.value().add_global(global);
+
+        if let Some(name) = name {
+            self.symbols.insert(name, Symbol::Global(global));
+        }
+        self.globals.push(global);
+
+        Ok(global)
+    }
+
+    /// Add a function declaration, whose address is in the program address space of the target
+    ///
+    /// # Panics
+    ///
+    /// If the name is already taken
+    #[track_caller]
+    pub fn add_function(
+        &mut self,
+        name: impl Name,
+        function_ty: types::FunctionTy<'ctx>,
+    ) -> Function<'ctx> {
+        unwrap_or_panic(self.try_add_function(name, function_ty))
+    }
+
+    pub fn try_add_function(
+        &mut self,
+        name: impl Name,
+        function_ty: types::FunctionTy<'ctx>,
+    ) -> Result<Function<'ctx>, Error> {
+        let name = name.to_name();
+        self.check_name(name)?;
+
+        let address_space = self.ctx.target().layout.program_address_space;
+        let ty = self.ctx.ptr_ty_at(AddressSpace::try_new(address_space)?);
+        let function = Function::new(self.ctx.alloc(), ty, name, function_ty);
+
+        if let Some(name) = name {
+            self.symbols.insert(name, Symbol::Function(function));
+        }
+        self.functions.push(function);
+
+        Ok(function)
+    }
+
+    fn check_name(&self, name: Option<istr::IStr>) -> Result<(), Error> {
+        match name {
+            Some(name) if self.symbols.contains_key(&name) => Err(Error::DuplicateSymbol {
+                name: name.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn get_global(&self, name: istr::IStr) -> Option<GlobalVariable<'ctx>> {
+        match self.symbols.get(&name)? {
+            Symbol::Global(global) => Some(*global),
+            Symbol::Function(_) => None,
+        }
+    }
+
+    pub fn get_function(&self, name: istr::IStr) -> Option<Function<'ctx>> {
+        match self.symbols.get(&name)? {
+            Symbol::Function(function) => Some(*function),
+            Symbol::Global(_) => None,
+        }
+    }
+
+    /// The global or function with the given name
+    pub fn get_symbol(&self, name: istr::IStr) -> Option<value::Value<'ctx>> {
+        match *self.symbols.get(&name)? {
+            Symbol::Global(global) => Some(global.erase()),
+            Symbol::Function(function) => Some(function.erase()),
+        }
+    }
+
+    /// All globals, in the order they were added
+    #[inline]
+    pub fn globals(&self) -> &[GlobalVariable<'ctx>] {
+        &self.globals
+    }
+
+    /// All functions, in the order they were added
+    #[inline]
+    pub fn functions(&self) -> &[Function<'ctx>] {
+        &self.functions
+    }
+}
+
+#[test]
+fn test_module() {
+    let target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+
+    Context::with(target, |ctx| {
+        let mut module = Module::new(ctx, "test");
+        assert_eq!(module.name(), Some(istr::IStr::new("test")));
+
+        let counter = module.add_global("counter", ctx.i32(), AddressSpace::DEFAULT);
+        assert_eq!(counter.ty(), ctx.ptr_ty().erase());
+        assert_eq!(counter.value_ty(), ctx.i32().erase());
+        assert!(counter.is_declaration());
+
+        let zero = ctx.const_u32(ctx.i32(), 0).unwrap();
+        counter.set_initializer(Some(zero.erase()));
+        assert_eq!(counter.initializer(), Some(zero.erase()));
+        assert!(counter
+            .try_set_initializer(Some(ctx.const_f32(ctx.f32(), 0.0).erase()))
+            .is_err());
+
+        let table = module.add_global("table", ctx.array_ty(2, ctx.ptr_ty()), AddressSpace::new(1));
+        table.set_constant(true);
+        table.set_linkage(value::Linkage::Private);
+        assert!(table.is_constant());
+        assert_eq!(table.address_space(), AddressSpace::new(1));
+
+        let main_ty = ctx.function_ty(ctx.i32(), &[]);
+        let main = module.add_function("main", main_ty);
+        let exit = module.add_function("exit", ctx.function_ty(ctx.unit(), &[ctx.i32().erase()]));
+        assert_eq!(main.function_ty(), main_ty);
+        assert_eq!(main.linkage(), value::Linkage::External);
+
+        assert_eq!(
+            module.try_add_function("counter", main_ty),
+            Err(Error::DuplicateSymbol {
+                name: "counter".to_owned()
+            })
+        );
+        assert!(matches!(
+            module.try_add_global("bad", ctx.unit(), AddressSpace::DEFAULT),
+            Err(Error::NotFirstClass { .. })
+        ));
+
+        let unnamed = module.add_global((), ctx.i8(), AddressSpace::DEFAULT);
+        assert_ne!(
+            unnamed,
+            module.add_global((), ctx.i8(), AddressSpace::DEFAULT)
+        );
+
+        assert_eq!(module.get_global(istr::IStr::new("counter")), Some(counter));
+        assert_eq!(module.get_function(istr::IStr::new("exit")), Some(exit));
+        assert_eq!(module.get_function(istr::IStr::new("counter")), None);
+        assert_eq!(
+            module.get_symbol(istr::IStr::new("main")),
+            Some(main.erase())
+        );
+        assert_eq!(module.globals()[..2], [counter, table]);
+        assert_eq!(module.functions(), [main, exit]);
+
+        // globals are constants, so their addresses can be used in other constants
+        let fns = ctx.array_ty(2, ctx.ptr_ty());
+        let init = ctx.const_array(fns, &[main.erase(), exit.erase()]).unwrap();
+        table.set_initializer(Some(init));
+        assert_eq!(init.to_bytes(ctx.data_layout()), None);
+        assert_eq!(format!("{:?}", init), "[@main, @exit]");
+    });
+}
+
+#[test]
+fn test_invalid_program_address_space() {
+    let mut target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+    target.layout.program_address_space = 1 << 24;
+
+    Context::with(target, |ctx| {
+        let mut module = Module::new(ctx, "test");
+        let function_ty = ctx.function_ty(ctx.unit(), &[]);
+        assert!(matches!(
+            module.try_add_function("f", function_ty),
+            Err(Error::InvalidAddressSpace { .. })
+        ));
+        assert!(module.get_symbol(istr::IStr::new("f")).is_none());
+    });
+}
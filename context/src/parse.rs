@@ -0,0 +1,1709 @@
+//! Parsing the textual form of the IR written by the `Display` impls, see the `print` module
+//!
+//! The `%Name = type` definitions are parsed first, then the globals and the headers of the
+//! functions, and last the initializers of the globals and the bodies of the functions,
+//! so types, globals and functions can be used before they are defined. Inside a function,
+//! values and blocks can also be used before they are defined
+//!
+//! Numbered values (`@0`, `%1`) must be numbered in order like the printer does, and
+//! a function body can leave out the label of its entry block
+
+mod lexer;
+
+use hashbrown::HashMap;
+
+use crate::{
+    layout::Align,
+    types::{
+        AddressSpace, ArrayInfo, FloatInfo, FloatKind, FloatTy, IntegerInfo, PointerInfo,
+        StructFlags, StructInfo, StructTy, Type, TypeTag, VectorInfo,
+    },
+    value::{
+        float_bits, BasicBlock, CastOp, ConstOpcode, FloatBinOp, FloatPredicate, Function,
+        GlobalVariable, Instruction, IntBinOp, IntFlags, IntPredicate, Linkage, Opcode, Value,
+    },
+    Context, Error, Module,
+};
+
+use lexer::{Name, Token, TokenKind};
+
+const LINKAGES: [Linkage; 9] = [
+    Linkage::External,
+    Linkage::Internal,
+    Linkage::Private,
+    Linkage::Weak,
+    Linkage::WeakOdr,
+    Linkage::LinkOnce,
+    Linkage::LinkOnceOdr,
+    Linkage::Common,
+    Linkage::ExternWeak,
+];
+
+const FLOAT_KINDS: [FloatKind; 7] = [
+    FloatKind::Ieee16Bit,
+    FloatKind::Ieee32Bit,
+    FloatKind::Ieee64Bit,
+    FloatKind::Ieee128Bit,
+    FloatKind::BFloat16,
+    FloatKind::X86Fp80,
+    FloatKind::PpcFp128,
+];
+
+const INT_BIN_OPS: [IntBinOp; 13] = [
+    IntBinOp::Add,
+    IntBinOp::Sub,
+    IntBinOp::Mul,
+    IntBinOp::UDiv,
+    IntBinOp::SDiv,
+    IntBinOp::URem,
+    IntBinOp::SRem,
+    IntBinOp::Shl,
+    IntBinOp::LShr,
+    IntBinOp::AShr,
+    IntBinOp::And,
+    IntBinOp::Or,
+    IntBinOp::Xor,
+];
+
+const FLOAT_BIN_OPS: [FloatBinOp; 5] = [
+    FloatBinOp::FAdd,
+    FloatBinOp::FSub,
+    FloatBinOp::FMul,
+    FloatBinOp::FDiv,
+    FloatBinOp::FRem,
+];
+
+const CAST_OPS: [CastOp; 13] = [
+    CastOp::Trunc,
+    CastOp::ZExt,
+    CastOp::SExt,
+    CastOp::FPTrunc,
+    CastOp::FPExt,
+    CastOp::FPToUI,
+    CastOp::FPToSI,
+    CastOp::UIToFP,
+    CastOp::SIToFP,
+    CastOp::PtrToInt,
+    CastOp::IntToPtr,
+    CastOp::BitCast,
+    CastOp::AddrSpaceCast,
+];
+
+const INT_PREDICATES: [IntPredicate; 10] = [
+    IntPredicate::Eq,
+    IntPredicate::Ne,
+    IntPredicate::Ugt,
+    IntPredicate::Uge,
+    IntPredicate::Ult,
+    IntPredicate::Ule,
+    IntPredicate::Sgt,
+    IntPredicate::Sge,
+    IntPredicate::Slt,
+    IntPredicate::Sle,
+];
+
+const FLOAT_PREDICATES: [FloatPredicate; 16] = [
+    FloatPredicate::False,
+    FloatPredicate::Oeq,
+    FloatPredicate::Ogt,
+    FloatPredicate::Oge,
+    FloatPredicate::Olt,
+    FloatPredicate::Ole,
+    FloatPredicate::One,
+    FloatPredicate::Ord,
+    FloatPredicate::Ueq,
+    FloatPredicate::Ugt,
+    FloatPredicate::Uge,
+    FloatPredicate::Ult,
+    FloatPredicate::Ule,
+    FloatPredicate::Une,
+    FloatPredicate::Uno,
+    FloatPredicate::True,
+];
+
+fn find<T: Copy>(options: &[T], name: impl Fn(T) -> &'static str, word: &str) -> Option<T> {
+    options.iter().copied().find(|&option| name(option) == word)
+}
+
+fn symbol_name(name: &Name) -> Option<&str> {
+    match name {
+        Name::Named(name) => Some(name),
+        Name::Slot(_) => None,
+    }
+}
+
+/// The values and blocks of the function being parsed
+#[derive(Default)]
+struct Locals<'ctx> {
+    values: HashMap<Name, Value<'ctx>>,
+    /// Values which were used before they were defined, with a stand-in
+    /// and the token where they were first used
+    forward: HashMap<Name, (Instruction<'ctx>, usize)>,
+    blocks: HashMap<Name, BasicBlock<'ctx>>,
+    next_slot: usize,
+}
+
+struct Parser<'ctx, 'a> {
+    ctx: Context<'ctx>,
+    module: Module<'ctx>,
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    /// Identified structs, with the token where they were first used until they are defined
+    structs: HashMap<Name, (StructTy<'ctx>, Option<usize>)>,
+    /// Whether all the `%Name = type` definitions have been parsed
+    structs_defined: bool,
+    /// The identified structs in the context which a definition with the same
+    /// name and body refers to, instead of creating a new struct
+    existing_structs: HashMap<Name, StructTy<'ctx>>,
+    /// Whether the definitions are only being compared with `existing_structs`
+    probing: bool,
+    unnamed_symbols: Vec<Value<'ctx>>,
+    locals: Option<Locals<'ctx>>,
+}
+
+impl<'ctx> Module<'ctx> {
+    /// Parse a module in the textual form written by its `Display` impl
+    ///
+    /// A `%Name = type` definition refers to the identified struct `Name` already in
+    /// `ctx` if it has the same body, so a module printed and parsed again in the same
+    /// context uses the same types. Other definitions create a new struct, which is
+    /// renamed if the name is taken (see [`Context::identified_struct_ty`]).
+    ///
+    /// Printing the parsed module gives back the same text unless a struct was renamed.
+    /// Errors are [`Error::Parse`], with the line and column where the problem was found
+    pub fn parse(ctx: Context<'ctx>, text: &str) -> Result<Self, Error> {
+        Parser::new(ctx, text)?.parse_module()
+    }
+}
+
+impl<'ctx, 'a> Parser<'ctx, 'a> {
+    fn new(ctx: Context<'ctx>, text: &'a str) -> Result<Self, Error> {
+        let tokens = lexer::tokenize(text)?;
+
+        let (name, pos) = match &tokens[..] {
+            [Token {
+                kind: TokenKind::Ident("source_filename"),
+                ..
+            }, Token {
+                kind: TokenKind::Punct('='),
+                ..
+            }, Token {
+                kind: TokenKind::Str(name),
+                ..
+            }, ..] => (Some(name.as_str()), 3),
+            _ => (None, 0),
+        };
+
+        Ok(Self {
+            ctx,
+            module: Module::new(ctx, name),
+            tokens,
+            pos,
+            structs: HashMap::new(),
+            structs_defined: false,
+            existing_structs: HashMap::new(),
+            probing: false,
+            unnamed_symbols: Vec::new(),
+            locals: None,
+        })
+    }
+
+    fn peek(&self) -> &TokenKind<'a> {
+        &self.tokens[self.pos].kind
+    }
+
+    fn peek_nth(&self, n: usize) -> &TokenKind<'a> {
+        &self.tokens[(self.pos + n).min(self.tokens.len() - 1)].kind
+    }
+
+    /// Move past the current token and return it, staying at the end of the input
+    fn advance(&mut self) -> TokenKind<'a> {
+        let kind = self.tokens[self.pos].kind.clone();
+        if kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        kind
+    }
+
+    fn error_at(&self, index: usize, message: impl Into<String>) -> Error {
+        let token = &self.tokens[index];
+        Error::Parse {
+            line: token.line,
+            column: token.column,
+            message: message.into(),
+        }
+    }
+
+    /// Report an error from constructing a type or value at the token `index`
+    fn wrap(&self, index: usize, err: Error) -> Error {
+        self.error_at(index, err.to_string())
+    }
+
+    fn unexpected(&self, expected: &str) -> Error {
+        let found = self.peek();
+        self.error_at(self.pos, format!("expected {expected}, found {found}"))
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let found = *self.peek() == TokenKind::Punct(c);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<(), Error> {
+        if self.eat_punct(c) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{c}`")))
+        }
+    }
+
+    fn eat_keyword(&mut self, word: &str) -> bool {
+        let found = *self.peek() == TokenKind::Ident(word);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, word: &str) -> Result<(), Error> {
+        if self.eat_keyword(word) {
+            Ok(())
+        } else {
+            Err(self.unexpected(&format!("`{word}`")))
+        }
+    }
+
+    fn expect_int<T: core::str::FromStr>(&mut self) -> Result<T, Error> {
+        let index = self.pos;
+        match self.advance() {
+            TokenKind::Int(text) => text
+                .parse()
+                .map_err(|_| self.error_at(index, format!("`{text}` is out of range"))),
+            _ => {
+                self.pos = index;
+                Err(self.unexpected("an integer"))
+            }
+        }
+    }
+
+    /// One of `options`, written as its name
+    fn expect_option<T: Copy>(
+        &mut self,
+        options: &[T],
+        name: impl Fn(T) -> &'static str,
+        expected: &str,
+    ) -> Result<T, Error> {
+        if let TokenKind::Ident(word) = *self.peek() {
+            if let Some(option) = find(options, name, word) {
+                self.pos += 1;
+                return Ok(option);
+            }
+        }
+        Err(self.unexpected(expected))
+    }
+
+    /// Whether the current token starts a new top level item, or ends the
+    /// initializer of a global
+    fn at_item_end(&self) -> bool {
+        *self.peek() == TokenKind::Punct(',') || self.at_item_start()
+    }
+
+    /// Whether the current token starts a new top level item, or is the end of the input
+    fn at_item_start(&self) -> bool {
+        match self.peek() {
+            TokenKind::Eof => true,
+            TokenKind::Ident(word) => matches!(*word, "define" | "declare" | "source_filename"),
+            TokenKind::Global(_) | TokenKind::Local(_) => {
+                *self.peek_nth(1) == TokenKind::Punct('=')
+            }
+            _ => false,
+        }
+    }
+
+    /// Skip to the start of the next top level item
+    fn skip_item(&mut self) {
+        let mut depth = 0usize;
+        self.advance();
+        while depth != 0 || !self.at_item_start() {
+            match self.advance() {
+                TokenKind::Punct('(' | '[' | '{' | '<') => depth += 1,
+                TokenKind::Punct(')' | ']' | '}' | '>') => depth = depth.saturating_sub(1),
+                TokenKind::Eof => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// Skip to the `}` closing the `{` at the current token
+    fn skip_body(&mut self) -> Result<(), Error> {
+        let open = self.pos;
+        self.expect_punct('{')?;
+
+        let mut depth = 1;
+        while depth != 0 {
+            match self.advance() {
+                TokenKind::Punct('{') => depth += 1,
+                TokenKind::Punct('}') => depth -= 1,
+                TokenKind::Eof => return Err(self.error_at(open, "unclosed `{`")),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_module(mut self) -> Result<Module<'ctx>, Error> {
+        let start = self.pos;
+        self.find_existing_structs();
+        self.pos = start;
+
+        // the types are defined first, so that globals can have types defined after them
+        let mut items = Vec::new();
+        loop {
+            match self.peek() {
+                TokenKind::Eof => break,
+                TokenKind::Local(_) => self.parse_struct_def()?,
+                TokenKind::Ident("define" | "declare") | TokenKind::Global(_) => {
+                    items.push(self.pos);
+                    self.skip_item();
+                }
+                TokenKind::Ident("source_filename") => {
+                    let message = "`source_filename` must come before everything else";
+                    return Err(self.error_at(self.pos, message));
+                }
+                _ => return Err(self.unexpected("a global, function or type definition")),
+            }
+        }
+
+        self.check_structs_defined()?;
+        self.structs_defined = true;
+
+        let mut initializers = Vec::new();
+        let mut bodies = Vec::new();
+        for index in items {
+            self.pos = index;
+            match self.peek() {
+                TokenKind::Ident("define") => bodies.extend(self.parse_function()?),
+                TokenKind::Ident(_) => {
+                    self.parse_function()?;
+                }
+                _ => initializers.extend(self.parse_global()?),
+            }
+
+            if !self.at_item_start() {
+                return Err(self.unexpected("a global, function or type definition"));
+            }
+        }
+
+        for (global, index) in initializers {
+            self.pos = index;
+            let initializer = self.parse_value(global.value_ty())?;
+            if !self.at_item_end() {
+                return Err(self.unexpected("the end of the initializer"));
+            }
+            global
+                .try_set_initializer(Some(initializer))
+                .map_err(|err| self.wrap(index, err))?;
+        }
+
+        for (function, index) in bodies {
+            self.pos = index;
+            self.parse_body(function)?;
+        }
+
+        Ok(self.module)
+    }
+
+    /// Fill `existing_structs` with the named definitions which match a struct in the context
+    fn find_existing_structs(&mut self) {
+        let mut defs = Vec::new();
+        while *self.peek() != TokenKind::Eof {
+            if let TokenKind::Local(Name::Named(name)) = self.peek() {
+                let name = name.clone();
+                if let Some(ty) = self.ctx.ty().get_identified_struct(istr::IStr::new(&name)) {
+                    self.existing_structs.insert(Name::Named(name.clone()), ty);
+                    defs.push((self.pos, Name::Named(name)));
+                }
+            }
+            self.skip_item();
+        }
+
+        // a definition can only match if the structs it uses match too,
+        // so drop the mismatches until the rest all match
+        self.probing = true;
+        loop {
+            let len = self.existing_structs.len();
+            for (index, name) in &defs {
+                let Some(&ty) = self.existing_structs.get(name) else {
+                    continue;
+                };
+                self.pos = *index;
+                if !self.struct_def_matches(ty).unwrap_or(false) {
+                    self.existing_structs.remove(name);
+                }
+            }
+
+            if self.existing_structs.len() == len {
+                break;
+            }
+        }
+        self.probing = false;
+    }
+
+    /// Whether the definition at the current token has the same body as `ty`
+    fn struct_def_matches(&mut self, ty: StructTy<'ctx>) -> Result<bool, Error> {
+        self.pos += 1;
+        self.expect_punct('=')?;
+        self.expect_keyword("type")?;
+        Ok(match self.parse_struct_body()? {
+            None => ty.is_opaque(),
+            Some((field_tys, packed)) => {
+                !ty.is_opaque() && ty.is_packed() == packed && ty.field_tys() == field_tys
+            }
+        })
+    }
+
+    fn check_structs_defined(&self) -> Result<(), Error> {
+        let undefined = self
+            .structs
+            .iter()
+            .filter_map(|(name, &(_, first_use))| first_use.map(|index| (index, name)));
+        match undefined.min_by_key(|&(index, _)| index) {
+            Some((index, name)) => {
+                Err(self.error_at(index, format!("use of undefined type `%{name}`")))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn parse_linkage(&mut self) -> Option<Linkage> {
+        let TokenKind::Ident(word) = *self.peek() else {
+            return None;
+        };
+        let linkage = find(&LINKAGES, Linkage::name, word)?;
+        self.pos += 1;
+        Some(linkage)
+    }
+
+    fn parse_align(&mut self) -> Result<Align, Error> {
+        let index = self.pos;
+        let bytes = self.expect_int()?;
+        Align::from_bytes(bytes)
+            .ok_or_else(|| self.error_at(index, "alignment must be a power of two"))
+    }
+
+    /// `, align N`, if the next token is a comma
+    fn parse_optional_align(&mut self) -> Result<Option<Align>, Error> {
+        if !self.eat_punct(',') {
+            return Ok(None);
+        }
+        self.expect_keyword("align")?;
+        Ok(Some(self.parse_align()?))
+    }
+
+    /// Check that a numbered global gets the next number
+    fn check_symbol_slot(&self, name: &Name, index: usize) -> Result<(), Error> {
+        match *name {
+            Name::Slot(slot) if slot != self.unnamed_symbols.len() => {
+                let expected = self.unnamed_symbols.len();
+                Err(self.error_at(index, format!("expected this to be numbered `@{expected}`")))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn add_symbol(&mut self, name: &Name, value: Value<'ctx>) {
+        if let Name::Slot(_) = name {
+            self.unnamed_symbols.push(value);
+        }
+    }
+
+    /// The identified struct `%name`, which is created if the types are still being defined
+    fn struct_ref(&mut self, name: Name, index: usize) -> Result<StructTy<'ctx>, Error> {
+        if let Some(&(ty, _)) = self.structs.get(&name) {
+            return Ok(ty);
+        }
+
+        let existing = self.existing_structs.get(&name).copied();
+        let undefined = || self.error_at(index, format!("use of undefined type `%{name}`"));
+        if self.probing {
+            return existing.ok_or_else(undefined);
+        }
+        if self.structs_defined {
+            return Err(undefined());
+        }
+
+        let ty = match (&name, existing) {
+            (_, Some(ty)) => ty,
+            (Name::Named(name), None) => self.ctx.identified_struct_ty(name.as_str()),
+            (Name::Slot(_), None) => self.ctx.identified_struct_ty(()),
+        };
+        self.structs.insert(name, (ty, Some(index)));
+        Ok(ty)
+    }
+
+    /// `%Name = type { .. }` or `%Name = type opaque`
+    fn parse_struct_def(&mut self) -> Result<(), Error> {
+        let index = self.pos;
+        let TokenKind::Local(name) = self.advance() else {
+            unreachable!()
+        };
+        self.expect_punct('=')?;
+        self.expect_keyword("type")?;
+
+        let ty = self.struct_ref(name.clone(), index)?;
+        let first_use = &mut self.structs.get_mut(&name).unwrap().1;
+        if first_use.take().is_none() {
+            return Err(self.error_at(index, format!("redefinition of type `%{name}`")));
+        }
+
+        let body = self.parse_struct_body()?;
+        // a struct from the context already has this body
+        if self.existing_structs.contains_key(&name) {
+            return Ok(());
+        }
+
+        match body {
+            None => Ok(()),
+            Some((field_tys, packed)) => self
+                .ctx
+                .try_set_struct_body(ty, &field_tys, packed)
+                .map_err(|err| self.wrap(index, err)),
+        }
+    }
+
+    /// `opaque`, `{ A, B }` or `<{ A, B }>`, returns the field types and
+    /// whether the struct is packed, or `None` if it is opaque
+    fn parse_struct_body(&mut self) -> Result<Option<(Vec<Type<'ctx>>, bool)>, Error> {
+        if self.eat_keyword("opaque") {
+            return Ok(None);
+        }
+
+        let packed = self.eat_punct('<');
+        self.expect_punct('{')?;
+        let field_tys = self.parse_type_list('}')?;
+        if packed {
+            self.expect_punct('>')?;
+        }
+        Ok(Some((field_tys, packed)))
+    }
+
+    /// `@name = [linkage] [addrspace(N)] global|constant T [init][, align N]`,
+    /// returns the global and the token where its initializer starts
+    fn parse_global(&mut self) -> Result<Option<(GlobalVariable<'ctx>, usize)>, Error> {
+        let index = self.pos;
+        let TokenKind::Global(name) = self.advance() else {
+            unreachable!()
+        };
+        self.expect_punct('=')?;
+
+        let linkage = self.parse_linkage();
+
+        let mut address_space = AddressSpace::DEFAULT;
+        if self.eat_keyword("addrspace") {
+            self.expect_punct('(')?;
+            let space_index = self.pos;
+            address_space = AddressSpace::try_new(self.expect_int()?)
+                .map_err(|err| self.wrap(space_index, err))?;
+            self.expect_punct(')')?;
+        }
+
+        let constant = self.eat_keyword("constant");
+        if !constant {
+            self.expect_keyword("global")?;
+        }
+
+        let value_ty = self.parse_type()?;
+
+        self.check_symbol_slot(&name, index)?;
+        let global = self
+            .module
+            .try_add_global(symbol_name(&name), value_ty, address_space)
+            .map_err(|err| self.wrap(index, err))?;
+        self.add_symbol(&name, global.erase());
+
+        global.set_linkage(linkage.unwrap_or_default());
+        global.set_constant(constant);
+
+        let initializer = if self.at_item_end() {
+            None
+        } else {
+            let start = self.pos;
+            self.skip_initializer();
+            Some((global, start))
+        };
+
+        global.set_align(self.parse_optional_align()?);
+        Ok(initializer)
+    }
+
+    fn skip_initializer(&mut self) {
+        let mut depth = 0usize;
+        while depth != 0 || !self.at_item_end() {
+            match self.advance() {
+                TokenKind::Punct('(' | '[' | '{' | '<') => depth += 1,
+                TokenKind::Punct(')' | ']' | '}' | '>') => depth = depth.saturating_sub(1),
+                TokenKind::Eof => break,
+                _ => {}
+            }
+        }
+    }
+
+    /// `declare [linkage] R @name(A, B)` or `define [linkage] R @name(A %a, B %b) { .. }`,
+    /// returns the function and the token where its body starts
+    fn parse_function(&mut self) -> Result<Option<(Function<'ctx>, usize)>, Error> {
+        let define = self.advance() == TokenKind::Ident("define");
+        let linkage = self.parse_linkage();
+        let output_ty = self.parse_type()?;
+
+        let index = self.pos;
+        let TokenKind::Global(name) = self.advance() else {
+            self.pos = index;
+            return Err(self.unexpected("a function name"));
+        };
+
+        self.expect_punct('(')?;
+        let mut argument_tys = Vec::new();
+        let mut argument_names = Vec::new();
+        if !self.eat_punct(')') {
+            loop {
+                argument_tys.push(self.parse_type()?);
+                if let TokenKind::Local(name) = self.peek() {
+                    argument_names.push(Some((name.clone(), self.pos)));
+                    self.pos += 1;
+                } else {
+                    argument_names.push(None);
+                }
+
+                if self.eat_punct(')') {
+                    break;
+                }
+                self.expect_punct(',')?;
+            }
+        }
+
+        let function_ty = self.ctx.function_ty(output_ty, &argument_tys);
+        self.check_symbol_slot(&name, index)?;
+        let function = self
+            .module
+            .try_add_function(symbol_name(&name), function_ty)
+            .map_err(|err| self.wrap(index, err))?;
+        self.add_symbol(&name, function.erase());
+        function.set_linkage(linkage.unwrap_or_default());
+
+        let mut next_slot = 0;
+        for (i, (&arg, name)) in function.arguments().iter().zip(&argument_names).enumerate() {
+            match name {
+                Some((Name::Named(name), index)) => {
+                    let duplicate = argument_names[..i]
+                        .iter()
+                        .flatten()
+                        .any(|(other, _)| matches!(other, Name::Named(other) if other == name));
+                    if duplicate {
+                        return Err(self.error_at(*index, format!("redefinition of `%{name}`")));
+                    }
+                    arg.set_name(name.as_str());
+                }
+                Some((Name::Slot(slot), index)) if *slot != next_slot => {
+                    let message = format!("expected this to be numbered `%{next_slot}`");
+                    return Err(self.error_at(*index, message));
+                }
+                _ => next_slot += 1,
+            }
+        }
+
+        if !define {
+            return Ok(None);
+        }
+
+        let start = self.pos;
+        self.skip_body()?;
+        Ok(Some((function, start)))
+    }
+
+    fn parse_body(&mut self, function: Function<'ctx>) -> Result<(), Error> {
+        let mut locals = Locals::default();
+        for &arg in function.arguments() {
+            let name = match arg.name() {
+                Some(name) => Name::Named(name.to_string()),
+                None => {
+                    locals.next_slot += 1;
+                    Name::Slot(locals.next_slot - 1)
+                }
+            };
+            locals.values.insert(name, arg.erase());
+        }
+
+        self.expect_punct('{')?;
+
+        // create the blocks up front, so that they are in order and branches can
+        // jump forward
+        let mut block = None;
+        if !matches!(self.peek(), TokenKind::Label(_)) {
+            let entry = self.ctx.append_basic_block(function, ());
+            locals.blocks.insert(Name::Slot(locals.next_slot), entry);
+            locals.next_slot += 1;
+            block = Some(entry);
+        }
+
+        let mut depth = 0usize;
+        for index in self.pos.. {
+            match &self.tokens[index].kind {
+                TokenKind::Punct('(' | '[' | '{' | '<') => depth += 1,
+                TokenKind::Punct('}') if depth == 0 => break,
+                TokenKind::Punct(')' | ']' | '}' | '>') => depth = depth.saturating_sub(1),
+                TokenKind::Label(name) if depth == 0 => {
+                    if locals.blocks.contains_key(name) {
+                        return Err(self.error_at(index, format!("redefinition of `%{name}`")));
+                    }
+                    let block = self.ctx.append_basic_block(function, symbol_name(name));
+                    locals.blocks.insert(name.clone(), block);
+                }
+                TokenKind::Eof => break,
+                _ => {}
+            }
+        }
+
+        self.locals = Some(locals);
+
+        loop {
+            let index = self.pos;
+            match self.peek().clone() {
+                TokenKind::Label(name) => {
+                    self.pos += 1;
+                    let locals = self.locals.as_mut().unwrap();
+                    if let Name::Slot(slot) = name {
+                        let expected = locals.next_slot;
+                        if slot != expected {
+                            let message = format!("expected this to be numbered `%{expected}`");
+                            return Err(self.error_at(index, message));
+                        }
+                        locals.next_slot += 1;
+                    }
+                    block = Some(locals.blocks[&name]);
+                }
+                TokenKind::Punct('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                TokenKind::Eof => return Err(self.unexpected("`}`")),
+                _ => self.parse_instruction(block.unwrap())?,
+            }
+        }
+
+        let locals = self.locals.take().unwrap();
+        let undefined = locals
+            .forward
+            .iter()
+            .map(|(name, &(_, index))| (index, name));
+        if let Some((index, name)) = undefined.min_by_key(|&(index, _)| index) {
+            return Err(self.error_at(index, format!("use of undefined value `%{name}`")));
+        }
+
+        Ok(())
+    }
+
+    /// Give the next number or the name to a value defined at token `index`,
+    /// and replace the uses from before it was defined
+    fn define_local(
+        &mut self,
+        name: Option<Name>,
+        value: Value<'ctx>,
+        index: usize,
+    ) -> Result<(), Error> {
+        let ctx = self.ctx;
+        let locals = self.locals.as_mut().unwrap();
+
+        let name = match name {
+            Some(Name::Slot(slot)) if slot != locals.next_slot => {
+                let message = format!("expected this to be numbered `%{}`", locals.next_slot);
+                return Err(self.error_at(index, message));
+            }
+            Some(Name::Named(name)) => Name::Named(name),
+            Some(Name::Slot(_)) | None => {
+                locals.next_slot += 1;
+                Name::Slot(locals.next_slot - 1)
+            }
+        };
+
+        if locals.values.contains_key(&name) || locals.blocks.contains_key(&name) {
+            return Err(self.error_at(index, format!("redefinition of `%{name}`")));
+        }
+
+        let forward = locals.forward.remove(&name);
+        locals.values.insert(name.clone(), value);
+
+        if let Some((placeholder, _)) = forward {
+            if placeholder.ty() != value.ty() {
+                let message = format!(
+                    "`%{name}` is defined with type `{}`, but was used as `{}`",
+                    value.ty(),
+                    placeholder.ty()
+                );
+                return Err(self.error_at(index, message));
+            }
+
+            ctx.try_replace_all_uses_with(placeholder, value)
+                .and_then(|()| ctx.try_erase_instruction(placeholder))
+                .map_err(|err| self.wrap(index, err))?;
+        }
+
+        Ok(())
+    }
+
+    fn parse_type(&mut self) -> Result<Type<'ctx>, Error> {
+        let mut ty = self.parse_non_function_type()?;
+
+        while self.eat_punct('(') {
+            let argument_tys = self.parse_type_list(')')?;
+            ty = self.ctx.function_ty(ty, &argument_tys).erase();
+        }
+
+        Ok(ty)
+    }
+
+    /// Types separated by commas, up to and including `close`
+    fn parse_type_list(&mut self, close: char) -> Result<Vec<Type<'ctx>>, Error> {
+        let mut tys = Vec::new();
+        if self.eat_punct(close) {
+            return Ok(tys);
+        }
+
+        loop {
+            tys.push(self.parse_type()?);
+            if self.eat_punct(close) {
+                return Ok(tys);
+            }
+            self.expect_punct(',')?;
+        }
+    }
+
+    fn parse_non_function_type(&mut self) -> Result<Type<'ctx>, Error> {
+        let ctx = self.ctx;
+        let index = self.pos;
+
+        match self.advance() {
+            TokenKind::Ident("void") => Ok(ctx.unit().erase()),
+            TokenKind::Ident("ptr") => {
+                let mut address_space = 0;
+                if self.eat_keyword("addrspace") {
+                    self.expect_punct('(')?;
+                    address_space = self.expect_int()?;
+                    self.expect_punct(')')?;
+                }
+                ctx.try_ptr_ty_at(address_space)
+                    .map(|ty| ty.erase())
+                    .map_err(|err| self.wrap(index, err))
+            }
+            TokenKind::Ident(".") => match self.advance() {
+                TokenKind::Str(name) => self.parse_literal_struct(Some(&name)),
+                _ => {
+                    self.pos -= 1;
+                    Err(self.unexpected("the name of a struct"))
+                }
+            },
+            TokenKind::Ident(word) if word.starts_with('.') => {
+                self.parse_literal_struct(Some(&word[1..]))
+            }
+            TokenKind::Ident(word) => {
+                if let Some(kind) = find(&FLOAT_KINDS, crate::print::float_kind_name, word) {
+                    return Ok(ctx.float_ty(kind).erase());
+                }
+
+                match word.strip_prefix('i').map(str::parse::<u16>) {
+                    Some(Ok(bits)) => ctx
+                        .try_int_ty(bits)
+                        .map(|ty| ty.erase())
+                        .map_err(|err| self.wrap(index, err)),
+                    _ => {
+                        self.pos = index;
+                        Err(self.unexpected("a type"))
+                    }
+                }
+            }
+            TokenKind::Punct('[') => {
+                let len = self.expect_int()?;
+                self.expect_keyword("x")?;
+                let item_ty = self.parse_type()?;
+                self.expect_punct(']')?;
+                Ok(ctx.array_ty(len, item_ty).erase())
+            }
+            TokenKind::Punct('{') => {
+                self.pos = index;
+                self.parse_literal_struct(None)
+            }
+            TokenKind::Punct('<') if *self.peek() == TokenKind::Punct('{') => {
+                self.pos = index;
+                self.parse_literal_struct(None)
+            }
+            TokenKind::Punct('<') => {
+                let scalable = self.eat_keyword("vscale");
+                if scalable {
+                    self.expect_keyword("x")?;
+                }
+                let len = self.expect_int()?;
+                self.expect_keyword("x")?;
+                let item_ty = self.parse_type()?;
+                self.expect_punct('>')?;
+
+                let ty = if scalable {
+                    ctx.try_scalable_vector_ty(len, item_ty)
+                } else {
+                    ctx.try_vector_ty(len, item_ty)
+                };
+                ty.map(|ty| ty.erase()).map_err(|err| self.wrap(index, err))
+            }
+            TokenKind::Local(name) => Ok(self.struct_ref(name, index)?.erase()),
+            _ => {
+                self.pos = index;
+                Err(self.unexpected("a type"))
+            }
+        }
+    }
+
+    /// `{ A, B }` or `<{ A, B }>`
+    fn parse_literal_struct(&mut self, name: Option<&str>) -> Result<Type<'ctx>, Error> {
+        let packed = self.eat_punct('<');
+        self.expect_punct('{')?;
+        let field_tys = self.parse_type_list('}')?;
+        if packed {
+            self.expect_punct('>')?;
+        }
+
+        let flags = if packed {
+            StructTy::PACKED
+        } else {
+            StructFlags::default()
+        };
+        Ok(self.ctx.struct_ty(name, flags, &field_tys).erase())
+    }
+
+    fn parse_typed_value(&mut self) -> Result<Value<'ctx>, Error> {
+        let ty = self.parse_type()?;
+        self.parse_value(ty)
+    }
+
+    /// Typed values separated by commas, up to and including `close`
+    fn parse_typed_list(&mut self, close: char) -> Result<Vec<Value<'ctx>>, Error> {
+        let mut values = Vec::new();
+        if self.eat_punct(close) {
+            return Ok(values);
+        }
+
+        loop {
+            values.push(self.parse_typed_value()?);
+            if self.eat_punct(close) {
+                return Ok(values);
+            }
+            self.expect_punct(',')?;
+        }
+    }
+
+    /// A value of type `ty`, written without its type
+    fn parse_value(&mut self, ty: Type<'ctx>) -> Result<Value<'ctx>, Error> {
+        let ctx = self.ctx;
+        let index = self.pos;
+        let mismatch = |this: &Self, what: &str| {
+            this.error_at(index, format!("{what} can't have type `{ty}`"))
+        };
+
+        let value = match self.advance() {
+            TokenKind::Int(text) => {
+                let Some(int_ty) = ty.try_cast::<IntegerInfo>() else {
+                    return Err(mismatch(self, "an integer constant"));
+                };
+                ctx.try_const_int_from_str(int_ty, text)
+                    .map_err(|err| self.wrap(index, err))?
+                    .erase()
+            }
+            TokenKind::Float(text) => {
+                let Some(float_ty) = ty.try_cast::<FloatInfo>() else {
+                    return Err(mismatch(self, "a float constant"));
+                };
+                self.parse_float(index, float_ty, text)?
+            }
+            TokenKind::Ident(word @ ("true" | "false")) => {
+                if ty != ctx.i1().erase() {
+                    return Err(mismatch(self, "a boolean constant"));
+                }
+                let value = u32::from(word == "true");
+                ctx.const_u32(ctx.i1(), value).unwrap().erase()
+            }
+            TokenKind::Ident("null") => {
+                let Some(ptr_ty) = ty.try_cast::<PointerInfo>() else {
+                    return Err(mismatch(self, "`null`"));
+                };
+                ctx.const_null(ptr_ty).erase()
+            }
+            TokenKind::Ident("undef") => ctx
+                .try_undef(ty)
+                .map_err(|err| self.wrap(index, err))?
+                .erase(),
+            TokenKind::Ident("poison") => ctx
+                .try_poison(ty)
+                .map_err(|err| self.wrap(index, err))?
+                .erase(),
+            TokenKind::Ident("zeroinitializer") => ctx
+                .try_const_aggr_zero(ty)
+                .map_err(|err| self.wrap(index, err))?
+                .erase(),
+            TokenKind::Punct('[') => {
+                let Some(array_ty) = ty.try_cast::<ArrayInfo>() else {
+                    return Err(mismatch(self, "an array constant"));
+                };
+                let items = self.parse_typed_list(']')?;
+                ctx.try_const_array(array_ty, &items)
+                    .map_err(|err| self.wrap(index, err))?
+            }
+            TokenKind::Punct('<') if *self.peek() != TokenKind::Punct('{') => {
+                let Some(vector_ty) = ty.try_cast::<VectorInfo>() else {
+                    return Err(mismatch(self, "a vector constant"));
+                };
+                let items = self.parse_typed_list('>')?;
+                ctx.try_const_vector(vector_ty, &items)
+                    .map_err(|err| self.wrap(index, err))?
+            }
+            TokenKind::Punct(open @ ('{' | '<')) => {
+                let packed = open == '<';
+                let struct_ty = match ty.try_cast::<StructInfo>() {
+                    Some(struct_ty) if struct_ty.is_packed() == packed => struct_ty,
+                    _ if packed => return Err(mismatch(self, "a packed struct constant")),
+                    _ => return Err(mismatch(self, "a struct constant")),
+                };
+
+                if packed {
+                    self.expect_punct('{')?;
+                }
+                let fields = self.parse_typed_list('}')?;
+                if packed {
+                    self.expect_punct('>')?;
+                }
+
+                ctx.try_const_struct(struct_ty, &fields)
+                    .map_err(|err| self.wrap(index, err))?
+            }
+            TokenKind::Ident(_) => {
+                self.pos = index;
+                self.parse_const_expr()?
+            }
+            TokenKind::Global(name) => {
+                let symbol = match &name {
+                    Name::Named(name) => self.module.get_symbol(istr::IStr::new(name)),
+                    Name::Slot(slot) => self.unnamed_symbols.get(*slot).copied(),
+                };
+                symbol.ok_or_else(|| {
+                    self.error_at(index, format!("use of undefined symbol `@{name}`"))
+                })?
+            }
+            TokenKind::Local(name) => self.local_value(name, ty, index)?,
+            _ => {
+                self.pos = index;
+                return Err(self.unexpected("a value"));
+            }
+        };
+
+        if value.ty() != ty {
+            let message = format!(
+                "expected a value of type `{ty}`, but found one of type `{}`",
+                value.ty()
+            );
+            return Err(self.error_at(index, message));
+        }
+
+        Ok(value)
+    }
+
+    /// A value of the function being parsed, or a stand-in if it isn't defined yet
+    fn local_value(
+        &mut self,
+        name: Name,
+        ty: Type<'ctx>,
+        index: usize,
+    ) -> Result<Value<'ctx>, Error> {
+        let ctx = self.ctx;
+        let Some(locals) = self.locals.as_mut() else {
+            let message = format!("`%{name}` can only be used inside a function");
+            return Err(self.error_at(index, message));
+        };
+
+        if let Some(&value) = locals.values.get(&name) {
+            return Ok(value);
+        }
+
+        if let Some(&(placeholder, _)) = locals.forward.get(&name) {
+            return Ok(placeholder.erase());
+        }
+
+        // a phi without incoming values can stand in for a value of any first class type
+        match ctx.try_instruction(Opcode::Phi { ty }, &[], &[]) {
+            Ok(placeholder) => {
+                locals.forward.insert(name, (placeholder, index));
+                Ok(placeholder.erase())
+            }
+            Err(err) => Err(self.wrap(index, err)),
+        }
+    }
+
+    /// `half`, `bfloat`, `fp128`, `x86_fp80` and `ppc_fp128` are written as their bits with
+    /// a prefix, and `float` and `double` in decimal or as the bits of a double
+    fn parse_float(
+        &self,
+        index: usize,
+        ty: FloatTy<'ctx>,
+        text: &str,
+    ) -> Result<Value<'ctx>, Error> {
+        let ctx = self.ctx;
+        let kind = ty.kind();
+        let invalid = || self.error_at(index, format!("`{text}` is not a valid `{ty}` constant"));
+
+        let Some(hex) = text.strip_prefix("0x") else {
+            return ctx
+                .try_const_float_from_str(ty, text)
+                .map(|value| value.erase())
+                .map_err(|err| self.wrap(index, err));
+        };
+
+        let (prefix, digits) = match hex.as_bytes().first() {
+            Some(b'H' | b'R' | b'K' | b'L' | b'M') => (Some(hex.as_bytes()[0]), &hex[1..]),
+            _ => (None, hex),
+        };
+
+        let (expected_prefix, len) = match kind {
+            FloatKind::Ieee16Bit => (Some(b'H'), 4),
+            FloatKind::BFloat16 => (Some(b'R'), 4),
+            FloatKind::X86Fp80 => (Some(b'K'), 20),
+            FloatKind::Ieee128Bit => (Some(b'L'), 32),
+            FloatKind::PpcFp128 => (Some(b'M'), 32),
+            FloatKind::Ieee32Bit | FloatKind::Ieee64Bit => (None, 16),
+        };
+
+        if prefix != expected_prefix || digits.len() != len {
+            return Err(invalid());
+        }
+
+        let bits = if len == 32 {
+            // the low 64 bits come first
+            let low = u64::from_str_radix(&digits[..16], 16).map_err(|_| invalid())?;
+            let high = u64::from_str_radix(&digits[16..], 16).map_err(|_| invalid())?;
+            (u128::from(high) << 64) | u128::from(low)
+        } else {
+            u128::from_str_radix(digits, 16).map_err(|_| invalid())?
+        };
+
+        if kind != FloatKind::Ieee32Bit {
+            return Ok(ctx.const_float_from_bits(ty, bits).erase());
+        }
+
+        // floats are written as the double with the same value
+        let double = f64::from_bits(bits as u64);
+        if double.is_nan() {
+            let payload = float_bits::nan_payload_of(FloatKind::Ieee64Bit, bits);
+            let payload = float_bits::convert_nan_payload(FloatKind::Ieee64Bit, kind, payload);
+            return Ok(ctx
+                .const_nan(ty, double.is_sign_negative(), payload)
+                .erase());
+        }
+
+        let single = double as f32;
+        if f64::from(single).to_bits() != double.to_bits() {
+            return Err(invalid());
+        }
+        Ok(ctx.const_f32(ty, single).erase())
+    }
+
+    /// `trunc (T v to U)`, `add (T a, T b)` or `getelementptr [inbounds] (S, ptr p, ..)`
+    fn parse_const_expr(&mut self) -> Result<Value<'ctx>, Error> {
+        let ctx = self.ctx;
+        let index = self.pos;
+        let TokenKind::Ident(word) = self.advance() else {
+            unreachable!()
+        };
+
+        let casts = [
+            ConstOpcode::Trunc,
+            ConstOpcode::ZExt,
+            ConstOpcode::SExt,
+            ConstOpcode::PtrToInt,
+            ConstOpcode::IntToPtr,
+            ConstOpcode::BitCast,
+        ];
+
+        let value = if let Some(opcode) = find(&casts, ConstOpcode::name, word) {
+            self.expect_punct('(')?;
+            let value = self.parse_typed_value()?;
+            self.expect_keyword("to")?;
+            let ty = self.parse_type()?;
+            self.expect_punct(')')?;
+            ctx.value().try_const_cast(ctx.alloc(), opcode, value, ty)
+        } else {
+            match word {
+                "add" | "sub" => {
+                    self.expect_punct('(')?;
+                    let lhs = self.parse_typed_value()?;
+                    self.expect_punct(',')?;
+                    let rhs = self.parse_typed_value()?;
+                    self.expect_punct(')')?;
+                    if word == "add" {
+                        ctx.try_const_add(lhs, rhs)
+                    } else {
+                        ctx.try_const_sub(lhs, rhs)
+                    }
+                }
+                "getelementptr" => {
+                    let inbounds = self.eat_keyword("inbounds");
+                    self.expect_punct('(')?;
+                    let source_ty = self.parse_type()?;
+                    self.expect_punct(',')?;
+                    let operands = self.parse_typed_list(')')?;
+                    let Some((&base, indices)) = operands.split_first() else {
+                        return Err(self.error_at(index, "expected a base pointer"));
+                    };
+                    if inbounds {
+                        ctx.try_const_inbounds_gep(source_ty, base, indices)
+                    } else {
+                        ctx.try_const_gep(source_ty, base, indices)
+                    }
+                }
+                _ => {
+                    self.pos = index;
+                    return Err(self.unexpected("a value"));
+                }
+            }
+        };
+
+        value.map_err(|err| self.wrap(index, err))
+    }
+
+    fn parse_block(&mut self) -> Result<BasicBlock<'ctx>, Error> {
+        let index = self.pos;
+        let TokenKind::Local(name) = self.advance() else {
+            self.pos = index;
+            return Err(self.unexpected("a label"));
+        };
+
+        let block = self.locals.as_ref().unwrap().blocks.get(&name).copied();
+        block.ok_or_else(|| self.error_at(index, format!("use of undefined label `%{name}`")))
+    }
+
+    /// `label %name`
+    fn parse_label(&mut self) -> Result<BasicBlock<'ctx>, Error> {
+        self.expect_keyword("label")?;
+        self.parse_block()
+    }
+
+    /// `T a, b`, the operands of binary operations and comparisons
+    fn parse_binary_operands(&mut self, operands: &mut Vec<Value<'ctx>>) -> Result<(), Error> {
+        let lhs = self.parse_typed_value()?;
+        self.expect_punct(',')?;
+        let rhs = self.parse_value(lhs.ty())?;
+        operands.extend([lhs, rhs]);
+        Ok(())
+    }
+
+    fn parse_instruction(&mut self, block: BasicBlock<'ctx>) -> Result<(), Error> {
+        let ctx = self.ctx;
+        let start = self.pos;
+
+        let result = match (self.peek(), self.peek_nth(1)) {
+            (TokenKind::Local(name), TokenKind::Punct('=')) => {
+                let name = name.clone();
+                self.pos += 2;
+                Some(name)
+            }
+            _ => None,
+        };
+
+        let index = self.pos;
+        let TokenKind::Ident(word) = self.advance() else {
+            self.pos = index;
+            return Err(self.unexpected("an instruction"));
+        };
+
+        let mut operands = Vec::new();
+        let mut blocks = Vec::new();
+
+        let opcode = if let Some(op) = find(&INT_BIN_OPS, IntBinOp::name, word) {
+            let mut flags = IntFlags::NONE;
+            loop {
+                if self.eat_keyword("nuw") {
+                    flags.nuw = true;
+                } else if self.eat_keyword("nsw") {
+                    flags.nsw = true;
+                } else if self.eat_keyword("exact") {
+                    flags.exact = true;
+                } else {
+                    break;
+                }
+            }
+            self.parse_binary_operands(&mut operands)?;
+            Opcode::IntBinary { op, flags }
+        } else if let Some(op) = find(&FLOAT_BIN_OPS, FloatBinOp::name, word) {
+            self.parse_binary_operands(&mut operands)?;
+            Opcode::FloatBinary(op)
+        } else if let Some(op) = find(&CAST_OPS, CastOp::name, word) {
+            operands.push(self.parse_typed_value()?);
+            self.expect_keyword("to")?;
+            let ty = self.parse_type()?;
+            Opcode::Cast { op, ty }
+        } else {
+            match word {
+                "fneg" => {
+                    operands.push(self.parse_typed_value()?);
+                    Opcode::FNeg
+                }
+                "icmp" => {
+                    let pred =
+                        self.expect_option(&INT_PREDICATES, IntPredicate::name, "a predicate")?;
+                    self.parse_binary_operands(&mut operands)?;
+                    Opcode::ICmp(pred)
+                }
+                "fcmp" => {
+                    let pred =
+                        self.expect_option(&FLOAT_PREDICATES, FloatPredicate::name, "a predicate")?;
+                    self.parse_binary_operands(&mut operands)?;
+                    Opcode::FCmp(pred)
+                }
+                "alloca" => {
+                    let allocated_ty = self.parse_type()?;
+                    let mut len = None;
+                    let mut align = None;
+                    while self.eat_punct(',') {
+                        if self.eat_keyword("align") {
+                            align = Some(self.parse_align()?);
+                        } else if len.is_none() && align.is_none() {
+                            len = Some(self.parse_typed_value()?);
+                        } else {
+                            return Err(self.unexpected("`align`"));
+                        }
+                    }
+                    operands
+                        .push(len.unwrap_or_else(|| ctx.const_u32(ctx.i32(), 1).unwrap().erase()));
+                    Opcode::Alloca {
+                        allocated_ty,
+                        align,
+                    }
+                }
+                "load" => {
+                    let ty = self.parse_type()?;
+                    self.expect_punct(',')?;
+                    operands.push(self.parse_typed_value()?);
+                    let align = self.parse_optional_align()?;
+                    Opcode::Load { ty, align }
+                }
+                "store" => {
+                    operands.push(self.parse_typed_value()?);
+                    self.expect_punct(',')?;
+                    operands.push(self.parse_typed_value()?);
+                    let align = self.parse_optional_align()?;
+                    Opcode::Store { align }
+                }
+                "getelementptr" => {
+                    let inbounds = self.eat_keyword("inbounds");
+                    let source_ty = self.parse_type()?;
+                    while self.eat_punct(',') {
+                        operands.push(self.parse_typed_value()?);
+                    }
+                    Opcode::GetElementPtr {
+                        source_ty,
+                        inbounds,
+                    }
+                }
+                "call" => {
+                    let output_ty = self.parse_type()?;
+                    let address_space = ctx.target().layout.program_address_space;
+                    let address_space = AddressSpace::try_new(address_space)
+                        .map_err(|err| self.wrap(index, err))?;
+                    let callee_ty = ctx.ptr_ty_at(address_space);
+                    operands.push(self.parse_value(callee_ty.erase())?);
+
+                    self.expect_punct('(')?;
+                    operands.extend(self.parse_typed_list(')')?);
+                    let argument_tys = operands[1..].iter().map(|arg| arg.ty()).collect::<Vec<_>>();
+                    let function_ty = ctx.function_ty(output_ty, &argument_tys);
+                    Opcode::Call { function_ty }
+                }
+                "select" => {
+                    operands.push(self.parse_typed_value()?);
+                    for _ in 0..2 {
+                        self.expect_punct(',')?;
+                        operands.push(self.parse_typed_value()?);
+                    }
+                    Opcode::Select
+                }
+                "phi" => {
+                    let ty = self.parse_type()?;
+                    if *self.peek() == TokenKind::Punct('[') {
+                        loop {
+                            self.expect_punct('[')?;
+                            operands.push(self.parse_value(ty)?);
+                            self.expect_punct(',')?;
+                            blocks.push(self.parse_block()?);
+                            self.expect_punct(']')?;
+                            if !self.eat_punct(',') {
+                                break;
+                            }
+                        }
+                    }
+                    Opcode::Phi { ty }
+                }
+                "br" if *self.peek() == TokenKind::Ident("label") => {
+                    blocks.push(self.parse_label()?);
+                    Opcode::Br
+                }
+                "br" => {
+                    operands.push(self.parse_typed_value()?);
+                    self.expect_punct(',')?;
+                    blocks.push(self.parse_label()?);
+                    self.expect_punct(',')?;
+                    blocks.push(self.parse_label()?);
+                    Opcode::CondBr
+                }
+                "switch" => {
+                    operands.push(self.parse_typed_value()?);
+                    self.expect_punct(',')?;
+                    blocks.push(self.parse_label()?);
+                    self.expect_punct('[')?;
+                    while !self.eat_punct(']') {
+                        operands.push(self.parse_typed_value()?);
+                        self.expect_punct(',')?;
+                        blocks.push(self.parse_label()?);
+                    }
+                    Opcode::Switch
+                }
+                "ret" => {
+                    if !self.eat_keyword("void") {
+                        operands.push(self.parse_typed_value()?);
+                    }
+                    Opcode::Ret
+                }
+                "unreachable" => Opcode::Unreachable,
+                _ => return Err(self.error_at(index, format!("unknown instruction `{word}`"))),
+            }
+        };
+
+        let inst = ctx
+            .try_instruction(opcode, &operands, &blocks)
+            .and_then(|inst| block.try_append(inst).map(|()| inst))
+            .map_err(|err| self.wrap(index, err))?;
+
+        if inst.ty().tag() == TypeTag::Unit {
+            if result.is_some() {
+                return Err(self.error_at(start, "instructions returning `void` can't be named"));
+            }
+            return Ok(());
+        }
+
+        if let Some(Name::Named(name)) = &result {
+            inst.set_name(name.as_str());
+        }
+        self.define_local(result, inst.erase(), start)
+    }
+}
+
+#[test]
+fn test_parse_round_trip() {
+    let text = r#"source_filename = "round trip"
+
+%Node = type { i32, ptr }
+%0 = type <{ i8, %Node }>
+
+@counter = internal global i64 -1, align 8
+@0 = private constant { float, double } { float 1.500000e+00, double 0x7FF8000000000000 }
+@table = global [2 x ptr] [ptr @counter, ptr getelementptr inbounds (%Node, ptr @0, i64 1, i32 1)]
+@node = global %0 zeroinitializer
+@ext = external global i32
+@halves = global <2 x half> <half 0xH3C00, half 0xHBC00>
+
+define i32 @sum(i32 %n, ptr %p) {
+entry:
+  %0 = icmp sgt i32 %n, 0
+  br i1 %0, label %loop, label %exit
+
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  %acc = phi i32 [ 0, %entry ], [ %2, %loop ]
+  %1 = load i32, ptr %p, align 4
+  %2 = add nsw i32 %acc, %1
+  %next = add nuw nsw i32 %i, 1
+  %3 = icmp ult i32 %next, %n
+  br i1 %3, label %loop, label %exit
+
+exit:
+  %4 = phi i32 [ 0, %entry ], [ %2, %loop ]
+  ret i32 %4
+}
+
+define internal void @1(i8 %0, ptr %out) {
+1:
+  %2 = zext i8 %0 to i64
+  %3 = getelementptr inbounds [4 x i64], ptr %out, i64 0, i64 %2
+  store i64 %2, ptr %3, align 8
+  %4 = alloca double, i32 4, align 16
+  %5 = sitofp i64 %2 to double
+  %6 = fmul double %5, 2.500000e-01
+  %7 = fcmp olt double %6, 0x7FF0000000000000
+  %8 = select i1 %7, ptr %4, ptr null
+  switch i8 %0, label %9 [
+    i8 0, label %11
+    i8 -1, label %11
+  ]
+
+9:
+  %10 = call i32 @sum(i32 7, ptr %8)
+  unreachable
+
+11:
+  ret void
+}
+
+declare ptr @malloc(i64)
+"#;
+
+    let target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+    Context::with(target, |ctx| {
+        let module = Module::parse(ctx, text).unwrap();
+        assert_eq!(module.to_string(), text);
+
+        let sum = module.get_symbol(istr::IStr::new("sum")).unwrap();
+        assert_eq!(sum.ty(), ctx.ptr_ty().erase());
+    });
+}
+
+#[test]
+fn test_parse_same_context() {
+    let target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+    Context::with(target, |ctx| {
+        let i32 = ctx.i32().erase();
+        let ptr = ctx.ptr_ty().erase();
+        let node = ctx.identified_struct_ty("Node");
+        ctx.set_struct_body(node, &[i32, ptr], false);
+
+        let mut module = Module::new(ctx, "built");
+        let head = module.add_global("head", node, AddressSpace::DEFAULT);
+        let value = module.add_function("value", ctx.function_ty(i32, &[ptr]));
+        let entry = ctx.append_basic_block(value, "entry");
+        let builder = crate::IrBuilder::at_end(ctx, entry);
+        let zero = ctx.const_u32(ctx.i32(), 0).unwrap().erase();
+        let field = builder.build_gep(node, value.arguments()[0], &[zero, zero]);
+        let loaded = builder.build_load(i32, field);
+        let one = ctx.const_u32(ctx.i32(), 1).unwrap();
+        let sum = builder.build_add(loaded, one);
+        builder.build_ret(sum);
+
+        let text = module.to_string();
+        let parsed = Module::parse(ctx, &text).unwrap();
+        assert_eq!(parsed.to_string(), text);
+
+        // `%Node` is the struct which was already in the context
+        assert_eq!(parsed.globals().len(), 1);
+        assert_eq!(parsed.globals()[0].value_ty(), head.value_ty());
+        assert_eq!(parsed.functions().len(), 1);
+        let function = parsed.functions()[0];
+        assert_eq!(function.function_ty(), value.function_ty());
+        fn instructions(function: Function<'_>) -> Vec<(Opcode<'_>, Type<'_>, usize)> {
+            function
+                .blocks()
+                .flat_map(|block| block.instructions())
+                .map(|inst| (inst.opcode(), inst.ty(), inst.num_operands()))
+                .collect()
+        }
+        assert_eq!(instructions(function), instructions(value));
+
+        // a definition with another body gets a new struct
+        let other = Module::parse(
+            ctx,
+            "%Node = type { i8 }\n@x = global %Node zeroinitializer",
+        )
+        .unwrap();
+        let ty = other.globals()[0]
+            .value_ty()
+            .try_cast::<StructInfo>()
+            .unwrap();
+        assert_ne!(ty, node);
+        assert_eq!(ty.name(), Some(istr::IStr::new("Node.0")));
+    });
+}
+
+#[test]
+fn test_parse_forward_references() {
+    let target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+    Context::with(target, |ctx| {
+        let text = "
+            ; the entry block doesn't need a label
+            define i32 @square(i32 %x) {
+              br label %body
+            exit:
+              ret i32 %y
+            body:
+              %y = mul i32 %x, %x
+              br label %exit
+            }
+        ";
+        let expected = "define i32 @square(i32 %x) {
+0:
+  br label %body
+
+exit:
+  ret i32 %y
+
+body:
+  %y = mul i32 %x, %x
+  br label %exit
+}
+";
+        let module = Module::parse(ctx, text).unwrap();
+        assert_eq!(module.to_string(), expected);
+
+        // types and globals can be used before they are defined
+        let text = "
+            define ptr @head() {
+              ret ptr @list
+            }
+            @list = global %List { ptr @list }
+            %List = type { ptr }
+        ";
+        assert!(Module::parse(ctx, text).is_ok());
+    });
+}
+
+#[test]
+fn test_parse_errors() {
+    let target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+    Context::with(target, |ctx| {
+        let position = |text: &str| match Module::parse(ctx, text).err() {
+            Some(Error::Parse { line, column, .. }) => (line, column),
+            err => panic!("expected a parse error, found {err:?}"),
+        };
+
+        assert_eq!(position("define i32 @f() {\n  ret i64 0\n}"), (2, 3));
+        assert_eq!(position("define void @f() {\n  br label %exit\n}"), (2, 12));
+        assert_eq!(position("@x = global i32 1.0"), (1, 17));
+        assert_eq!(position("@x = global i32 0 ]"), (1, 19));
+
+        let err = Module::parse(ctx, "define i32 @f() {\n  ret i32 %x\n}").err();
+        assert_eq!(
+            err.unwrap().to_string(),
+            "2:11: use of undefined value `%x`"
+        );
+
+        let err = Module::parse(ctx, "@x = external global %T").err();
+        assert_eq!(err.unwrap().to_string(), "1:22: use of undefined type `%T`");
+    });
+}
+
+#[test]
+fn test_parse_invalid_program_address_space() {
+    let mut target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+    target.layout.program_address_space = 1 << 24;
+
+    Context::with(target, |ctx| {
+        let text = "define void @f() {\n  call void @f()\n  ret void\n}";
+        // the header of `@f` already needs a pointer in the program address space
+        assert!(matches!(
+            Module::parse(ctx, text),
+            Err(Error::Parse { line: 1, .. })
+        ));
+    });
+}
@@ -0,0 +1,278 @@
+use core::fmt;
+
+use crate::Error;
+
+/// A global or local name after its sigil, or a block label
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum Name {
+    Named(String),
+    Slot(usize),
+}
+
+impl fmt::Display for Name {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Name::Named(name) => f.write_str(name),
+            Name::Slot(slot) => write!(f, "{slot}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum TokenKind<'a> {
+    /// Keywords and types, and the names of literal structs (`.Name`)
+    Ident(&'a str),
+    Int(&'a str),
+    /// Decimal floats and hexadecimal bit patterns (`0x..`, `0xH..`)
+    Float(&'a str),
+    Str(String),
+    Global(Name),
+    Local(Name),
+    Label(Name),
+    Punct(char),
+    Eof,
+}
+
+impl fmt::Display for TokenKind<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Ident(text) | TokenKind::Int(text) | TokenKind::Float(text) => {
+                write!(f, "`{text}`")
+            }
+            TokenKind::Str(_) => f.write_str("a string"),
+            TokenKind::Global(name) => write!(f, "`@{name}`"),
+            TokenKind::Local(name) => write!(f, "`%{name}`"),
+            TokenKind::Label(name) => write!(f, "the label `{name}:`"),
+            TokenKind::Punct(c) => write!(f, "`{c}`"),
+            TokenKind::Eof => f.write_str("the end of the input"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) struct Token<'a> {
+    pub kind: TokenKind<'a>,
+    pub line: usize,
+    pub column: usize,
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || matches!(c, '-' | '$' | '.' | '_')
+}
+
+fn is_ident_char(c: char) -> bool {
+    is_ident_start(c) || c.is_ascii_digit()
+}
+
+struct Lexer<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.text[self.pos..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn eat_while(&mut self, f: impl Fn(char) -> bool) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&f) {
+            self.bump();
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn error(&self, message: impl Into<String>) -> Error {
+        Error::Parse {
+            line: self.line,
+            column: self.column,
+            message: message.into(),
+        }
+    }
+
+    /// A quoted string, where `\XX` is a byte in hexadecimal and `\\` is a backslash
+    fn string(&mut self) -> Result<String, Error> {
+        let unterminated = self.error("unterminated string");
+        self.bump();
+
+        let mut bytes = Vec::new();
+        loop {
+            match self.bump() {
+                None => return Err(unterminated),
+                Some('"') => break,
+                Some('\\') if self.peek() == Some('\\') => {
+                    self.bump();
+                    bytes.push(b'\\');
+                }
+                Some('\\') => {
+                    let digits = self.text.get(self.pos..self.pos + 2);
+                    let byte = digits.and_then(|digits| u8::from_str_radix(digits, 16).ok());
+                    let Some(byte) = byte else {
+                        return Err(self.error("expected two hexadecimal digits after `\\`"));
+                    };
+                    self.bump();
+                    self.bump();
+                    bytes.push(byte);
+                }
+                Some(c) => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+
+        String::from_utf8(bytes).map_err(|_| self.error("string is not valid UTF-8"))
+    }
+
+    fn slot(&mut self) -> Result<usize, Error> {
+        let error = self.error("number is too large");
+        let digits = self.eat_while(|c| c.is_ascii_digit());
+        digits.parse().map_err(|_| error)
+    }
+
+    /// The name after a `@` or `%`
+    fn name(&mut self) -> Result<Name, Error> {
+        match self.peek() {
+            Some('"') => Ok(Name::Named(self.string()?)),
+            Some(c) if c.is_ascii_digit() => Ok(Name::Slot(self.slot()?)),
+            Some(c) if is_ident_start(c) => Ok(Name::Named(self.eat_while(is_ident_char).into())),
+            _ => Err(self.error("expected a name")),
+        }
+    }
+
+    fn number(&mut self) -> Result<TokenKind<'a>, Error> {
+        let start = self.pos;
+        let negative = self.peek() == Some('-');
+        if negative {
+            self.bump();
+        }
+
+        if self.text[self.pos..].starts_with("0x") {
+            self.bump();
+            self.bump();
+            if self
+                .peek()
+                .is_some_and(|c| matches!(c, 'H' | 'R' | 'K' | 'L' | 'M'))
+            {
+                self.bump();
+            }
+            self.eat_while(|c| c.is_ascii_hexdigit());
+            return Ok(TokenKind::Float(&self.text[start..self.pos]));
+        }
+
+        if !negative && self.text[self.pos..].starts_with(|c: char| c.is_ascii_digit()) {
+            let digits = self.text[self.pos..].trim_start_matches(|c: char| c.is_ascii_digit());
+            if digits.starts_with(':') {
+                let slot = self.slot()?;
+                self.bump();
+                return Ok(TokenKind::Label(Name::Slot(slot)));
+            }
+        }
+
+        self.eat_while(|c| c.is_ascii_digit());
+        if self.peek() != Some('.') {
+            return Ok(TokenKind::Int(&self.text[start..self.pos]));
+        }
+
+        self.bump();
+        self.eat_while(|c| c.is_ascii_digit());
+        if self.peek().is_some_and(|c| matches!(c, 'e' | 'E')) {
+            self.bump();
+            if self.peek().is_some_and(|c| matches!(c, '+' | '-')) {
+                self.bump();
+            }
+            self.eat_while(|c| c.is_ascii_digit());
+        }
+        Ok(TokenKind::Float(&self.text[start..self.pos]))
+    }
+}
+
+/// Split `text` into tokens, skipping whitespace and `;` comments.
+/// The last token is always [`TokenKind::Eof`]
+pub(super) fn tokenize(text: &str) -> Result<Vec<Token<'_>>, Error> {
+    let mut lexer = Lexer {
+        text,
+        pos: 0,
+        line: 1,
+        column: 1,
+    };
+    let mut tokens = Vec::new();
+
+    loop {
+        match lexer.peek() {
+            Some(c) if c.is_whitespace() => {
+                lexer.bump();
+                continue;
+            }
+            Some(';') => {
+                lexer.eat_while(|c| c != '\n');
+                continue;
+            }
+            _ => {}
+        }
+
+        let (line, column) = (lexer.line, lexer.column);
+        let Some(c) = lexer.peek() else {
+            tokens.push(Token {
+                kind: TokenKind::Eof,
+                line,
+                column,
+            });
+            return Ok(tokens);
+        };
+
+        let kind = match c {
+            '@' | '%' => {
+                lexer.bump();
+                let name = lexer.name()?;
+                if c == '@' {
+                    TokenKind::Global(name)
+                } else {
+                    TokenKind::Local(name)
+                }
+            }
+            '"' => {
+                let text = lexer.string()?;
+                if lexer.peek() == Some(':') {
+                    lexer.bump();
+                    TokenKind::Label(Name::Named(text))
+                } else {
+                    TokenKind::Str(text)
+                }
+            }
+            '-' if lexer.peek_second().is_some_and(|c| c.is_ascii_digit()) => lexer.number()?,
+            '0'..='9' => lexer.number()?,
+            c if is_ident_start(c) => {
+                let word = lexer.eat_while(is_ident_char);
+                if lexer.peek() == Some(':') {
+                    lexer.bump();
+                    TokenKind::Label(Name::Named(word.into()))
+                } else {
+                    TokenKind::Ident(word)
+                }
+            }
+            '=' | ',' | '(' | ')' | '[' | ']' | '{' | '}' | '<' | '>' => {
+                lexer.bump();
+                TokenKind::Punct(c)
+            }
+            _ => return Err(lexer.error(format!("unexpected character `{c}`"))),
+        };
+
+        tokens.push(Token { kind, line, column });
+    }
+}
@@ -0,0 +1,1106 @@
+//! The textual form of the IR, through the `Display` impls of types, values and modules
+//!
+//! The syntax follows LLVM assembly:
+//!
+//! - types: `void`, `i32`, `half`, `float`, `double`, `fp128`, `bfloat`, `x86_fp80`,
+//!   `ppc_fp128`, `ptr`, `ptr addrspace(1)`, `[4 x i8]`, `<4 x i32>`, `<vscale x 4 x i32>`,
+//!   `i64 (ptr, i32)`, literal structs `{ i8, ptr }` and packed `<{ i8, ptr }>`, and
+//!   identified structs `%Name`. A named literal struct is prefixed with its name, as in
+//!   `.Name { i8, ptr }`
+//! - constants: `true`, `-1`, `1.500000e+00`, `0x7FF8000000000000`, `0xH3C00` (half),
+//!   `0xR3F80` (bfloat), `0xL..` (fp128), `0xK..` (x86_fp80), `0xM..` (ppc_fp128),
+//!   `null`, `undef`, `poison`, `zeroinitializer`, `[i8 1, i8 2]`, `{ i8 1, ptr null }`,
+//!   `<i32 1, i32 2>` and expressions like `add (i32 1, i32 @g)`,
+//!   `trunc (i64 1 to i32)` and `getelementptr inbounds (i8, ptr @g, i64 1)`
+//! - globals: `@name = [linkage] [addrspace(N)] global|constant T [init][, align N]`
+//! - functions: `declare [linkage] R @name(A, B)` and
+//!   `define [linkage] R @name(A %a, B %b) { .. }`, where every block starts with a label
+//!   and every instruction is on its own line
+//!
+//! Names which aren't valid identifiers (`[-a-zA-Z$._][-a-zA-Z$._0-9]*`) are quoted, and
+//! unnamed values are numbered in order: identified structs and globals per module, and
+//! arguments, blocks and instructions per function. Duplicate local names get a `.N` suffix
+//!
+//! A module starts with its `source_filename`, then the bodies of the identified structs it
+//! uses (`%Name = type { .. }` or `%Name = type opaque`), its globals and its functions
+//!
+//! [`Module::display_llvm`] writes a module as LLVM assembly, which also has the data layout
+//! string of the target
+
+use core::fmt::{self, Display, Write as _};
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::{
+    types::{AddressSpace, FloatKind, StructTy, Ty, Type, TypeInfo, TypeTag, UnpackedType},
+    value::{
+        float_bits, BasicBlock, ConstExpr, ConstFloat, ConstOpcode, Function, GlobalVariable,
+        Instruction, IntBinOp, Linkage, Opcode, UnpackedValue, Val, Value, ValueInfo,
+    },
+    Module,
+};
+
+/// The name of an argument, block or instruction within its function
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum LocalName {
+    Slot(usize),
+    Named(String),
+}
+
+#[derive(Default)]
+struct LocalNames {
+    next_slot: usize,
+    used: HashSet<String>,
+}
+
+impl LocalNames {
+    fn assign(&mut self, name: Option<istr::IStr>) -> LocalName {
+        let Some(name) = name else {
+            self.next_slot += 1;
+            return LocalName::Slot(self.next_slot - 1);
+        };
+
+        if self.used.insert(name.to_string()) {
+            return LocalName::Named(name.to_string());
+        }
+
+        let name = (1..)
+            .map(|i| format!("{name}.{i}"))
+            .find(|name| !self.used.contains(name))
+            .unwrap();
+        self.used.insert(name.clone());
+        LocalName::Named(name)
+    }
+}
+
+/// The names of unnamed structs and globals, and the locals of one function
+#[derive(Default)]
+struct Printer<'ctx> {
+    structs: HashMap<Type<'ctx>, usize>,
+    struct_defs: Vec<StructTy<'ctx>>,
+    globals: HashMap<Value<'ctx>, usize>,
+    locals: HashMap<Value<'ctx>, LocalName>,
+    blocks: HashMap<BasicBlock<'ctx>, LocalName>,
+    /// Write LLVM assembly, see [`Module::display_llvm`]
+    llvm: bool,
+}
+
+fn is_ident(name: &str) -> bool {
+    let is_ident_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '-' | '$' | '.' | '_');
+    name.chars().all(is_ident_char) && name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+}
+
+/// Write a name after its sigil, quoting it if it isn't an identifier
+fn write_name(f: &mut fmt::Formatter<'_>, name: &str) -> fmt::Result {
+    if is_ident(name) {
+        return f.write_str(name);
+    }
+
+    f.write_char('"')?;
+    for byte in name.bytes() {
+        match byte {
+            b'"' | b'\\' => write!(f, "\\{byte:02X}")?,
+            0x20..=0x7e => f.write_char(byte as char)?,
+            _ => write!(f, "\\{byte:02X}")?,
+        }
+    }
+    f.write_char('"')
+}
+
+fn write_addrspace(f: &mut fmt::Formatter<'_>, address_space: AddressSpace) -> fmt::Result {
+    match address_space.get() {
+        0 => Ok(()),
+        n => write!(f, " addrspace({n})"),
+    }
+}
+
+pub(crate) fn float_kind_name(kind: FloatKind) -> &'static str {
+    match kind {
+        FloatKind::Ieee16Bit => "half",
+        FloatKind::Ieee32Bit => "float",
+        FloatKind::Ieee64Bit => "double",
+        FloatKind::Ieee128Bit => "fp128",
+        FloatKind::BFloat16 => "bfloat",
+        FloatKind::X86Fp80 => "x86_fp80",
+        FloatKind::PpcFp128 => "ppc_fp128",
+    }
+}
+
+/// Floats and doubles are written in decimal if that is exact, and otherwise as the
+/// bits of the equivalent double. The other kinds are written as their bits
+fn write_float(f: &mut fmt::Formatter<'_>, value: ConstFloat<'_>) -> fmt::Result {
+    let bits = value.bits();
+    let double = match value.kind() {
+        FloatKind::Ieee64Bit => bits as u64,
+        FloatKind::Ieee32Bit => match value.nan_payload() {
+            Some(payload) => {
+                let payload =
+                    float_bits::convert_nan_payload(value.kind(), FloatKind::Ieee64Bit, payload);
+                float_bits::nan_bits(FloatKind::Ieee64Bit, value.is_negative(), payload) as u64
+            }
+            None => f64::from(f32::from_bits(bits as u32)).to_bits(),
+        },
+        FloatKind::Ieee16Bit => return write!(f, "0xH{bits:04X}"),
+        FloatKind::BFloat16 => return write!(f, "0xR{bits:04X}"),
+        FloatKind::X86Fp80 => return write!(f, "0xK{bits:020X}"),
+        FloatKind::Ieee128Bit => return write!(f, "0xL{:016X}{:016X}", bits as u64, bits >> 64),
+        FloatKind::PpcFp128 => return write!(f, "0xM{:016X}{:016X}", bits as u64, bits >> 64),
+    };
+
+    let double_value = f64::from_bits(double);
+    if double_value.is_finite() {
+        let text = format!("{double_value:.6e}");
+        if text.parse::<f64>().map(f64::to_bits) == Ok(double) {
+            // `1.500000e0` in the LLVM form `1.500000e+00`
+            let (mantissa, exp) = text.split_once('e').unwrap();
+            let exp: i32 = exp.parse().unwrap();
+            let sign = if exp < 0 { '-' } else { '+' };
+            return write!(f, "{mantissa}e{sign}{:02}", exp.unsigned_abs());
+        }
+    }
+
+    write!(f, "0x{double:016X}")
+}
+
+impl<'ctx> Printer<'ctx> {
+    fn for_function(function: Function<'ctx>) -> Self {
+        let mut printer = Self::default();
+        printer.number_function(function);
+        printer
+    }
+
+    fn for_value(value: Value<'ctx>) -> Self {
+        match value.unpack() {
+            UnpackedValue::Argument(arg) => Self::for_function(arg.parent()),
+            UnpackedValue::Instruction(inst) => match inst.function() {
+                Some(function) => Self::for_function(function),
+                None => Self::default(),
+            },
+            UnpackedValue::Function(function) => Self::for_function(function),
+            _ => Self::default(),
+        }
+    }
+
+    fn for_module(module: &Module<'ctx>) -> Self {
+        let mut printer = Self::default();
+
+        let symbols = module
+            .globals()
+            .iter()
+            .map(|global| (global.erase(), global.name()));
+        let symbols = symbols.chain(module.functions().iter().map(|f| (f.erase(), f.name())));
+        for (symbol, name) in symbols {
+            if name.is_none() {
+                let slot = printer.globals.len();
+                printer.globals.insert(symbol, slot);
+            }
+        }
+
+        for global in module.globals() {
+            printer.visit_ty(global.value_ty());
+            if let Some(init) = global.initializer() {
+                printer.visit_value(init);
+            }
+        }
+
+        for function in module.functions() {
+            printer.visit_ty(function.function_ty().erase());
+            for inst in function.blocks().flat_map(BasicBlock::instructions) {
+                printer.visit_instruction(inst);
+            }
+        }
+
+        printer
+    }
+
+    /// Number the arguments, blocks and instructions of `function`
+    fn number_function(&mut self, function: Function<'ctx>) {
+        self.locals.clear();
+        self.blocks.clear();
+
+        let mut names = LocalNames::default();
+        for arg in function.arguments() {
+            self.locals.insert(arg.erase(), names.assign(arg.name()));
+        }
+
+        for block in function.blocks() {
+            self.blocks.insert(block, names.assign(block.name()));
+            for inst in block.instructions() {
+                if inst.ty().tag() != TypeTag::Unit {
+                    self.locals.insert(inst.erase(), names.assign(inst.name()));
+                }
+            }
+        }
+    }
+
+    /// Collect the identified structs reachable from `ty`, in the order they are found
+    fn visit_ty(&mut self, ty: Type<'ctx>) {
+        match ty.unpack() {
+            UnpackedType::Struct(ty) => {
+                if ty.is_identified() {
+                    if self.struct_defs.iter().any(|def| def.erase() == ty.erase()) {
+                        return;
+                    }
+                    self.struct_defs.push(ty);
+                    if ty.name().is_none() {
+                        let slot = self.structs.len();
+                        self.structs.insert(ty.erase(), slot);
+                    }
+                }
+
+                for &field_ty in ty.field_tys() {
+                    self.visit_ty(field_ty);
+                }
+            }
+            UnpackedType::Array(ty) => self.visit_ty(ty.item_ty()),
+            UnpackedType::Vector(ty) => self.visit_ty(ty.item_ty()),
+            UnpackedType::Function(ty) => {
+                self.visit_ty(ty.output_ty());
+                for &arg_ty in ty.arguments_tys() {
+                    self.visit_ty(arg_ty);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn visit_value(&mut self, value: Value<'ctx>) {
+        self.visit_ty(value.ty());
+
+        let items = match value.unpack() {
+            UnpackedValue::ConstArray(value) => value.items(),
+            UnpackedValue::ConstStruct(value) => value.items(),
+            UnpackedValue::ConstVector(value) => value.items(),
+            UnpackedValue::ConstExpr(value) => {
+                if let ConstOpcode::GetElementPtr { source_ty, .. } = value.opcode() {
+                    self.visit_ty(source_ty);
+                }
+                value.operands()
+            }
+            _ => &[],
+        };
+
+        for &item in items {
+            self.visit_value(item);
+        }
+    }
+
+    fn visit_instruction(&mut self, inst: Instruction<'ctx>) {
+        self.visit_ty(inst.ty());
+
+        match inst.opcode() {
+            Opcode::Cast { ty, .. } | Opcode::Load { ty, .. } | Opcode::Phi { ty } => {
+                self.visit_ty(ty)
+            }
+            Opcode::Alloca { allocated_ty, .. } => self.visit_ty(allocated_ty),
+            Opcode::GetElementPtr { source_ty, .. } => self.visit_ty(source_ty),
+            Opcode::Call { function_ty } => self.visit_ty(function_ty.erase()),
+            _ => {}
+        }
+
+        for operand in inst.operands() {
+            if operand.is_constant() {
+                self.visit_value(operand);
+            }
+        }
+    }
+
+    fn write_struct_name(&self, f: &mut fmt::Formatter<'_>, ty: StructTy<'ctx>) -> fmt::Result {
+        f.write_char('%')?;
+        match (ty.name(), self.structs.get(&ty.erase())) {
+            (Some(name), _) => write_name(f, &name.to_string()),
+            (None, Some(slot)) => write!(f, "{slot}"),
+            (None, None) => f.write_str("<unnamed>"),
+        }
+    }
+
+    fn write_struct_body(&self, f: &mut fmt::Formatter<'_>, ty: StructTy<'ctx>) -> fmt::Result {
+        if ty.is_opaque() {
+            return f.write_str("opaque");
+        }
+
+        if ty.is_packed() {
+            f.write_char('<')?;
+        }
+
+        if ty.field_tys().is_empty() {
+            f.write_str("{}")?;
+        } else {
+            f.write_str("{ ")?;
+            self.write_list(f, ty.field_tys().iter().copied(), Self::write_ty)?;
+            f.write_str(" }")?;
+        }
+
+        if ty.is_packed() {
+            f.write_char('>')?;
+        }
+
+        Ok(())
+    }
+
+    fn write_list<T: Copy>(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        items: impl IntoIterator<Item = T>,
+        mut write: impl FnMut(&Self, &mut fmt::Formatter<'_>, T) -> fmt::Result,
+    ) -> fmt::Result {
+        for (i, item) in items.into_iter().enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write(self, f, item)?;
+        }
+        Ok(())
+    }
+
+    fn write_ty(&self, f: &mut fmt::Formatter<'_>, ty: Type<'ctx>) -> fmt::Result {
+        match ty.unpack() {
+            UnpackedType::Unit(_) => f.write_str("void"),
+            UnpackedType::Integer(ty) => write!(f, "i{}", ty.bits()),
+            UnpackedType::Float(ty) => f.write_str(float_kind_name(ty.kind())),
+            UnpackedType::Pointer(ty) => {
+                f.write_str("ptr")?;
+                write_addrspace(f, ty.address_space())
+            }
+            UnpackedType::Function(ty) => {
+                self.write_ty(f, ty.output_ty())?;
+                f.write_str(" (")?;
+                self.write_list(f, ty.arguments_tys().iter().copied(), Self::write_ty)?;
+                f.write_char(')')
+            }
+            UnpackedType::Array(ty) => {
+                write!(f, "[{} x ", ty.len())?;
+                self.write_ty(f, ty.item_ty())?;
+                f.write_char(']')
+            }
+            UnpackedType::Vector(ty) => {
+                let vscale = if ty.is_scalable() { "vscale x " } else { "" };
+                write!(f, "<{vscale}{} x ", ty.len())?;
+                self.write_ty(f, ty.item_ty())?;
+                f.write_char('>')
+            }
+            UnpackedType::Struct(ty) if ty.is_identified() => self.write_struct_name(f, ty),
+            UnpackedType::Struct(ty) => {
+                // LLVM doesn't have named literal structs
+                if let Some(name) = ty.name().filter(|_| !self.llvm) {
+                    f.write_char('.')?;
+                    write_name(f, &name.to_string())?;
+                    f.write_char(' ')?;
+                }
+                self.write_struct_body(f, ty)
+            }
+        }
+    }
+
+    fn write_local(&self, f: &mut fmt::Formatter<'_>, name: Option<&LocalName>) -> fmt::Result {
+        match name {
+            Some(LocalName::Slot(slot)) => write!(f, "{slot}"),
+            Some(LocalName::Named(name)) => write_name(f, name),
+            None => f.write_str("<unnamed>"),
+        }
+    }
+
+    fn write_global_name(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        value: Value<'ctx>,
+        name: Option<istr::IStr>,
+    ) -> fmt::Result {
+        f.write_char('@')?;
+        match (name, self.globals.get(&value)) {
+            (Some(name), _) => write_name(f, &name.to_string()),
+            (None, Some(slot)) => write!(f, "{slot}"),
+            (None, None) => f.write_str("<unnamed>"),
+        }
+    }
+
+    /// Write a value without its type, as it appears as an operand
+    fn write_operand(&self, f: &mut fmt::Formatter<'_>, value: Value<'ctx>) -> fmt::Result {
+        match value.unpack() {
+            UnpackedValue::ConstInt(value) if value.bits() == 1 => {
+                f.write_str(if value.is_zero() { "false" } else { "true" })
+            }
+            UnpackedValue::ConstInt(value) => write!(f, "{}", value.as_signed()),
+            UnpackedValue::ConstFloat(value) => write_float(f, value),
+            UnpackedValue::ConstArray(value) => {
+                f.write_char('[')?;
+                self.write_list(f, value.items().iter().copied(), Self::write_typed)?;
+                f.write_char(']')
+            }
+            UnpackedValue::ConstStruct(value) => {
+                let ty = value.ty().cast::<crate::types::StructInfo>();
+                if ty.is_packed() {
+                    f.write_char('<')?;
+                }
+                if value.items().is_empty() {
+                    f.write_str("{}")?;
+                } else {
+                    f.write_str("{ ")?;
+                    self.write_list(f, value.items().iter().copied(), Self::write_typed)?;
+                    f.write_str(" }")?;
+                }
+                if ty.is_packed() {
+                    f.write_char('>')?;
+                }
+                Ok(())
+            }
+            UnpackedValue::ConstVector(value) => {
+                f.write_char('<')?;
+                self.write_list(f, value.items().iter().copied(), Self::write_typed)?;
+                f.write_char('>')
+            }
+            UnpackedValue::ConstAggrZero(_) => f.write_str("zeroinitializer"),
+            UnpackedValue::ConstNull(_) => f.write_str("null"),
+            UnpackedValue::Undef(_) => f.write_str("undef"),
+            UnpackedValue::Poison(_) => f.write_str("poison"),
+            UnpackedValue::ConstExpr(value) => self.write_const_expr(f, value),
+            UnpackedValue::GlobalVariable(global) => {
+                self.write_global_name(f, value, global.name())
+            }
+            UnpackedValue::Function(function) => self.write_global_name(f, value, function.name()),
+            UnpackedValue::Argument(_) | UnpackedValue::Instruction(_) => {
+                f.write_char('%')?;
+                self.write_local(f, self.locals.get(&value))
+            }
+        }
+    }
+
+    /// Write a value with its type, as in `i32 1`
+    fn write_typed(&self, f: &mut fmt::Formatter<'_>, value: Value<'ctx>) -> fmt::Result {
+        self.write_ty(f, value.ty())?;
+        f.write_char(' ')?;
+        self.write_operand(f, value)
+    }
+
+    fn write_const_expr(&self, f: &mut fmt::Formatter<'_>, value: ConstExpr<'ctx>) -> fmt::Result {
+        let opcode = value.opcode();
+        let operands = value.operands();
+
+        f.write_str(opcode.name())?;
+        match opcode {
+            ConstOpcode::GetElementPtr {
+                source_ty,
+                inbounds,
+            } => {
+                if inbounds {
+                    f.write_str(" inbounds")?;
+                }
+                f.write_str(" (")?;
+                self.write_ty(f, source_ty)?;
+                for &operand in operands {
+                    f.write_str(", ")?;
+                    self.write_typed(f, operand)?;
+                }
+                f.write_char(')')
+            }
+            _ if opcode.is_cast() => {
+                f.write_str(" (")?;
+                self.write_typed(f, operands[0])?;
+                f.write_str(" to ")?;
+                self.write_ty(f, value.ty())?;
+                f.write_char(')')
+            }
+            _ => {
+                f.write_str(" (")?;
+                self.write_list(f, operands.iter().copied(), Self::write_typed)?;
+                f.write_char(')')
+            }
+        }
+    }
+
+    fn write_label(&self, f: &mut fmt::Formatter<'_>, block: BasicBlock<'ctx>) -> fmt::Result {
+        f.write_str("label %")?;
+        self.write_local(f, self.blocks.get(&block))
+    }
+
+    fn write_align(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        align: Option<crate::layout::Align>,
+    ) -> fmt::Result {
+        match align {
+            Some(align) => write!(f, ", align {}", align.bytes()),
+            None => Ok(()),
+        }
+    }
+
+    /// ` T a, b`, the operands of binary operations and comparisons
+    fn write_binary_operands(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        operands: &[Value<'ctx>],
+    ) -> fmt::Result {
+        f.write_char(' ')?;
+        self.write_typed(f, operands[0])?;
+        f.write_str(", ")?;
+        self.write_operand(f, operands[1])
+    }
+
+    fn write_instruction(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        inst: Instruction<'ctx>,
+    ) -> fmt::Result {
+        if inst.ty().tag() != TypeTag::Unit {
+            f.write_char('%')?;
+            self.write_local(f, self.locals.get(&inst.erase()))?;
+            f.write_str(" = ")?;
+        }
+
+        let opcode = inst.opcode();
+        let operands = inst.operands().collect::<Vec<_>>();
+        let blocks = inst.blocks().collect::<Vec<_>>();
+
+        f.write_str(opcode.name())?;
+        match opcode {
+            Opcode::IntBinary { op, flags } => {
+                if matches!(
+                    op,
+                    IntBinOp::Add | IntBinOp::Sub | IntBinOp::Mul | IntBinOp::Shl
+                ) {
+                    if flags.nuw {
+                        f.write_str(" nuw")?;
+                    }
+                    if flags.nsw {
+                        f.write_str(" nsw")?;
+                    }
+                }
+                let exact_ops = [
+                    IntBinOp::UDiv,
+                    IntBinOp::SDiv,
+                    IntBinOp::LShr,
+                    IntBinOp::AShr,
+                ];
+                if flags.exact && exact_ops.contains(&op) {
+                    f.write_str(" exact")?;
+                }
+
+                self.write_binary_operands(f, &operands)
+            }
+            Opcode::ICmp(_) | Opcode::FCmp(_) | Opcode::FloatBinary(_) => {
+                let pred = match opcode {
+                    Opcode::ICmp(pred) => Some(pred.name()),
+                    Opcode::FCmp(pred) => Some(pred.name()),
+                    _ => None,
+                };
+                if let Some(pred) = pred {
+                    write!(f, " {pred}")?;
+                }
+
+                self.write_binary_operands(f, &operands)
+            }
+            Opcode::FNeg => {
+                f.write_char(' ')?;
+                self.write_typed(f, operands[0])
+            }
+            Opcode::Cast { ty, .. } => {
+                f.write_char(' ')?;
+                self.write_typed(f, operands[0])?;
+                f.write_str(" to ")?;
+                self.write_ty(f, ty)
+            }
+            Opcode::Alloca {
+                allocated_ty,
+                align,
+            } => {
+                f.write_char(' ')?;
+                self.write_ty(f, allocated_ty)?;
+                let len = operands[0];
+                let is_one = match len.unpack() {
+                    UnpackedValue::ConstInt(len) => len.bits() == 32 && *len.as_unsigned() == 1,
+                    _ => false,
+                };
+                if !is_one {
+                    f.write_str(", ")?;
+                    self.write_typed(f, len)?;
+                }
+                self.write_align(f, align)
+            }
+            Opcode::Load { ty, align } => {
+                f.write_char(' ')?;
+                self.write_ty(f, ty)?;
+                f.write_str(", ")?;
+                self.write_typed(f, operands[0])?;
+                self.write_align(f, align)
+            }
+            Opcode::Store { align } => {
+                f.write_char(' ')?;
+                self.write_typed(f, operands[0])?;
+                f.write_str(", ")?;
+                self.write_typed(f, operands[1])?;
+                self.write_align(f, align)
+            }
+            Opcode::GetElementPtr {
+                source_ty,
+                inbounds,
+            } => {
+                if inbounds {
+                    f.write_str(" inbounds")?;
+                }
+                f.write_char(' ')?;
+                self.write_ty(f, source_ty)?;
+                for &operand in &operands {
+                    f.write_str(", ")?;
+                    self.write_typed(f, operand)?;
+                }
+                Ok(())
+            }
+            Opcode::Call { function_ty } => {
+                f.write_char(' ')?;
+                self.write_ty(f, function_ty.output_ty())?;
+                f.write_char(' ')?;
+                self.write_operand(f, operands[0])?;
+                f.write_char('(')?;
+                self.write_list(f, operands[1..].iter().copied(), Self::write_typed)?;
+                f.write_char(')')
+            }
+            Opcode::Select => {
+                f.write_char(' ')?;
+                self.write_list(f, operands, Self::write_typed)
+            }
+            Opcode::Phi { ty } => {
+                f.write_char(' ')?;
+                self.write_ty(f, ty)?;
+                f.write_char(' ')?;
+                let incoming = operands.iter().copied().zip(blocks.iter().copied());
+                self.write_list(f, incoming, |this, f, (value, block)| {
+                    f.write_str("[ ")?;
+                    this.write_operand(f, value)?;
+                    f.write_str(", %")?;
+                    this.write_local(f, this.blocks.get(&block))?;
+                    f.write_str(" ]")
+                })
+            }
+            Opcode::Br => {
+                f.write_char(' ')?;
+                self.write_label(f, blocks[0])
+            }
+            Opcode::CondBr => {
+                f.write_char(' ')?;
+                self.write_typed(f, operands[0])?;
+                f.write_str(", ")?;
+                self.write_label(f, blocks[0])?;
+                f.write_str(", ")?;
+                self.write_label(f, blocks[1])
+            }
+            Opcode::Switch => {
+                f.write_char(' ')?;
+                self.write_typed(f, operands[0])?;
+                f.write_str(", ")?;
+                self.write_label(f, blocks[0])?;
+                f.write_str(" [")?;
+                for (&case, &target) in operands[1..].iter().zip(&blocks[1..]) {
+                    f.write_str("\n    ")?;
+                    self.write_typed(f, case)?;
+                    f.write_str(", ")?;
+                    self.write_label(f, target)?;
+                }
+                f.write_str("\n  ]")
+            }
+            Opcode::Ret => match operands.first() {
+                Some(&value) => {
+                    f.write_char(' ')?;
+                    self.write_typed(f, value)
+                }
+                None => f.write_str(" void"),
+            },
+            Opcode::Unreachable => Ok(()),
+        }
+    }
+
+    fn write_block(&self, f: &mut fmt::Formatter<'_>, block: BasicBlock<'ctx>) -> fmt::Result {
+        self.write_local(f, self.blocks.get(&block))?;
+        f.write_str(":\n")?;
+        for inst in block.instructions() {
+            f.write_str("  ")?;
+            self.write_instruction(f, inst)?;
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    fn write_global(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        global: GlobalVariable<'ctx>,
+    ) -> fmt::Result {
+        self.write_global_name(f, global.erase(), global.name())?;
+        f.write_str(" =")?;
+
+        let linkage = global.linkage();
+        if linkage != Linkage::External || global.is_declaration() {
+            write!(f, " {}", linkage.name())?;
+        }
+        write_addrspace(f, global.address_space())?;
+
+        f.write_str(if global.is_constant() {
+            " constant "
+        } else {
+            " global "
+        })?;
+        self.write_ty(f, global.value_ty())?;
+        if let Some(init) = global.initializer() {
+            f.write_char(' ')?;
+            self.write_operand(f, init)?;
+        }
+        self.write_align(f, global.align())
+    }
+
+    fn write_function(&self, f: &mut fmt::Formatter<'_>, function: Function<'ctx>) -> fmt::Result {
+        let declaration = function.is_declaration();
+        f.write_str(if declaration { "declare " } else { "define " })?;
+
+        let linkage = function.linkage();
+        if linkage != Linkage::External {
+            write!(f, "{} ", linkage.name())?;
+        }
+
+        self.write_ty(f, function.output_ty())?;
+        f.write_char(' ')?;
+        self.write_global_name(f, function.erase(), function.name())?;
+        f.write_char('(')?;
+        self.write_list(f, function.arguments().iter().copied(), |this, f, arg| {
+            this.write_ty(f, arg.ty())?;
+            if declaration {
+                return Ok(());
+            }
+            f.write_str(" %")?;
+            this.write_local(f, this.locals.get(&arg.erase()))
+        })?;
+        f.write_char(')')?;
+
+        if declaration {
+            return Ok(());
+        }
+
+        f.write_str(" {\n")?;
+        for (i, block) in function.blocks().enumerate() {
+            if i != 0 {
+                f.write_char('\n')?;
+            }
+            self.write_block(f, block)?;
+        }
+        f.write_char('}')
+    }
+
+    fn write_module(&mut self, f: &mut fmt::Formatter<'_>, module: &Module<'ctx>) -> fmt::Result {
+        let mut first = true;
+        let mut section = |f: &mut fmt::Formatter<'_>| {
+            let result = if first { Ok(()) } else { f.write_char('\n') };
+            first = false;
+            result
+        };
+
+        if module.name().is_some() || self.llvm {
+            section(f)?;
+        }
+
+        if let Some(name) = module.name() {
+            f.write_str("source_filename = \"")?;
+            for byte in name.bytes() {
+                match byte {
+                    b'"' | b'\\' | 0x00..=0x1f | 0x7f..=0xff => write!(f, "\\{byte:02X}")?,
+                    _ => f.write_char(byte as char)?,
+                }
+            }
+            f.write_str("\"\n")?;
+        }
+
+        if self.llvm {
+            writeln!(f, "target datalayout = \"{}\"", module.ctx().target())?;
+        }
+
+        if !self.struct_defs.is_empty() {
+            section(f)?;
+            for &ty in &self.struct_defs {
+                self.write_struct_name(f, ty)?;
+                f.write_str(" = type ")?;
+                self.write_struct_body(f, ty)?;
+                f.write_char('\n')?;
+            }
+        }
+
+        if !module.globals().is_empty() {
+            section(f)?;
+            for &global in module.globals() {
+                self.write_global(f, global)?;
+                f.write_char('\n')?;
+            }
+        }
+
+        for &function in module.functions() {
+            section(f)?;
+            self.number_function(function);
+            self.write_function(f, function)?;
+            f.write_char('\n')?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Type<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Printer::default().write_ty(f, *self)
+    }
+}
+
+impl<'ctx, T: ?Sized + TypeInfo<'ctx>> Display for Ty<'ctx, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.erase().fmt(f)
+    }
+}
+
+/// Instructions, functions and globals are written as their definitions, and other
+/// values with their type, as in `i32 1`
+impl Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let printer = Printer::for_value(*self);
+        match self.unpack() {
+            UnpackedValue::Instruction(inst) => printer.write_instruction(f, inst),
+            UnpackedValue::Function(function) => printer.write_function(f, function),
+            UnpackedValue::GlobalVariable(global) => printer.write_global(f, global),
+            _ => printer.write_typed(f, *self),
+        }
+    }
+}
+
+impl<T: ?Sized + ValueInfo> Display for Val<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.erase().fmt(f)
+    }
+}
+
+impl Display for BasicBlock<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Printer::for_function(self.parent()).write_block(f, *self)
+    }
+}
+
+impl Display for Module<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Printer::for_module(self).write_module(f, self)
+    }
+}
+
+/// Writes a module as LLVM assembly, see [`Module::display_llvm`]
+pub struct DisplayLlvm<'a, 'ctx>(&'a Module<'ctx>);
+
+impl<'ctx> Module<'ctx> {
+    /// The module as LLVM assembly, which can be written to a `.ll` file
+    ///
+    /// This is the same as the `Display` impl, except that the data layout string of the
+    /// target comes after the `source_filename`, and named literal structs are written
+    /// without their names
+    pub fn display_llvm(&self) -> DisplayLlvm<'_, 'ctx> {
+        DisplayLlvm(self)
+    }
+}
+
+impl Display for DisplayLlvm<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut printer = Printer::for_module(self.0);
+        printer.llvm = true;
+        printer.write_module(f, self.0)
+    }
+}
+
+#[test]
+fn test_print() {
+    use crate::{layout::Align, value::IntPredicate, Context, IrBuilder};
+
+    let target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+
+    Context::with(target, |ctx| {
+        let mut module = Module::new(ctx, "test");
+        let i32 = ctx.i32().erase();
+        let ptr = ctx.ptr_ty().erase();
+
+        assert_eq!(ctx.function_ty(i32, &[ptr]).to_string(), "i32 (ptr)");
+        assert_eq!(ctx.vector_ty(4, ctx.f32()).to_string(), "<4 x float>");
+        assert_eq!(
+            ctx.ptr_ty_at(AddressSpace::new(3)).to_string(),
+            "ptr addrspace(3)"
+        );
+        assert_eq!(
+            ctx.const_f64(ctx.f64(), 1.5).to_string(),
+            "double 1.500000e+00"
+        );
+        assert_eq!(
+            ctx.const_f32(ctx.f32(), 0.1).to_string(),
+            "float 0x3FB99999A0000000"
+        );
+
+        let pair = ctx.identified_struct_ty("Pair");
+        let anon = ctx.identified_struct_ty(());
+        ctx.set_struct_body(pair, &[i32, anon.erase()], false);
+        ctx.set_struct_body(anon, &[ptr], true);
+
+        let count = module.add_global("count", i32, AddressSpace::DEFAULT);
+        count.set_linkage(Linkage::Internal);
+        count.set_initializer(Some(ctx.const_u32(ctx.i32(), 0).unwrap().erase()));
+        count.set_align(Align::from_bytes(4));
+
+        let bytes_ty = ctx.array_ty(2, ctx.i8());
+        let bytes = module.add_global((), bytes_ty, AddressSpace::new(1));
+        let items = [1, 255].map(|i| ctx.const_u32(ctx.i8(), i).unwrap().erase());
+        bytes.set_constant(true);
+        bytes.set_initializer(ctx.const_array(bytes_ty, &items));
+
+        module.add_global("table", pair, AddressSpace::DEFAULT);
+
+        let abs = module.add_function("abs", ctx.function_ty(i32, &[i32]));
+        let x = abs.arguments()[0];
+        let entry = ctx.append_basic_block(abs, "entry");
+        let negate = ctx.append_basic_block(abs, ());
+        let exit = ctx.append_basic_block(abs, ());
+
+        let zero = ctx.const_u32(ctx.i32(), 0).unwrap();
+        let mut builder = IrBuilder::at_end(ctx, entry);
+        let negative = builder.build_icmp(IntPredicate::Slt, x, zero);
+        builder.build_cond_br(negative, negate, exit);
+
+        builder.position_at_end(negate);
+        let negated = builder.build_sub(zero, x);
+        builder.build_br(exit);
+
+        builder.position_at_end(exit);
+        let phi = builder.build_phi(i32);
+        ctx.add_phi_incoming(phi, x, entry);
+        ctx.add_phi_incoming(phi, negated, negate);
+        builder.build_ret(phi);
+
+        module.add_function("exit", ctx.function_ty(ctx.unit(), &[i32]));
+
+        assert_eq!(negated.to_string(), "%3 = sub i32 0, %0");
+        assert_eq!(x.to_string(), "i32 %0");
+
+        let expected = r#"source_filename = "test"
+
+%Pair = type { i32, %0 }
+%0 = type <{ ptr }>
+
+@count = internal global i32 0, align 4
+@0 = addrspace(1) constant [2 x i8] [i8 1, i8 -1]
+@table = external global %Pair
+
+define i32 @abs(i32 %0) {
+entry:
+  %1 = icmp slt i32 %0, 0
+  br i1 %1, label %2, label %4
+
+2:
+  %3 = sub i32 0, %0
+  br label %4
+
+4:
+  %5 = phi i32 [ %0, %entry ], [ %3, %2 ]
+  ret i32 %5
+}
+
+declare void @exit(i32)
+"#;
+        assert_eq!(module.to_string(), expected);
+    });
+}
+
+#[test]
+fn test_display_llvm() {
+    use crate::{
+        types::StructFlags,
+        value::{CastOp, IntFlags, IntPredicate},
+        Context, IrBuilder, Target,
+    };
+
+    let layout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128";
+    let target = Target::from_data_layout_str(layout).unwrap();
+
+    Context::with(target, |ctx| {
+        let mut module = Module::new(ctx, "golden");
+        let (i8, i32, i64) = (ctx.i8(), ctx.i32(), ctx.i64());
+        let ptr = ctx.ptr_ty();
+
+        let payload_ty = ctx.vector_ty(4, ctx.f32()).erase();
+        let payload = ctx.struct_ty("Payload", StructFlags::default(), &[i8.erase(), payload_ty]);
+        assert_eq!(payload.to_string(), ".Payload { i8, <4 x float> }");
+
+        let node = ctx.identified_struct_ty("Node");
+        ctx.set_struct_body(node, &[i64.erase(), ptr.erase(), payload.erase()], false);
+        let flags_ty = ctx.identified_struct_ty(());
+        let bits_ty = ctx.array_ty(3, ctx.i16()).erase();
+        ctx.set_struct_body(flags_ty, &[ctx.i1().erase(), bits_ty], true);
+
+        let count = module.add_global("count", i64, AddressSpace::DEFAULT);
+        count.set_linkage(Linkage::Internal);
+        count.set_initializer(Some(ctx.const_u32(i64, 0).unwrap().erase()));
+        count.set_align(crate::layout::Align::from_bytes(8));
+
+        let pair_ty = ctx.struct_ty((), StructTy::PACKED, &[i8.erase(), i32.erase()]);
+        let pair = module.add_global((), pair_ty, AddressSpace::DEFAULT);
+        let fields = [
+            ctx.const_u32(i8, 1).unwrap().erase(),
+            ctx.const_u32(i32, u32::MAX).unwrap().erase(),
+        ];
+        pair.set_linkage(Linkage::Private);
+        pair.set_constant(true);
+        pair.set_initializer(ctx.const_struct(pair_ty, &fields));
+
+        let nodes_ty = ctx.array_ty(2, node);
+        let nodes = module.add_global("nodes", nodes_ty, AddressSpace::DEFAULT);
+        nodes.set_initializer(ctx.const_aggr_zero(nodes_ty).map(|zero| zero.erase()));
+
+        let flags = module.add_global("flags", flags_ty, AddressSpace::new(1));
+        flags.set_initializer(Some(ctx.undef(flags_ty).unwrap().erase()));
+
+        let ones_ty = ctx.struct_ty(
+            (),
+            StructFlags::default(),
+            &[ctx.f16().erase(), ctx.x86_fp80().erase()],
+        );
+        let ones = module.add_global("ones", ones_ty, AddressSpace::DEFAULT);
+        let fields = [
+            ctx.const_float_from_bits(ctx.f16(), 0x3c00).erase(),
+            ctx.const_float_from_bits(ctx.x86_fp80(), 0x3fff_8000_0000_0000_0000)
+                .erase(),
+        ];
+        ones.set_initializer(ctx.const_struct(ones_ty, &fields));
+
+        let second = module.add_global("second", ptr, AddressSpace::DEFAULT);
+        let one = ctx.const_u32(i64, 1).unwrap().erase();
+        second.set_initializer(ctx.const_inbounds_gep(node, nodes, &[one]));
+
+        let callback = module.add_global("callback", ptr, AddressSpace::DEFAULT);
+
+        let visit = module.add_function("visit", ctx.function_ty(i64, &[ptr.erase(), i32.erase()]));
+        let [node_arg, depth] = [0, 1].map(|i| visit.arguments()[i]);
+        node_arg.set_name("node");
+        depth.set_name("depth");
+        callback.set_initializer(Some(visit.erase()));
+
+        let entry = ctx.append_basic_block(visit, "entry");
+        let builder = IrBuilder::at_end(ctx, entry);
+        let zero_i32 = ctx.const_u32(i32, 0).unwrap().erase();
+        let zero_i64 = ctx.const_u32(i64, 0).unwrap().erase();
+        let field = builder.build_gep(node, node_arg, &[zero_i64, zero_i32]);
+        let value = builder.build_load(i64, field);
+        let depth = builder.build_cast(CastOp::SExt, depth, i64);
+        let nsw = IntFlags {
+            nsw: true,
+            ..IntFlags::NONE
+        };
+        let sum = builder.build_int_binop(IntBinOp::Add, nsw, value, depth);
+        let is_null = builder.build_icmp(IntPredicate::Eq, node_arg, ctx.const_null(ptr));
+        let result = builder.build_select(is_null, zero_i64, sum);
+        builder.build_ret(result);
+
+        module.add_function("free", ctx.function_ty(ctx.unit(), &[ptr.erase()]));
+
+        let expected = include_str!("../tests/golden/visit.ll");
+        assert_eq!(module.display_llvm().to_string(), expected);
+
+        // only the header and named literal structs differ from the `Display` impl
+        let text = module.to_string();
+        assert!(text.contains("%Node = type { i64, ptr, .Payload { i8, <4 x float> } }"));
+        assert!(!text.contains("target datalayout"));
+    });
+}
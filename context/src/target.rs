@@ -0,0 +1,67 @@
+use crate::layout::LayoutSpec;
+
+mod data_layout_str;
+mod presets;
+
+pub use data_layout_str::DataLayoutError;
+pub use presets::PRESET_TRIPLES;
+
+/// A description of the machine being compiled for
+///
+/// `ptr_diff_bits` and `ptr_size_bits` decide which integer types
+/// [`Context::isize`](crate::Context::isize) and [`Context::iptr`](crate::Context::iptr) are,
+/// everything about the size and alignment of types lives in [`Target::layout`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub ptr_diff_bits: PtrBits,
+    pub ptr_size_bits: PtrBits,
+    pub endianness: Endianness,
+    pub layout: LayoutSpec,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+#[repr(u16)]
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PtrBits {
+    _8 = 8,
+    _16 = 16,
+    _32 = 32,
+    _64 = 64,
+    _128 = 128,
+}
+
+impl PtrBits {
+    #[inline]
+    pub const fn bits(self) -> u32 {
+        self as u32
+    }
+
+    pub const fn from_bits(bits: u32) -> Option<Self> {
+        Some(match bits {
+            8 => Self::_8,
+            16 => Self::_16,
+            32 => Self::_32,
+            64 => Self::_64,
+            128 => Self::_128,
+            _ => return None,
+        })
+    }
+}
+
+impl Target {
+    /// A little endian target with the default layout for the given pointer sizes
+    pub fn new(ptr_diff_bits: PtrBits, ptr_size_bits: PtrBits) -> Self {
+        Self {
+            ptr_diff_bits,
+            ptr_size_bits,
+            endianness: Endianness::Little,
+            layout: LayoutSpec::new(ptr_size_bits.bits(), ptr_diff_bits.bits()),
+        }
+    }
+}
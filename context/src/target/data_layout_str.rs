@@ -0,0 +1,507 @@
+use core::fmt;
+use std::{collections::BTreeMap, str::FromStr};
+
+use crate::layout::{Align, AlignSpec, FunctionPtrAlign, LayoutSpec, Mangling, PointerSpec};
+
+use super::{Endianness, PtrBits, Target};
+
+/// An error from parsing an LLVM-style data layout string
+///
+/// Each variant carries the `-` separated specification it was found in
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataLayoutError {
+    /// The specification doesn't start with a known letter
+    UnknownSpec {
+        spec: String,
+    },
+    /// A field isn't a decimal number
+    InvalidNumber {
+        spec: String,
+        field: String,
+    },
+    MissingField {
+        spec: String,
+    },
+    TooManyFields {
+        spec: String,
+    },
+    /// An alignment isn't a power of two number of bytes
+    InvalidAlignment {
+        spec: String,
+        bits: u32,
+    },
+    /// The preferred alignment is less than the ABI alignment
+    PrefAlignTooSmall {
+        spec: String,
+    },
+    /// A type or pointer width is zero
+    ZeroWidth {
+        spec: String,
+    },
+    /// The address space doesn't fit in 24 bits, or can't be used in this position
+    InvalidAddressSpace {
+        spec: String,
+        address_space: u32,
+    },
+    /// The pointer or index size of the default address space isn't a [`PtrBits`]
+    UnsupportedPointerSize {
+        spec: String,
+        bits: u32,
+    },
+    /// The index size is larger than the pointer size
+    InvalidIndexSize {
+        spec: String,
+    },
+    InvalidMangling {
+        spec: String,
+    },
+}
+
+impl fmt::Display for DataLayoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSpec { spec } => write!(f, "unknown data layout specification `{spec}`"),
+            Self::InvalidNumber { spec, field } => {
+                write!(f, "`{field}` is not a valid number in `{spec}`")
+            }
+            Self::MissingField { spec } => write!(f, "missing a field in `{spec}`"),
+            Self::TooManyFields { spec } => write!(f, "too many fields in `{spec}`"),
+            Self::InvalidAlignment { spec, bits } => write!(
+                f,
+                "alignment of {bits} bits in `{spec}` is not a power of two number of bytes"
+            ),
+            Self::PrefAlignTooSmall { spec } => write!(
+                f,
+                "the preferred alignment is less than the ABI alignment in `{spec}`"
+            ),
+            Self::ZeroWidth { spec } => write!(f, "zero width in `{spec}`"),
+            Self::InvalidAddressSpace {
+                spec,
+                address_space,
+            } => write!(f, "invalid address space {address_space} in `{spec}`"),
+            Self::UnsupportedPointerSize { spec, bits } => {
+                write!(f, "unsupported pointer size of {bits} bits in `{spec}`")
+            }
+            Self::InvalidIndexSize { spec } => write!(
+                f,
+                "the index size is larger than the pointer size in `{spec}`"
+            ),
+            Self::InvalidMangling { spec } => write!(f, "invalid mangling mode in `{spec}`"),
+        }
+    }
+}
+
+impl std::error::Error for DataLayoutError {}
+
+const MAX_ADDRESS_SPACE: u32 = (1 << 24) - 1;
+
+struct Spec<'a>(&'a str);
+
+impl Spec<'_> {
+    fn name(&self) -> String {
+        self.0.to_owned()
+    }
+
+    fn number(&self, field: &str) -> Result<u32, DataLayoutError> {
+        let is_number = !field.is_empty() && field.bytes().all(|b| b.is_ascii_digit());
+
+        match field.parse() {
+            Ok(value) if is_number => Ok(value),
+            _ => Err(DataLayoutError::InvalidNumber {
+                spec: self.name(),
+                field: field.to_owned(),
+            }),
+        }
+    }
+
+    fn width(&self, field: &str) -> Result<u32, DataLayoutError> {
+        match self.number(field)? {
+            0 => Err(DataLayoutError::ZeroWidth { spec: self.name() }),
+            bits => Ok(bits),
+        }
+    }
+
+    fn address_space(&self, field: &str) -> Result<u32, DataLayoutError> {
+        match self.number(field)? {
+            address_space @ 0..=MAX_ADDRESS_SPACE => Ok(address_space),
+            address_space => Err(DataLayoutError::InvalidAddressSpace {
+                spec: self.name(),
+                address_space,
+            }),
+        }
+    }
+
+    fn align(&self, field: &str, allow_zero: bool) -> Result<Align, DataLayoutError> {
+        match self.number(field)? {
+            0 if allow_zero => Ok(Align::ONE),
+            bits => Align::from_bits(bits.into()).ok_or(DataLayoutError::InvalidAlignment {
+                spec: self.name(),
+                bits,
+            }),
+        }
+    }
+
+    /// parses `abi[:pref]`
+    fn align_spec(&self, fields: &[&str], allow_zero: bool) -> Result<AlignSpec, DataLayoutError> {
+        let (abi, pref) = match *fields {
+            [] => return Err(DataLayoutError::MissingField { spec: self.name() }),
+            [abi] => (abi, None),
+            [abi, pref] => (abi, Some(pref)),
+            _ => return Err(DataLayoutError::TooManyFields { spec: self.name() }),
+        };
+
+        let abi = self.align(abi, allow_zero)?;
+        let pref = match pref {
+            Some(pref) => self.align(pref, allow_zero)?,
+            None => abi,
+        };
+
+        if pref < abi {
+            return Err(DataLayoutError::PrefAlignTooSmall { spec: self.name() });
+        }
+
+        Ok(AlignSpec::new(abi, pref))
+    }
+
+    fn no_fields(&self, fields: &[&str]) -> Result<(), DataLayoutError> {
+        if fields.is_empty() {
+            Ok(())
+        } else {
+            Err(DataLayoutError::TooManyFields { spec: self.name() })
+        }
+    }
+}
+
+impl Target {
+    /// Parse an LLVM-style data layout string such as `e-m:e-p:64:64-i64:64-n32:64-S128`
+    ///
+    /// Anything not specified by the string keeps LLVM's default, which is a
+    /// little endian target with 64-bit pointers
+    pub fn from_data_layout_str(layout: &str) -> Result<Self, DataLayoutError> {
+        let mut target = Target::new(PtrBits::_64, PtrBits::_64);
+
+        if layout.is_empty() {
+            return Ok(target);
+        }
+
+        for spec in layout.split('-') {
+            target.parse_spec(Spec(spec))?;
+        }
+
+        Ok(target)
+    }
+
+    fn parse_spec(&mut self, spec: Spec<'_>) -> Result<(), DataLayoutError> {
+        let mut chars = spec.0.chars();
+        let Some(kind) = chars.next() else {
+            return Err(DataLayoutError::UnknownSpec { spec: spec.name() });
+        };
+
+        let mut parts = chars.as_str().split(':');
+        let head = parts.next().unwrap_or_default();
+        let fields = parts.collect::<Vec<_>>();
+        let layout = &mut self.layout;
+
+        match kind {
+            'e' | 'E' => {
+                if !head.is_empty() {
+                    return Err(DataLayoutError::UnknownSpec { spec: spec.name() });
+                }
+                spec.no_fields(&fields)?;
+
+                self.endianness = if kind == 'e' {
+                    Endianness::Little
+                } else {
+                    Endianness::Big
+                };
+            }
+            'm' => {
+                let mangling = match (head, &*fields) {
+                    ("", [code]) => {
+                        let mut code = code.chars();
+                        match (code.next(), code.next()) {
+                            (Some(code), None) => Mangling::from_code(code),
+                            _ => None,
+                        }
+                    }
+                    _ => None,
+                };
+
+                match mangling {
+                    Some(mangling) => layout.mangling = Some(mangling),
+                    None => return Err(DataLayoutError::InvalidMangling { spec: spec.name() }),
+                }
+            }
+            'p' => {
+                let address_space = if head.is_empty() {
+                    0
+                } else {
+                    spec.address_space(head)?
+                };
+
+                // size:abi[:pref[:index]]
+                let (size, align, index) = match fields.len() {
+                    0 | 1 => return Err(DataLayoutError::MissingField { spec: spec.name() }),
+                    2 | 3 => (fields[0], &fields[1..], None),
+                    4 => (fields[0], &fields[1..3], Some(fields[3])),
+                    _ => return Err(DataLayoutError::TooManyFields { spec: spec.name() }),
+                };
+
+                let size_bits = spec.width(size)?;
+                let align = spec.align_spec(align, false)?;
+                let index_bits = match index {
+                    Some(index) => spec.width(index)?,
+                    None => size_bits,
+                };
+
+                if index_bits > size_bits {
+                    return Err(DataLayoutError::InvalidIndexSize { spec: spec.name() });
+                }
+
+                if address_space == 0 {
+                    let ptr_bits = |bits| {
+                        PtrBits::from_bits(bits).ok_or(DataLayoutError::UnsupportedPointerSize {
+                            spec: spec.name(),
+                            bits,
+                        })
+                    };
+
+                    self.ptr_size_bits = ptr_bits(size_bits)?;
+                    self.ptr_diff_bits = ptr_bits(index_bits)?;
+                }
+
+                layout.pointers.insert(
+                    address_space,
+                    PointerSpec {
+                        size_bits,
+                        align,
+                        index_bits,
+                    },
+                );
+            }
+            'i' | 'f' | 'v' => {
+                let bits = spec.width(head)?;
+                let align = spec.align_spec(&fields, false)?;
+
+                let table = match kind {
+                    'i' => &mut layout.ints,
+                    'f' => &mut layout.floats,
+                    _ => &mut layout.vectors,
+                };
+
+                table.insert(bits, align);
+            }
+            'a' => {
+                // `a0` is an old spelling of `a`
+                if !matches!(head, "" | "0") {
+                    return Err(DataLayoutError::UnknownSpec { spec: spec.name() });
+                }
+
+                layout.aggregate = spec.align_spec(&fields, true)?;
+            }
+            'n' if head == "i" => {
+                let address_spaces = fields
+                    .iter()
+                    .map(|field| match spec.address_space(field)? {
+                        0 => Err(DataLayoutError::InvalidAddressSpace {
+                            spec: spec.name(),
+                            address_space: 0,
+                        }),
+                        address_space => Ok(address_space),
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if address_spaces.is_empty() {
+                    return Err(DataLayoutError::MissingField { spec: spec.name() });
+                }
+
+                layout.non_integral_address_spaces = address_spaces;
+            }
+            'n' => {
+                layout.native_ints = core::iter::once(head)
+                    .chain(fields.iter().copied())
+                    .map(|field| spec.width(field))
+                    .collect::<Result<_, _>>()?;
+            }
+            'S' => {
+                spec.no_fields(&fields)?;
+                layout.stack_align = match spec.number(head)? {
+                    0 => None,
+                    _ => Some(spec.align(head, false)?),
+                };
+            }
+            'A' | 'P' | 'G' => {
+                spec.no_fields(&fields)?;
+                let address_space = spec.address_space(head)?;
+
+                match kind {
+                    'A' => layout.alloca_address_space = address_space,
+                    'P' => layout.program_address_space = address_space,
+                    _ => layout.global_address_space = address_space,
+                }
+            }
+            'F' => {
+                spec.no_fields(&fields)?;
+
+                let independent = match head.chars().next() {
+                    Some('i') => true,
+                    Some('n') => false,
+                    _ => return Err(DataLayoutError::UnknownSpec { spec: spec.name() }),
+                };
+
+                layout.function_ptr_align = Some(FunctionPtrAlign {
+                    align: spec.align(&head[1..], false)?,
+                    independent,
+                });
+            }
+            _ => return Err(DataLayoutError::UnknownSpec { spec: spec.name() }),
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for Target {
+    type Err = DataLayoutError;
+
+    fn from_str(layout: &str) -> Result<Self, Self::Err> {
+        Self::from_data_layout_str(layout)
+    }
+}
+
+fn write_align_spec(f: &mut fmt::Formatter<'_>, align: AlignSpec) -> fmt::Result {
+    write!(f, ":{}", align.abi.bits())?;
+    if align.pref != align.abi {
+        write!(f, ":{}", align.pref.bits())?;
+    }
+    Ok(())
+}
+
+fn write_align_table(
+    f: &mut fmt::Formatter<'_>,
+    kind: char,
+    table: &BTreeMap<u32, AlignSpec>,
+    defaults: &BTreeMap<u32, AlignSpec>,
+) -> fmt::Result {
+    for (&bits, &align) in table {
+        if defaults.get(&bits) != Some(&align) {
+            write!(f, "-{kind}{bits}")?;
+            write_align_spec(f, align)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_list(f: &mut fmt::Formatter<'_>, prefix: &str, items: &[u32]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i == 0 {
+            write!(f, "{prefix}{item}")?;
+        } else {
+            write!(f, ":{item}")?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the data layout string of this target
+///
+/// Only the parts of the layout that differ from LLVM's defaults are written,
+/// so parsing the string with [`Target::from_data_layout_str`] gives back an equal `Target`
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let defaults = LayoutSpec::new(64, 64);
+        let layout = &self.layout;
+
+        f.write_str(match self.endianness {
+            Endianness::Little => "e",
+            Endianness::Big => "E",
+        })?;
+
+        if let Some(mangling) = layout.mangling {
+            write!(f, "-m:{}", mangling.code())?;
+        }
+
+        for (&address_space, &pointer) in &layout.pointers {
+            if defaults.pointers.get(&address_space) == Some(&pointer) {
+                continue;
+            }
+
+            f.write_str("-p")?;
+            if address_space != 0 {
+                write!(f, "{address_space}")?;
+            }
+            write!(f, ":{}:{}", pointer.size_bits, pointer.align.abi.bits())?;
+
+            if pointer.index_bits != pointer.size_bits {
+                write!(f, ":{}:{}", pointer.align.pref.bits(), pointer.index_bits)?;
+            } else if pointer.align.pref != pointer.align.abi {
+                write!(f, ":{}", pointer.align.pref.bits())?;
+            }
+        }
+
+        write_align_table(f, 'i', &layout.ints, &defaults.ints)?;
+        write_align_table(f, 'f', &layout.floats, &defaults.floats)?;
+        write_align_table(f, 'v', &layout.vectors, &defaults.vectors)?;
+
+        if layout.aggregate != defaults.aggregate {
+            f.write_str("-a")?;
+            write_align_spec(f, layout.aggregate)?;
+        }
+
+        if let Some(function_ptr_align) = layout.function_ptr_align {
+            let kind = if function_ptr_align.independent {
+                'i'
+            } else {
+                'n'
+            };
+            write!(f, "-F{kind}{}", function_ptr_align.align.bits())?;
+        }
+
+        write_list(f, "-n", &layout.native_ints)?;
+        write_list(f, "-ni:", &layout.non_integral_address_spaces)?;
+
+        if let Some(stack_align) = layout.stack_align {
+            write!(f, "-S{}", stack_align.bits())?;
+        }
+
+        for (kind, address_space) in [
+            ('A', layout.alloca_address_space),
+            ('P', layout.program_address_space),
+            ('G', layout.global_address_space),
+        ] {
+            if address_space != 0 {
+                write!(f, "-{kind}{address_space}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_round_trip() {
+    let layout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128";
+    let target = Target::from_data_layout_str(layout).unwrap();
+
+    assert_eq!(target.to_string(), layout);
+    assert_eq!(target.endianness, Endianness::Little);
+    assert_eq!(target.ptr_size_bits, PtrBits::_64);
+    assert_eq!(target.layout.native_ints, [8, 16, 32, 64]);
+    assert_eq!(target.layout.pointer(271).size_bits, 32);
+
+    let target = Target::from_data_layout_str("E-p:32:32:32:16-a:0:32-ni:1").unwrap();
+    assert_eq!(target.ptr_size_bits, PtrBits::_32);
+    assert_eq!(target.ptr_diff_bits, PtrBits::_16);
+    assert_eq!(target.to_string().parse::<Target>(), Ok(target));
+
+    assert_eq!(
+        Target::from_data_layout_str("e-i64:48"),
+        Err(DataLayoutError::InvalidAlignment {
+            spec: "i64:48".to_owned(),
+            bits: 48
+        })
+    );
+    assert!(Target::from_data_layout_str("e-p:24:32").is_err());
+    assert!(Target::from_data_layout_str("e-q").is_err());
+}
@@ -0,0 +1,116 @@
+use super::Target;
+
+/// The canonical triples of the built-in target presets
+///
+/// [`Target::from_triple`] also accepts other vendors, operating systems and
+/// common aliases (such as `amd64` or `arm64`) for these architectures
+pub const PRESET_TRIPLES: &[&str] = &[
+    "x86_64-unknown-linux-gnu",
+    "x86_64-apple-darwin",
+    "x86_64-pc-windows-msvc",
+    "i686-unknown-linux-gnu",
+    "i686-pc-windows-msvc",
+    "aarch64-unknown-linux-gnu",
+    "aarch64-apple-darwin",
+    "armv7-unknown-linux-gnueabihf",
+    "riscv32-unknown-none-elf",
+    "riscv64-unknown-linux-gnu",
+    "wasm32-unknown-unknown",
+    "wasm64-unknown-unknown",
+    "powerpc64le-unknown-linux-gnu",
+    "powerpc64-unknown-linux-gnu",
+    "s390x-unknown-linux-gnu",
+    "avr-unknown-unknown",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ObjectFormat {
+    Elf,
+    MachO,
+    Coff,
+}
+
+/// The default LLVM data layout for a target triple
+fn data_layout_of(triple: &str) -> Option<&'static str> {
+    let mut parts = triple.split('-');
+    let arch = parts.next()?;
+    let rest = parts.collect::<Vec<_>>();
+
+    let has = |prefixes: &[&str]| {
+        rest.iter()
+            .any(|part| prefixes.iter().any(|prefix| part.starts_with(prefix)))
+    };
+
+    let format = if has(&["apple", "darwin", "macos", "ios", "tvos", "watchos"]) {
+        ObjectFormat::MachO
+    } else if has(&["windows", "win32"]) {
+        ObjectFormat::Coff
+    } else {
+        ObjectFormat::Elf
+    };
+
+    Some(match arch {
+        "x86_64" | "amd64" => match format {
+            ObjectFormat::Elf => "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128",
+            ObjectFormat::MachO => "e-m:o-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128",
+            ObjectFormat::Coff => "e-m:w-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128",
+        },
+        "i386" | "i486" | "i586" | "i686" | "x86" => match format {
+            ObjectFormat::Elf => "e-m:e-p:32:32-p270:32:32-p271:32:32-p272:64:64-i128:128-f64:32:64-f80:32-n8:16:32-S128",
+            ObjectFormat::MachO => "e-m:o-p:32:32-p270:32:32-p271:32:32-p272:64:64-i128:128-f64:32:64-f80:128-n8:16:32-S128",
+            ObjectFormat::Coff => "e-m:x-p:32:32-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:32-n8:16:32-a:0:32-S32",
+        },
+        "aarch64" | "arm64" => match format {
+            ObjectFormat::MachO => "e-m:o-i64:64-i128:128-n32:64-S128",
+            ObjectFormat::Coff => "e-m:w-p270:32:32-p271:32:32-p272:64:64-p:64:64-i32:32-i64:64-i128:128-n32:64-S128",
+            ObjectFormat::Elf => "e-m:e-i8:8:32-i16:16:32-i64:64-i128:128-n32:64-S128",
+        },
+        "arm" | "thumb" => "e-m:e-p:32:32-Fi8-i64:64-v128:64:128-a:0:32-n32-S64",
+        arch if arch.starts_with("armv") || arch.starts_with("thumbv") => {
+            "e-m:e-p:32:32-Fi8-i64:64-v128:64:128-a:0:32-n32-S64"
+        }
+        "riscv32" => "e-m:e-p:32:32-i64:64-n32-S128",
+        "riscv64" => "e-m:e-p:64:64-i64:64-i128:128-n32:64-S128",
+        "wasm32" => "e-m:e-p:32:32-p10:8:8-p20:8:8-i64:64-n32:64-S128-ni:1:10:20",
+        "wasm64" => "e-m:e-p:64:64-p10:8:8-p20:8:8-i64:64-n32:64-S128-ni:1:10:20",
+        "powerpc64le" | "ppc64le" => "e-m:e-Fn32-i64:64-n32:64-S128-v256:256:512-v512:512:512",
+        "powerpc64" | "ppc64" => "E-m:e-Fi64-i64:64-n32:64-S128-v256:256:512-v512:512:512",
+        "s390x" | "systemz" => "E-m:e-i1:8:16-i8:8:16-i64:64-f128:64-v128:64-a:8:16-n32:64",
+        "avr" => "e-P1-p:16:8-i8:8-i16:8-i32:8-i64:8-f32:8-f64:8-n8-a:8",
+        _ => return None,
+    })
+}
+
+impl Target {
+    /// The built-in preset for a target triple such as `x86_64-unknown-linux-gnu`
+    ///
+    /// Returns `None` if the architecture isn't known, see [`PRESET_TRIPLES`]
+    pub fn from_triple(triple: &str) -> Option<Self> {
+        let layout = data_layout_of(triple)?;
+
+        match Self::from_data_layout_str(layout) {
+            Ok(target) => Some(target),
+            Err(err) => unreachable!("invalid data layout for {triple}: {err}"),
+        }
+    }
+}
+
+#[test]
+fn test_presets() {
+    for triple in PRESET_TRIPLES {
+        assert!(Target::from_triple(triple).is_some(), "{triple}");
+    }
+
+    let x86_64 = Target::from_triple("x86_64-unknown-linux-gnu").unwrap();
+    assert_eq!(x86_64.ptr_size_bits, super::PtrBits::_64);
+    assert_eq!(x86_64, Target::from_triple("amd64-linux").unwrap());
+
+    let avr = Target::from_triple("avr-unknown-unknown").unwrap();
+    assert_eq!(avr.ptr_size_bits, super::PtrBits::_16);
+    assert_eq!(avr.layout.program_address_space, 1);
+
+    let s390x = Target::from_triple("s390x-unknown-linux-gnu").unwrap();
+    assert_eq!(s390x.endianness, super::Endianness::Big);
+
+    assert!(Target::from_triple("z80-unknown-none").is_none());
+}
@@ -9,7 +9,9 @@ mod int;
 mod ptr;
 mod raw_type;
 mod struct_ty;
+mod type_size;
 mod unit;
+mod vector;
 
 pub(crate) use type_cache::TypeCache;
 
@@ -20,12 +22,17 @@ pub use float::{FloatKind, FloatTy};
 pub use function::FunctionTy;
 pub use int::IntegerTy;
 pub use ptr::PointerTy;
-pub use raw_type::{Ty, Type};
+pub use raw_type::{Ty, Type, TypeTag, UnpackedType};
 pub use struct_ty::{StructFlags, StructTy};
+pub use type_size::TypeSize;
 pub use unit::UnitTy;
+pub use vector::VectorTy;
 
 pub(crate) use array::{ArrayInfo, ArrayInit};
+pub(crate) use float::FloatInfo;
 pub(crate) use function::{FunctionInfo, FunctionInit};
 pub(crate) use int::IntegerInfo;
 pub(crate) use ptr::PointerInfo;
+pub(crate) use raw_type::TypeInfo;
 pub(crate) use struct_ty::{StructInfo, StructInit};
+pub(crate) use vector::{VectorInfo, VectorInit};
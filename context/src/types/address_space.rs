@@ -2,6 +2,8 @@ use std::hash::Hash;
 
 use init::Ctor;
 
+use crate::{error::unwrap_or_panic, Error};
+
 #[derive(Clone, Copy, Eq)]
 #[allow(non_camel_case_types)]
 pub struct AddressSpace(u8, u8, u8);
@@ -10,17 +12,28 @@ impl AddressSpace {
     pub const DEFAULT: Self = Self(0, 0, 0);
 
     pub fn get(self) -> u32 {
-        u32::from_ne_bytes([0, self.0, self.1, self.2])
+        u32::from_le_bytes([self.0, self.1, self.2, 0])
     }
 
     pub fn is_default(self) -> bool {
         self.get() == 0
     }
 
+    /// # Panics
+    ///
+    /// If `address_space` doesn't fit in 24 bits
+    #[track_caller]
     pub fn new(address_space: u32) -> Self {
-        assert_eq!(address_space & 0xff000000, 0);
+        unwrap_or_panic(Self::try_new(address_space))
+    }
+
+    pub fn try_new(address_space: u32) -> Result<Self, Error> {
+        if address_space & 0xff000000 != 0 {
+            return Err(Error::InvalidAddressSpace { address_space });
+        }
+
         let [a, b, c, _] = u32::to_le_bytes(address_space);
-        Self(a, b, c)
+        Ok(Self(a, b, c))
     }
 }
 
@@ -10,12 +10,85 @@ use super::{
     Ty,
 };
 
-#[allow(clippy::enum_variant_names)]
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum FloatKind {
     Ieee16Bit,
     Ieee32Bit,
     Ieee64Bit,
+    Ieee128Bit,
+    /// The 16-bit "brain" float, an `f32` with a truncated mantissa
+    BFloat16,
+    /// The 80-bit x87 extended precision float, which has an explicit integer bit
+    X86Fp80,
+    /// The PowerPC double-double, a pair of `f64` whose sum is the value
+    PpcFp128,
+}
+
+impl FloatKind {
+    /// The number of explicitly stored mantissa bits, excluding the
+    /// integer bit (even for [`FloatKind::X86Fp80`] which stores it)
+    ///
+    /// For [`FloatKind::PpcFp128`] this is the combined mantissa of both halves
+    pub const fn mantissa_bits(self) -> u32 {
+        match self {
+            FloatKind::Ieee16Bit => 10,
+            FloatKind::Ieee32Bit => 23,
+            FloatKind::Ieee64Bit => 52,
+            FloatKind::Ieee128Bit => 112,
+            FloatKind::BFloat16 => 7,
+            FloatKind::X86Fp80 => 63,
+            FloatKind::PpcFp128 => 105,
+        }
+    }
+
+    /// The number of exponent bits
+    ///
+    /// For [`FloatKind::PpcFp128`] this is the exponent of each half
+    pub const fn exponent_bits(self) -> u32 {
+        match self {
+            FloatKind::Ieee16Bit => 5,
+            FloatKind::Ieee32Bit | FloatKind::BFloat16 => 8,
+            FloatKind::Ieee64Bit | FloatKind::PpcFp128 => 11,
+            FloatKind::Ieee128Bit | FloatKind::X86Fp80 => 15,
+        }
+    }
+
+    /// The number of bits used to store a value of this kind, ignoring any padding
+    pub const fn storage_bits(self) -> u32 {
+        match self {
+            FloatKind::Ieee16Bit | FloatKind::BFloat16 => 16,
+            FloatKind::Ieee32Bit => 32,
+            FloatKind::Ieee64Bit => 64,
+            FloatKind::X86Fp80 => 80,
+            FloatKind::Ieee128Bit | FloatKind::PpcFp128 => 128,
+        }
+    }
+
+    /// The number of significant bits, including the (possibly implicit) integer bit
+    pub const fn precision(self) -> u32 {
+        self.mantissa_bits() + 1
+    }
+
+    /// The largest unbiased exponent of a finite value
+    pub const fn max_exponent(self) -> i32 {
+        (1 << (self.exponent_bits() - 1)) - 1
+    }
+
+    /// The smallest unbiased exponent of a normal value
+    pub const fn min_exponent(self) -> i32 {
+        1 - self.max_exponent()
+    }
+
+    /// Is this one of the IEEE-754 binary interchange formats
+    pub const fn is_ieee(self) -> bool {
+        matches!(
+            self,
+            FloatKind::Ieee16Bit
+                | FloatKind::Ieee32Bit
+                | FloatKind::Ieee64Bit
+                | FloatKind::Ieee128Bit
+        )
+    }
 }
 
 #[non_exhaustive]
@@ -30,6 +103,10 @@ impl core::fmt::Debug for FloatInfo {
             FloatKind::Ieee16Bit => write!(f, "f16"),
             FloatKind::Ieee32Bit => write!(f, "f32"),
             FloatKind::Ieee64Bit => write!(f, "f64"),
+            FloatKind::Ieee128Bit => write!(f, "f128"),
+            FloatKind::BFloat16 => write!(f, "bf16"),
+            FloatKind::X86Fp80 => write!(f, "x86_fp80"),
+            FloatKind::PpcFp128 => write!(f, "ppc_fp128"),
         }
     }
 }
@@ -37,11 +114,12 @@ impl core::fmt::Debug for FloatInfo {
 pub type FloatTy<'ctx> = Ty<'ctx, FloatInfo>;
 
 unsafe impl<'ctx> TypeInfo<'ctx> for FloatInfo {
-    const TAG: TypeTag = TypeTag::Integer;
+    const TAG: TypeTag = TypeTag::Float;
     type Flags = ();
 
     type Key<'a> = FloatKind where 'ctx: 'a;
 
+    #[inline]
     fn key<'a>(&'ctx self, (): Self::Flags) -> Self::Key<'a>
     where
         'ctx: 'a,
@@ -58,13 +136,30 @@ unsafe impl<'ctx> TypeInfo<'ctx> for FloatInfo {
 }
 
 impl<'ctx> FloatTy<'ctx> {
+    #[must_use]
     pub(crate) fn create(alloc: AllocContext<'ctx>, kind: FloatKind) -> Self {
         Ty::create_in_place(alloc, kind, ())
     }
+
     #[inline]
     pub fn kind(self) -> FloatKind {
         self.info().kind
     }
+
+    #[inline]
+    pub fn mantissa_bits(self) -> u32 {
+        self.kind().mantissa_bits()
+    }
+
+    #[inline]
+    pub fn exponent_bits(self) -> u32 {
+        self.kind().exponent_bits()
+    }
+
+    #[inline]
+    pub fn storage_bits(self) -> u32 {
+        self.kind().storage_bits()
+    }
 }
 
 impl Ctor<FloatKind> for FloatInfo {
@@ -45,10 +45,12 @@ impl<'ctx> fmt::Debug for Type<'ctx> {
         let x: &dyn fmt::Debug = match &x {
             UnpackedType::Unit(x) => x,
             UnpackedType::Integer(x) => x,
+            UnpackedType::Float(x) => x,
             UnpackedType::Pointer(x) => x,
             UnpackedType::Function(x) => x,
             UnpackedType::Array(x) => x,
             UnpackedType::Struct(x) => x,
+            UnpackedType::Vector(x) => x,
         };
 
         fmt::Debug::fmt(x, f)
@@ -93,8 +95,10 @@ impl<'ctx, T: ?Sized + TypeInfo<'ctx> + PartialEq> PartialEq for Ty<'ctx, T> {
     fn eq(&self, other: &Self) -> bool {
         match T::TAG {
             TypeTag::Unit => true,
-            TypeTag::Integer => core::ptr::eq(self.data, other.data),
-            TypeTag::Pointer | TypeTag::Function | TypeTag::Array | TypeTag::Struct => {
+            TypeTag::Integer | TypeTag::Float | TypeTag::Struct => {
+                core::ptr::eq(self.data, other.data)
+            }
+            TypeTag::Pointer | TypeTag::Function | TypeTag::Array | TypeTag::Vector => {
                 self.data == other.data
             }
         }
@@ -105,8 +109,10 @@ impl<'ctx, T: ?Sized + TypeInfo<'ctx> + Hash> Hash for Ty<'ctx, T> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         match T::TAG {
             TypeTag::Unit => (),
-            TypeTag::Integer => core::ptr::hash(self.data, state),
-            TypeTag::Pointer | TypeTag::Function | TypeTag::Array | TypeTag::Struct => {
+            TypeTag::Integer | TypeTag::Float | TypeTag::Struct => {
+                core::ptr::hash(self.data, state)
+            }
+            TypeTag::Pointer | TypeTag::Function | TypeTag::Array | TypeTag::Vector => {
                 self.data.hash(state)
             }
         }
@@ -176,20 +182,24 @@ where
 pub enum TypeTag {
     Unit,
     Integer,
+    Float,
     Pointer,
     Function,
     Array,
     Struct,
+    Vector,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UnpackedType<'ctx> {
     Unit(super::UnitTy<'ctx>),
     Integer(super::IntegerTy<'ctx>),
+    Float(super::FloatTy<'ctx>),
     Pointer(super::PointerTy<'ctx>),
     Function(super::FunctionTy<'ctx>),
     Array(super::ArrayTy<'ctx>),
     Struct(super::StructTy<'ctx>),
+    Vector(super::VectorTy<'ctx>),
 }
 
 /// # Safety
@@ -317,10 +327,26 @@ impl<'ctx> Type<'ctx> {
         match self.tag() {
             TypeTag::Unit => UnpackedType::Unit(unsafe { self.cast_unchecked() }),
             TypeTag::Integer => UnpackedType::Integer(unsafe { self.cast_unchecked() }),
+            TypeTag::Float => UnpackedType::Float(unsafe { self.cast_unchecked() }),
             TypeTag::Pointer => UnpackedType::Pointer(unsafe { self.cast_unchecked() }),
             TypeTag::Function => UnpackedType::Function(unsafe { self.cast_unchecked() }),
             TypeTag::Array => UnpackedType::Array(unsafe { self.cast_unchecked() }),
             TypeTag::Struct => UnpackedType::Struct(unsafe { self.cast_unchecked() }),
+            TypeTag::Vector => UnpackedType::Vector(unsafe { self.cast_unchecked() }),
+        }
+    }
+
+    /// Can values of this type be produced by instructions, i.e. it isn't
+    /// the unit type, a function type or an opaque struct
+    pub fn is_first_class(self) -> bool {
+        match self.unpack() {
+            UnpackedType::Unit(_) | UnpackedType::Function(_) => false,
+            UnpackedType::Struct(ty) => !ty.is_opaque(),
+            UnpackedType::Integer(_)
+            | UnpackedType::Float(_)
+            | UnpackedType::Pointer(_)
+            | UnpackedType::Array(_)
+            | UnpackedType::Vector(_) => true,
         }
     }
 }
@@ -1,5 +1,7 @@
 use std::{
     alloc::Layout,
+    cell::OnceCell,
+    hash::Hash,
     ops::{BitAnd, BitOr},
 };
 
@@ -8,36 +10,73 @@ use init::{
     Ctor,
 };
 
-use crate::ctx::AllocContext;
+use crate::{
+    ctx::AllocContext,
+    error::{describe, unwrap_or_panic, Error},
+};
 
 use super::{
-    raw_type::{TypeInfo, TypeTag},
+    raw_type::{TypeInfo, TypeTag, UnpackedType},
     Ty, Type,
 };
 
+/// Literal structs are interned by their contents and store their fields inline,
+/// identified structs are unique by identity and have their body set at most once
+/// after they are created (see [`StructTy::set_body`])
 #[repr(C)]
 #[non_exhaustive]
-#[derive(PartialEq, Eq, Hash)]
 pub struct StructInfo<'ctx> {
     name: Option<istr::IStr>,
+    // `None` for literal structs
+    body: Option<OnceCell<StructBody<'ctx>>>,
     field_tys: [Type<'ctx>],
 }
 
-impl core::fmt::Debug for StructInfo<'_> {
+#[derive(Clone, Copy)]
+struct StructBody<'ctx> {
+    packed: bool,
+    field_tys: &'ctx [Type<'ctx>],
+}
+
+// every struct is unique, literal structs are interned by their contents
+// and identified structs are unique by construction
+
+impl Eq for StructInfo<'_> {}
+impl PartialEq for StructInfo<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self, other)
+    }
+}
+
+impl Hash for StructInfo<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        core::ptr::hash(self, state)
+    }
+}
+
+struct FmtFields<'a, 'ctx>(&'a [Type<'ctx>]);
+
+impl core::fmt::Debug for FmtFields<'_, '_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        struct FmtFields<'a, 'ctx>(&'a [Type<'ctx>]);
-
-        impl core::fmt::Debug for FmtFields<'_, '_> {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                write!(f, "[")?;
-                for (i, arg) in self.0.iter().enumerate() {
-                    if i != 0 {
-                        write!(f, ", ")?
-                    }
-                    write!(f, "{:?}", arg)?
-                }
-                write!(f, "]")
+        write!(f, "[")?;
+        for (i, arg) in self.0.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?
             }
+            write!(f, "{:?}", arg)?
+        }
+        write!(f, "]")
+    }
+}
+
+impl core::fmt::Debug for StructInfo<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.body.is_some() {
+            // identified structs may be recursive, so only print their name
+            return match self.name {
+                Some(name) => write!(f, "%{name}"),
+                None => write!(f, "%anon"),
+            };
         }
 
         if let Some(name) = self.name {
@@ -66,6 +105,7 @@ unsafe impl<'ctx> TypeInfo<'ctx> for StructInfo<'ctx> {
             StructInit {
                 name: self.name,
                 fields: &self.field_tys,
+                identified: self.body.is_some(),
             },
         )
     }
@@ -129,14 +169,125 @@ impl<'ctx> StructTy<'ctx> {
     pub const LITERAL: StructFlags = StructFlags(1 << 2);
     pub const SIZED: StructFlags = StructFlags(1 << 3);
 
+    #[must_use]
+    pub(crate) fn create_identified(alloc: AllocContext<'ctx>, name: Option<istr::IStr>) -> Self {
+        Ty::create_in_place(
+            alloc,
+            StructInit {
+                name,
+                fields: &[],
+                identified: true,
+            },
+            StructFlags::default(),
+        )
+    }
+
     #[inline]
     pub fn name(self) -> Option<istr::IStr> {
         self.info().name
     }
 
+    /// The fields of this struct, opaque structs have no fields
     #[inline]
     pub fn field_tys(self) -> &'ctx [Type<'ctx>] {
-        &self.info().field_tys
+        let info = self.info();
+        match &info.body {
+            None => &info.field_tys,
+            Some(body) => body.get().map_or(&[], |body| body.field_tys),
+        }
+    }
+
+    /// Is this struct interned by its contents
+    #[inline]
+    pub fn is_literal(self) -> bool {
+        self.info().body.is_none()
+    }
+
+    /// Is this struct unique by identity, see [`TypeContext::identified_struct`](crate::TypeContext::identified_struct)
+    #[inline]
+    pub fn is_identified(self) -> bool {
+        self.info().body.is_some()
+    }
+
+    /// Is this an identified struct whose body hasn't been set yet
+    #[inline]
+    pub fn is_opaque(self) -> bool {
+        self.info()
+            .body
+            .as_ref()
+            .is_some_and(|body| body.get().is_none())
+    }
+
+    #[inline]
+    pub fn is_packed(self) -> bool {
+        self.struct_flags().packed()
+    }
+
+    /// The flags of this struct, taking into account the body of identified structs
+    pub fn struct_flags(self) -> StructFlags {
+        match &self.info().body {
+            None => self.flags() | StructTy::LITERAL,
+            Some(body) => match body.get() {
+                None => StructTy::OPAQUE,
+                Some(body) if body.packed => StructTy::PACKED,
+                Some(_) => StructFlags::default(),
+            },
+        }
+    }
+
+    /// Set the body of an identified struct
+    ///
+    /// # Panics
+    ///
+    /// * if this is a literal struct
+    /// * if the body was already set
+    /// * if this struct would contain itself (other than through a pointer)
+    #[track_caller]
+    pub fn set_body(self, alloc: AllocContext<'ctx>, field_tys: &[Type<'ctx>], packed: bool) {
+        unwrap_or_panic(self.try_set_body(alloc, field_tys, packed))
+    }
+
+    /// Set the body of an identified struct, see [`StructTy::set_body`]
+    pub fn try_set_body(
+        self,
+        alloc: AllocContext<'ctx>,
+        field_tys: &[Type<'ctx>],
+        packed: bool,
+    ) -> Result<(), Error> {
+        let Some(body) = &self.info().body else {
+            return Err(Error::LiteralStructBody { ty: describe(self) });
+        };
+
+        if body.get().is_some() {
+            return Err(Error::StructBodyAlreadySet { ty: describe(self) });
+        }
+
+        if field_tys.iter().any(|&ty| self.is_contained_by(ty)) {
+            return Err(Error::RecursiveStruct { ty: describe(self) });
+        }
+
+        let field_tys = alloc.alloc_slice_copy(field_tys);
+
+        if body.set(StructBody { packed, field_tys }).is_err() {
+            unreachable!()
+        }
+
+        Ok(())
+    }
+
+    fn is_contained_by(self, ty: Type<'ctx>) -> bool {
+        match ty.unpack() {
+            UnpackedType::Struct(ty) => {
+                ty == self || ty.field_tys().iter().any(|&ty| self.is_contained_by(ty))
+            }
+            UnpackedType::Array(ty) => self.is_contained_by(ty.item_ty()),
+            UnpackedType::Unit(_)
+            | UnpackedType::Integer(_)
+            | UnpackedType::Float(_)
+            | UnpackedType::Pointer(_)
+            | UnpackedType::Function(_)
+            | UnpackedType::Vector(_) => false,
+        }
     }
 }
 
@@ -144,6 +295,7 @@ impl<'ctx> StructTy<'ctx> {
 pub struct StructInit<'ctx, 'a> {
     pub(crate) name: Option<istr::IStr>,
     pub(crate) fields: &'a [Type<'ctx>],
+    pub(crate) identified: bool,
 }
 
 impl<'ctx> Ctor<StructInit<'ctx, '_>> for StructInfo<'ctx> {
@@ -154,6 +306,7 @@ impl<'ctx> Ctor<StructInit<'ctx, '_>> for StructInfo<'ctx> {
         init::init_struct! {
             uninit => Self {
                 name: init::ctor(|uninit| uninit.write(args.name)),
+                body: init::ctor(|uninit| uninit.write(args.identified.then(OnceCell::new))),
                 field_tys: args.fields
             }
         }
@@ -161,20 +314,24 @@ impl<'ctx> Ctor<StructInit<'ctx, '_>> for StructInfo<'ctx> {
 }
 
 impl<'ctx> HasLayoutProvider<StructInit<'ctx, '_>> for StructInfo<'ctx> {
-    type LayoutProvider = FunctionInfoLayoutProvider;
+    type LayoutProvider = StructInfoLayoutProvider;
 }
 
-pub struct FunctionInfoLayoutProvider;
+pub struct StructInfoLayoutProvider;
 
 unsafe impl<'ctx> LayoutProvider<StructInfo<'ctx>, StructInit<'ctx, '_>>
-    for FunctionInfoLayoutProvider
+    for StructInfoLayoutProvider
 {
     fn layout_of(args: &StructInit<'ctx, '_>) -> Option<std::alloc::Layout> {
         Some(
             Layout::new::<Option<istr::IStr>>()
+                .extend(Layout::new::<Option<OnceCell<StructBody>>>())
+                .ok()?
+                .0
                 .extend(Layout::array::<Type>(args.fields.len()).ok()?)
                 .ok()?
-                .0,
+                .0
+                .pad_to_align(),
         )
     }
 
@@ -0,0 +1,90 @@
+use core::fmt;
+
+/// The size of a type, which may not be known at compile time
+///
+/// Scalable vectors have a size that is a runtime multiple (`vscale`) of
+/// [`TypeSize::known_min`], all other types have a fixed size.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TypeSize {
+    known_min: u64,
+    scalable: bool,
+}
+
+impl fmt::Debug for TypeSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.scalable {
+            write!(f, "vscale\u{d7}{}", self.known_min)
+        } else {
+            write!(f, "{}", self.known_min)
+        }
+    }
+}
+
+impl TypeSize {
+    pub const ZERO: Self = Self::fixed(0);
+
+    #[inline]
+    pub const fn fixed(size: u64) -> Self {
+        Self {
+            known_min: size,
+            scalable: false,
+        }
+    }
+
+    #[inline]
+    pub const fn scalable(known_min: u64) -> Self {
+        Self {
+            known_min,
+            scalable: true,
+        }
+    }
+
+    #[inline]
+    pub const fn new(known_min: u64, scalable: bool) -> Self {
+        Self {
+            known_min,
+            scalable,
+        }
+    }
+
+    /// The minimum size, for scalable sizes the actual size is a multiple of this
+    #[inline]
+    pub const fn known_min(self) -> u64 {
+        self.known_min
+    }
+
+    #[inline]
+    pub const fn is_scalable(self) -> bool {
+        self.scalable
+    }
+
+    /// The size if it is known at compile time
+    #[inline]
+    pub const fn get_fixed(self) -> Option<u64> {
+        if self.scalable {
+            None
+        } else {
+            Some(self.known_min)
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If this size is scalable
+    #[inline]
+    pub fn unwrap_fixed(self) -> u64 {
+        match self.get_fixed() {
+            Some(size) => size,
+            None => panic!("expected a fixed size, but found a scalable size: {self:?}"),
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub fn map(self, f: impl FnOnce(u64) -> u64) -> Self {
+        Self {
+            known_min: f(self.known_min),
+            scalable: self.scalable,
+        }
+    }
+}
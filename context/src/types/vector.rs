@@ -0,0 +1,111 @@
+use init::{
+    layout_provider::{HasLayoutProvider, SizedLayoutProvider},
+    Ctor,
+};
+
+use crate::ctx::AllocContext;
+
+use super::{
+    raw_type::{TypeInfo, TypeTag},
+    Ty, Type,
+};
+
+#[non_exhaustive]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VectorInfo<'ctx> {
+    item_ty: Type<'ctx>,
+    len: u32,
+    scalable: bool,
+}
+
+impl core::fmt::Debug for VectorInfo<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.scalable {
+            write!(f, "<vscale\u{d7}{:?}\u{d7}{}>", self.item_ty, self.len)
+        } else {
+            write!(f, "<{:?}\u{d7}{}>", self.item_ty, self.len)
+        }
+    }
+}
+
+pub type VectorTy<'ctx> = Ty<'ctx, VectorInfo<'ctx>>;
+
+unsafe impl<'ctx> TypeInfo<'ctx> for VectorInfo<'ctx> {
+    const TAG: TypeTag = TypeTag::Vector;
+    type Flags = ();
+
+    type Key<'a> = VectorInit<'ctx> where 'ctx: 'a;
+
+    #[inline]
+    fn key<'a>(&'ctx self, (): Self::Flags) -> Self::Key<'a>
+    where
+        'ctx: 'a,
+    {
+        VectorInit {
+            item_ty: self.item_ty,
+            len: self.len,
+            scalable: self.scalable,
+        }
+    }
+
+    fn create_from_key<'a>(alloc: AllocContext<'ctx>, key: Self::Key<'a>) -> Ty<'ctx, Self>
+    where
+        'ctx: 'a,
+    {
+        Ty::create_in_place(alloc, key, ())
+    }
+}
+
+impl<'ctx> VectorTy<'ctx> {
+    /// Can a vector hold items of this type
+    ///
+    /// Only integers, floats and pointers may be vector items
+    pub fn is_valid_item_ty(item_ty: Type<'ctx>) -> bool {
+        matches!(
+            item_ty.tag(),
+            TypeTag::Integer | TypeTag::Float | TypeTag::Pointer
+        )
+    }
+
+    #[inline]
+    pub fn item_ty(self) -> Type<'ctx> {
+        self.info().item_ty
+    }
+
+    /// The number of lanes in this vector
+    ///
+    /// For scalable vectors this is the minimum number of lanes, the actual
+    /// number is a runtime multiple (`vscale`) of this
+    #[allow(clippy::len_without_is_empty)]
+    #[inline]
+    pub fn len(self) -> u32 {
+        self.info().len
+    }
+
+    #[inline]
+    pub fn is_scalable(self) -> bool {
+        self.info().scalable
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VectorInit<'ctx> {
+    pub item_ty: Type<'ctx>,
+    pub len: u32,
+    pub scalable: bool,
+}
+
+impl<'ctx> Ctor<VectorInit<'ctx>> for VectorInfo<'ctx> {
+    #[inline]
+    fn init<'a>(uninit: init::Uninit<'a, Self>, init: VectorInit<'ctx>) -> init::Init<'a, Self> {
+        uninit.write(Self {
+            item_ty: init.item_ty,
+            len: init.len,
+            scalable: init.scalable,
+        })
+    }
+}
+
+impl HasLayoutProvider<VectorInit<'_>> for VectorInfo<'_> {
+    type LayoutProvider = SizedLayoutProvider;
+}
@@ -1,7 +1,37 @@
+mod argument;
+mod basic_block;
 mod const_aggr_zero;
+mod const_aggregate;
+mod const_expr;
+mod const_float;
 mod const_int;
+mod const_null;
+mod encode;
+pub(crate) mod float_bits;
+pub mod fold;
+mod function;
+mod global_variable;
+mod instruction;
+mod linkage;
+mod opcode;
 mod raw_value;
+mod undef;
+mod uses;
 
+pub use argument::Argument;
+pub use basic_block::{BasicBlock, BasicBlocks, Instructions};
 pub use const_aggr_zero::ConstAggrZero;
+pub use const_aggregate::{ConstArray, ConstStruct, ConstVector};
+pub use const_expr::{ConstExpr, ConstOpcode};
+pub use const_float::ConstFloat;
 pub use const_int::ConstInt;
-pub use raw_value::{Val, Value, ValueInfo, ValueTag};
+pub use const_null::ConstNull;
+pub use fold::{IntBinOp, IntFlags, IntPredicate};
+pub use function::Function;
+pub use global_variable::GlobalVariable;
+pub use instruction::Instruction;
+pub use linkage::Linkage;
+pub use opcode::{CastOp, FloatBinOp, FloatPredicate, Opcode};
+pub use raw_value::{UnpackedValue, Val, Value, ValueInfo, ValueTag};
+pub use undef::{Poison, Undef};
+pub use uses::{Use, Users};
@@ -0,0 +1,97 @@
+use std::{
+    cell::{Cell, OnceCell},
+    hash::Hash,
+};
+
+use init::{
+    layout_provider::{HasLayoutProvider, SizedLayoutProvider},
+    Ctor,
+};
+
+use crate::{types::Type, AllocContext};
+
+use super::Function;
+
+/// A parameter of a function, the value is the argument it is called with
+pub struct ArgumentInfo<'ctx> {
+    index: u32,
+    name: Cell<Option<istr::IStr>>,
+    // set once the function is created
+    parent: OnceCell<Function<'ctx>>,
+}
+
+impl Eq for ArgumentInfo<'_> {}
+impl PartialEq for ArgumentInfo<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self, other)
+    }
+}
+
+impl Hash for ArgumentInfo<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        core::ptr::hash(self, state)
+    }
+}
+
+impl core::fmt::Debug for ArgumentInfo<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name.get() {
+            Some(name) => write!(f, "%{name}"),
+            None => write!(f, "%<unnamed>"),
+        }
+    }
+}
+
+pub type Argument<'ctx> = super::Val<'ctx, ArgumentInfo<'ctx>>;
+
+unsafe impl<'ctx> super::ValueInfo for ArgumentInfo<'ctx> {
+    const TAG: super::ValueTag = super::ValueTag::Argument;
+    type Flags = ();
+}
+
+impl<'ctx> Argument<'ctx> {
+    pub(crate) fn new(ctx: AllocContext<'ctx>, ty: Type<'ctx>, index: u32) -> Argument<'ctx> {
+        Self::create_in_place(ctx, ty, index, ())
+    }
+
+    pub(crate) fn set_parent(self, function: Function<'ctx>) {
+        if self.info().parent.set(function).is_err() {
+            unreachable!()
+        }
+    }
+
+    /// The position of this argument in the function's parameters
+    #[inline]
+    pub fn index(self) -> u32 {
+        self.info().index
+    }
+
+    #[inline]
+    pub fn parent(self) -> Function<'ctx> {
+        *self.info().parent.get().unwrap()
+    }
+
+    #[inline]
+    pub fn name(self) -> Option<istr::IStr> {
+        self.info().name.get()
+    }
+
+    #[inline]
+    pub fn set_name(self, name: impl crate::name::Name) {
+        self.info().name.set(name.to_name())
+    }
+}
+
+impl Ctor<u32> for ArgumentInfo<'_> {
+    fn init(uninit: init::Uninit<'_, Self>, index: u32) -> init::Init<'_, Self> {
+        uninit.write(Self {
+            index,
+            name: Cell::new(None),
+            parent: OnceCell::new(),
+        })
+    }
+}
+
+impl HasLayoutProvider<u32> for ArgumentInfo<'_> {
+    type LayoutProvider = SizedLayoutProvider;
+}
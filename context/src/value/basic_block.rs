@@ -0,0 +1,260 @@
+use std::{cell::Cell, hash::Hash};
+
+use crate::{
+    error::{check_block, check_function, check_ty, describe, unwrap_or_panic},
+    types::TypeTag,
+    AllocContext, Error,
+};
+
+use super::{Function, Instruction, Opcode};
+
+pub struct BasicBlockInfo<'ctx> {
+    name: Cell<Option<istr::IStr>>,
+    parent: Function<'ctx>,
+    prev: Cell<Option<BasicBlock<'ctx>>>,
+    next: Cell<Option<BasicBlock<'ctx>>>,
+    first: Cell<Option<Instruction<'ctx>>>,
+    last: Cell<Option<Instruction<'ctx>>>,
+}
+
+/// A straight line sequence of instructions in a function,
+/// which should end with a terminator
+///
+/// Blocks are unique by identity, and are created by
+/// [`Context::append_basic_block`](crate::Context::append_basic_block)
+#[derive(Clone, Copy)]
+pub struct BasicBlock<'ctx> {
+    info: &'ctx BasicBlockInfo<'ctx>,
+}
+
+impl Eq for BasicBlock<'_> {}
+impl PartialEq for BasicBlock<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self.info, other.info)
+    }
+}
+
+impl Hash for BasicBlock<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        core::ptr::hash(self.info, state)
+    }
+}
+
+impl core::fmt::Debug for BasicBlock<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "%{name}"),
+            None => write!(f, "%<unnamed>"),
+        }
+    }
+}
+
+impl<'ctx> BasicBlock<'ctx> {
+    /// Create a block at the end of `function`
+    pub(crate) fn new(
+        ctx: AllocContext<'ctx>,
+        function: Function<'ctx>,
+        name: Option<istr::IStr>,
+    ) -> BasicBlock<'ctx> {
+        let last = function.last_block();
+
+        let block = BasicBlock {
+            info: ctx.alloc(BasicBlockInfo {
+                name: Cell::new(name),
+                parent: function,
+                prev: Cell::new(last),
+                next: Cell::new(None),
+                first: Cell::new(None),
+                last: Cell::new(None),
+            }),
+        };
+
+        match last {
+            Some(last) => last.info.next.set(Some(block)),
+            None => function.info().first_block.set(Some(block)),
+        }
+        function.info().last_block.set(Some(block));
+
+        block
+    }
+
+    #[inline]
+    pub fn name(self) -> Option<istr::IStr> {
+        self.info.name.get()
+    }
+
+    #[inline]
+    pub fn set_name(self, name: impl crate::name::Name) {
+        self.info.name.set(name.to_name())
+    }
+
+    #[inline]
+    pub fn parent(self) -> Function<'ctx> {
+        self.info.parent
+    }
+
+    /// The previous block in the function
+    #[inline]
+    pub fn prev(self) -> Option<BasicBlock<'ctx>> {
+        self.info.prev.get()
+    }
+
+    /// The next block in the function
+    #[inline]
+    pub fn next(self) -> Option<BasicBlock<'ctx>> {
+        self.info.next.get()
+    }
+
+    #[inline]
+    pub fn first_instruction(self) -> Option<Instruction<'ctx>> {
+        self.info.first.get()
+    }
+
+    #[inline]
+    pub fn last_instruction(self) -> Option<Instruction<'ctx>> {
+        self.info.last.get()
+    }
+
+    /// The last instruction, if it is a terminator
+    pub fn terminator(self) -> Option<Instruction<'ctx>> {
+        self.last_instruction().filter(|inst| inst.is_terminator())
+    }
+
+    pub fn instructions(self) -> Instructions<'ctx> {
+        Instructions {
+            next: self.first_instruction(),
+        }
+    }
+
+    /// Whether this is the first block of its function, which is run when it is called
+    #[inline]
+    pub fn is_entry(self) -> bool {
+        self.prev().is_none()
+    }
+
+    /// Add `inst` to the end of this block
+    ///
+    /// # Panics
+    ///
+    /// If `inst` is already in a block, it uses values or blocks of another
+    /// function, or it is a `ret` which doesn't match the output type of the function
+    #[track_caller]
+    pub fn append(self, inst: Instruction<'ctx>) {
+        unwrap_or_panic(self.try_append(inst))
+    }
+
+    pub fn try_append(self, inst: Instruction<'ctx>) -> Result<(), Error> {
+        self.check_insert(inst)?;
+        self.link(inst, self.last_instruction(), None);
+        Ok(())
+    }
+
+    pub(super) fn check_insert(self, inst: Instruction<'ctx>) -> Result<(), Error> {
+        if inst.parent().is_some() {
+            return Err(Error::AlreadyInserted {
+                value: describe(inst),
+            });
+        }
+
+        if inst.is_erased() {
+            return Err(Error::Erased {
+                value: describe(inst),
+            });
+        }
+
+        let function = self.parent();
+        for value in inst.operands() {
+            check_function(function, value)?;
+        }
+        for block in inst.blocks() {
+            check_block(function, block)?;
+        }
+
+        if inst.opcode() == Opcode::Ret {
+            let output_ty = function.output_ty();
+            match inst.operands().next() {
+                Some(value) => check_ty(output_ty, value.ty())?,
+                None if output_ty.tag() == TypeTag::Unit => (),
+                None => {
+                    return Err(Error::OperandCount {
+                        opcode: "ret",
+                        expected: 1,
+                        found: 0,
+                    })
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn link(
+        self,
+        inst: Instruction<'ctx>,
+        prev: Option<Instruction<'ctx>>,
+        next: Option<Instruction<'ctx>>,
+    ) {
+        let info = inst.info();
+        info.parent.set(Some(self));
+        info.prev.set(prev);
+        info.next.set(next);
+
+        match prev {
+            Some(prev) => prev.info().next.set(Some(inst)),
+            None => self.info.first.set(Some(inst)),
+        }
+
+        match next {
+            Some(next) => next.info().prev.set(Some(inst)),
+            None => self.info.last.set(Some(inst)),
+        }
+    }
+
+    pub(super) fn unlink(self, inst: Instruction<'ctx>) {
+        let info = inst.info();
+        let (prev, next) = (info.prev.take(), info.next.take());
+        info.parent.set(None);
+
+        match prev {
+            Some(prev) => prev.info().next.set(next),
+            None => self.info.first.set(next),
+        }
+
+        match next {
+            Some(next) => next.info().prev.set(prev),
+            None => self.info.last.set(prev),
+        }
+    }
+}
+
+/// The instructions of a block, in order
+#[derive(Clone)]
+pub struct Instructions<'ctx> {
+    next: Option<Instruction<'ctx>>,
+}
+
+impl<'ctx> Iterator for Instructions<'ctx> {
+    type Item = Instruction<'ctx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let inst = self.next?;
+        self.next = inst.next();
+        Some(inst)
+    }
+}
+
+/// The blocks of a function, in order
+#[derive(Clone)]
+pub struct BasicBlocks<'ctx> {
+    pub(super) next: Option<BasicBlock<'ctx>>,
+}
+
+impl<'ctx> Iterator for BasicBlocks<'ctx> {
+    type Item = BasicBlock<'ctx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let block = self.next?;
+        self.next = block.next();
+        Some(block)
+    }
+}
@@ -3,13 +3,20 @@ use init::{
     Ctor,
 };
 
-use crate::{types::Type, AllocContext};
-
-use super::Value;
+use crate::{
+    types::{Type, UnpackedType},
+    AllocContext,
+};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ConstAggrZeroInfo {}
 
+impl core::fmt::Debug for ConstAggrZeroInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "zeroinitializer")
+    }
+}
+
 pub type ConstAggrZero<'ctx> = super::Val<'ctx, ConstAggrZeroInfo>;
 
 unsafe impl super::ValueInfo for ConstAggrZeroInfo {
@@ -18,9 +25,22 @@ unsafe impl super::ValueInfo for ConstAggrZeroInfo {
 }
 
 impl<'ctx> ConstAggrZero<'ctx> {
-    #[allow(clippy::new_ret_no_self)]
-    pub fn new(ctx: AllocContext<'ctx>, ty: Type<'ctx>) -> Value<'ctx> {
-        Self::create_in_place(ctx, ty, (), ()).erase()
+    /// `ty` must be a valid type, see [`ConstAggrZero::is_valid_ty`]
+    pub(crate) fn new(ctx: AllocContext<'ctx>, ty: Type<'ctx>) -> ConstAggrZero<'ctx> {
+        Self::create_in_place(ctx, ty, (), ())
+    }
+
+    /// Can `ty` be zero initialized by a [`ConstAggrZero`]
+    pub fn is_valid_ty(ty: Type<'ctx>) -> bool {
+        match ty.unpack() {
+            UnpackedType::Array(_) | UnpackedType::Vector(_) => true,
+            UnpackedType::Struct(ty) => !ty.is_opaque(),
+            UnpackedType::Unit(_)
+            | UnpackedType::Integer(_)
+            | UnpackedType::Float(_)
+            | UnpackedType::Pointer(_)
+            | UnpackedType::Function(_) => false,
+        }
     }
 }
 
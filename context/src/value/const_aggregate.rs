@@ -0,0 +1,150 @@
+use std::alloc::Layout;
+
+use init::{
+    layout_provider::{HasLayoutProvider, LayoutProvider},
+    Ctor,
+};
+
+use crate::{
+    types::{ArrayTy, StructTy, VectorTy},
+    AllocContext,
+};
+
+use super::{UnpackedValue, Value};
+
+#[derive(Clone, Copy)]
+pub(crate) struct AggregateInit<'ctx, 'a> {
+    pub items: &'a [Value<'ctx>],
+}
+
+pub struct AggregateLayoutProvider;
+
+macro_rules! aggregate {
+    (
+        $(#[$meta:meta])*
+        $info:ident, $name:ident, $ty:ident, $open:literal, $close:literal
+    ) => {
+        $(#[$meta])*
+        #[repr(C)]
+        #[non_exhaustive]
+        #[derive(PartialEq, Eq, Hash)]
+        pub struct $info<'ctx> {
+            items: [Value<'ctx>],
+        }
+
+        impl core::fmt::Debug for $info<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, $open)?;
+                for (i, item) in self.items.iter().enumerate() {
+                    if i != 0 {
+                        write!(f, ", ")?
+                    }
+                    write!(f, "{:?}", item)?
+                }
+                write!(f, $close)
+            }
+        }
+
+        pub type $name<'ctx> = super::Val<'ctx, $info<'ctx>>;
+
+        unsafe impl<'ctx> super::ValueInfo for $info<'ctx> {
+            const TAG: super::ValueTag = super::ValueTag::$name;
+            type Flags = ();
+        }
+
+        impl<'ctx> $name<'ctx> {
+            pub(crate) fn new(
+                ctx: AllocContext<'ctx>,
+                ty: $ty<'ctx>,
+                items: &[Value<'ctx>],
+            ) -> $name<'ctx> {
+                Self::create_in_place(ctx, ty.erase(), AggregateInit { items }, ())
+            }
+
+            #[inline]
+            pub fn items(self) -> &'ctx [Value<'ctx>] {
+                &self.info().items
+            }
+        }
+
+        impl<'ctx> Ctor<AggregateInit<'ctx, '_>> for $info<'ctx> {
+            fn init<'a>(
+                uninit: init::Uninit<'a, Self>,
+                args: AggregateInit<'ctx, '_>,
+            ) -> init::Init<'a, Self> {
+                init::init_struct! {
+                    uninit => Self {
+                        items: args.items,
+                    }
+                }
+            }
+        }
+
+        impl<'ctx> HasLayoutProvider<AggregateInit<'ctx, '_>> for $info<'ctx> {
+            type LayoutProvider = AggregateLayoutProvider;
+        }
+
+        unsafe impl<'ctx> LayoutProvider<$info<'ctx>, AggregateInit<'ctx, '_>>
+            for AggregateLayoutProvider
+        {
+            fn layout_of(args: &AggregateInit<'ctx, '_>) -> Option<std::alloc::Layout> {
+                Layout::array::<Value>(args.items.len()).ok()
+            }
+
+            unsafe fn cast(
+                ptr: std::ptr::NonNull<u8>,
+                args: &AggregateInit<'ctx, '_>,
+            ) -> std::ptr::NonNull<$info<'ctx>> {
+                std::ptr::NonNull::from_raw_parts(ptr.cast(), args.items.len())
+            }
+        }
+    };
+}
+
+aggregate! {
+    /// A constant array with at least one non-zero item
+    ConstArrayInfo, ConstArray, ArrayTy, "[", "]"
+}
+
+aggregate! {
+    /// A constant struct with at least one non-zero field
+    ConstStructInfo, ConstStruct, StructTy, "{{", "}}"
+}
+
+aggregate! {
+    /// A constant fixed-length vector with at least one non-zero item
+    ConstVectorInfo, ConstVector, VectorTy, "<", ">"
+}
+
+impl<'ctx> Value<'ctx> {
+    /// The items of a constant array, struct or vector, `None` for every other value
+    pub fn aggregate_items(self) -> Option<&'ctx [Value<'ctx>]> {
+        match self.unpack() {
+            UnpackedValue::ConstArray(value) => Some(value.items()),
+            UnpackedValue::ConstStruct(value) => Some(value.items()),
+            UnpackedValue::ConstVector(value) => Some(value.items()),
+            _ => None,
+        }
+    }
+
+    /// Is every bit of this constant zero
+    ///
+    /// All zero aggregates are always created as [`ConstAggrZero`](super::ConstAggrZero)
+    pub fn is_zero(self) -> bool {
+        match self.unpack() {
+            UnpackedValue::ConstAggrZero(_) | UnpackedValue::ConstNull(_) => true,
+            UnpackedValue::ConstInt(value) => value.is_zero(),
+            UnpackedValue::ConstFloat(value) => value.bits() == 0,
+            UnpackedValue::ConstArray(_)
+            | UnpackedValue::ConstStruct(_)
+            | UnpackedValue::ConstVector(_)
+            | UnpackedValue::Undef(_)
+            | UnpackedValue::Poison(_)
+            | UnpackedValue::ConstExpr(_)
+            | UnpackedValue::GlobalVariable(_)
+            | UnpackedValue::Function(_)
+            | UnpackedValue::Argument(_)
+            | UnpackedValue::Instruction(_) => false,
+        }
+    }
+}
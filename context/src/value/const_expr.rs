@@ -0,0 +1,173 @@
+use std::alloc::Layout;
+
+use init::{
+    layout_provider::{HasLayoutProvider, LayoutProvider},
+    Ctor,
+};
+
+use crate::{types::Type, AllocContext};
+
+use super::Value;
+
+/// The operation performed by a [`ConstExpr`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstOpcode<'ctx> {
+    Trunc,
+    ZExt,
+    SExt,
+    PtrToInt,
+    IntToPtr,
+    BitCast,
+    Add,
+    Sub,
+    /// The first operand is the base pointer and the rest are the indices,
+    /// the first index steps over whole values of `source_ty`
+    GetElementPtr {
+        source_ty: Type<'ctx>,
+        inbounds: bool,
+    },
+}
+
+impl ConstOpcode<'_> {
+    pub fn name(self) -> &'static str {
+        match self {
+            ConstOpcode::Trunc => "trunc",
+            ConstOpcode::ZExt => "zext",
+            ConstOpcode::SExt => "sext",
+            ConstOpcode::PtrToInt => "ptrtoint",
+            ConstOpcode::IntToPtr => "inttoptr",
+            ConstOpcode::BitCast => "bitcast",
+            ConstOpcode::Add => "add",
+            ConstOpcode::Sub => "sub",
+            ConstOpcode::GetElementPtr { .. } => "getelementptr",
+        }
+    }
+
+    #[inline]
+    pub fn is_cast(self) -> bool {
+        matches!(
+            self,
+            ConstOpcode::Trunc
+                | ConstOpcode::ZExt
+                | ConstOpcode::SExt
+                | ConstOpcode::PtrToInt
+                | ConstOpcode::IntToPtr
+                | ConstOpcode::BitCast
+        )
+    }
+}
+
+/// A constant computed from other constants, which can't be folded
+/// (usually because it depends on the address of a global)
+#[repr(C)]
+#[non_exhaustive]
+#[derive(PartialEq, Eq, Hash)]
+pub struct ConstExprInfo<'ctx> {
+    opcode: ConstOpcode<'ctx>,
+    operands: [Value<'ctx>],
+}
+
+impl core::fmt::Debug for ConstExprInfo<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ", self.opcode.name())?;
+
+        if let ConstOpcode::GetElementPtr {
+            source_ty,
+            inbounds,
+        } = self.opcode
+        {
+            if inbounds {
+                write!(f, "inbounds ")?
+            }
+            write!(f, "({:?}", source_ty)?;
+            for operand in &self.operands {
+                write!(f, ", {:?}", operand)?
+            }
+            return write!(f, ")");
+        }
+
+        write!(f, "(")?;
+        for (i, operand) in self.operands.iter().enumerate() {
+            if i != 0 {
+                write!(f, ", ")?
+            }
+            write!(f, "{:?}", operand)?
+        }
+        write!(f, ")")
+    }
+}
+
+pub type ConstExpr<'ctx> = super::Val<'ctx, ConstExprInfo<'ctx>>;
+
+unsafe impl<'ctx> super::ValueInfo for ConstExprInfo<'ctx> {
+    const TAG: super::ValueTag = super::ValueTag::ConstExpr;
+    type Flags = ();
+}
+
+impl<'ctx> ConstExpr<'ctx> {
+    pub(crate) fn new(
+        ctx: AllocContext<'ctx>,
+        ty: Type<'ctx>,
+        opcode: ConstOpcode<'ctx>,
+        operands: &[Value<'ctx>],
+    ) -> ConstExpr<'ctx> {
+        Self::create_in_place(ctx, ty, ConstExprInit { opcode, operands }, ())
+    }
+
+    #[inline]
+    pub fn opcode(self) -> ConstOpcode<'ctx> {
+        self.info().opcode
+    }
+
+    #[inline]
+    pub fn operands(self) -> &'ctx [Value<'ctx>] {
+        &self.info().operands
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct ConstExprInit<'ctx, 'a> {
+    opcode: ConstOpcode<'ctx>,
+    operands: &'a [Value<'ctx>],
+}
+
+impl<'ctx> Ctor<ConstExprInit<'ctx, '_>> for ConstExprInfo<'ctx> {
+    fn init<'a>(
+        uninit: init::Uninit<'a, Self>,
+        args: ConstExprInit<'ctx, '_>,
+    ) -> init::Init<'a, Self> {
+        init::init_struct! {
+            uninit => Self {
+                opcode: args.opcode,
+                operands: args.operands,
+            }
+        }
+    }
+}
+
+impl<'ctx> HasLayoutProvider<ConstExprInit<'ctx, '_>> for ConstExprInfo<'ctx> {
+    type LayoutProvider = ConstExprInfoLayoutProvider;
+}
+
+pub struct ConstExprInfoLayoutProvider;
+
+unsafe impl<'ctx> LayoutProvider<ConstExprInfo<'ctx>, ConstExprInit<'ctx, '_>>
+    for ConstExprInfoLayoutProvider
+{
+    fn layout_of(args: &ConstExprInit<'ctx, '_>) -> Option<std::alloc::Layout> {
+        Some(
+            Layout::new::<ConstOpcode>()
+                .extend(Layout::array::<Value>(args.operands.len()).ok()?)
+                .ok()?
+                .0
+                .pad_to_align(),
+        )
+    }
+
+    unsafe fn cast(
+        ptr: std::ptr::NonNull<u8>,
+        args: &ConstExprInit<'ctx, '_>,
+    ) -> std::ptr::NonNull<ConstExprInfo<'ctx>> {
+        std::ptr::NonNull::from_raw_parts(ptr.cast(), args.operands.len())
+    }
+}
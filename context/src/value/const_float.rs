@@ -0,0 +1,127 @@
+use std::hash::Hash;
+
+use init::{
+    layout_provider::{HasLayoutProvider, SizedLayoutProvider},
+    Ctor,
+};
+use rug::float::BorrowFloat;
+
+use crate::{
+    types::{FloatKind, FloatTy},
+    AllocContext,
+};
+
+/// A floating point constant
+///
+/// The value is kept both as its exact bit pattern in the format of the type,
+/// which is used for equality and hashing (so `-0.0 != 0.0` and NaNs with
+/// different payloads are different constants), and as a `rug::Float` with
+/// exactly the precision of the type for arithmetic
+#[derive(Clone, Copy)]
+pub struct ConstFloatInfo<'ctx> {
+    bits: u128,
+    value: BorrowFloat<'ctx>,
+}
+
+impl Eq for ConstFloatInfo<'_> {}
+impl PartialEq for ConstFloatInfo<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+
+impl Hash for ConstFloatInfo<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bits.hash(state);
+    }
+}
+
+impl core::fmt::Debug for ConstFloatInfo<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.value.is_nan() {
+            write!(f, "nan(0x{:x})", self.bits)
+        } else {
+            write!(f, "{}", *self.value)
+        }
+    }
+}
+
+pub type ConstFloat<'ctx> = super::Val<'ctx, ConstFloatInfo<'ctx>>;
+
+unsafe impl<'ctx> super::ValueInfo for ConstFloatInfo<'ctx> {
+    const TAG: super::ValueTag = super::ValueTag::ConstFloat;
+    type Flags = ();
+}
+
+impl<'ctx> ConstFloat<'ctx> {
+    #[allow(clippy::new_ret_no_self)]
+    pub(crate) fn new(
+        ctx: AllocContext<'ctx>,
+        ty: FloatTy<'ctx>,
+        bits: u128,
+        value: BorrowFloat<'ctx>,
+    ) -> ConstFloat<'ctx> {
+        Self::create_in_place(ctx, ty.erase(), ConstFloatInit { bits, value }, ())
+    }
+
+    #[inline]
+    pub fn kind(&self) -> FloatKind {
+        self.ty().cast::<crate::types::FloatInfo>().kind()
+    }
+
+    /// The bit pattern of this value in the format of its type
+    ///
+    /// For [`FloatKind::PpcFp128`] the high-order double is in the low 64 bits
+    #[inline]
+    pub fn bits(&self) -> u128 {
+        self.info().bits
+    }
+
+    /// The exact value, with the precision of the type
+    ///
+    /// NaNs don't carry their payload here, see [`ConstFloat::nan_payload`]
+    #[inline]
+    pub fn value(&self) -> BorrowFloat<'ctx> {
+        self.info().value
+    }
+
+    #[inline]
+    pub fn is_nan(&self) -> bool {
+        self.value().is_nan()
+    }
+
+    #[inline]
+    pub fn is_negative(&self) -> bool {
+        self.value().is_sign_negative()
+    }
+
+    /// The mantissa bits of a NaN, including the quiet bit
+    pub fn nan_payload(&self) -> Option<u128> {
+        if self.is_nan() {
+            Some(super::float_bits::nan_payload_of(self.kind(), self.bits()))
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) struct ConstFloatInit<'ctx> {
+    bits: u128,
+    value: BorrowFloat<'ctx>,
+}
+
+impl<'ctx> Ctor<ConstFloatInit<'ctx>> for ConstFloatInfo<'ctx> {
+    fn init<'a>(
+        uninit: init::Uninit<'a, Self>,
+        args: ConstFloatInit<'ctx>,
+    ) -> init::Init<'a, Self> {
+        uninit.write(Self {
+            bits: args.bits,
+            value: args.value,
+        })
+    }
+}
+
+impl<'ctx> HasLayoutProvider<ConstFloatInit<'ctx>> for ConstFloatInfo<'ctx> {
+    type LayoutProvider = SizedLayoutProvider;
+}
@@ -8,8 +8,11 @@ use rug::integer::BorrowInteger;
 
 use crate::{types::IntegerTy, AllocContext};
 
+/// An integer constant, stored as its two's complement bit pattern
+/// so that it has a single representation whichever way it is interpreted
 #[derive(Debug, Clone, Copy)]
 pub struct ConstIntInfo<'ctx> {
+    // always in `0..2^bits`
     value: BorrowInteger<'ctx>,
 }
 
@@ -30,7 +33,7 @@ pub type ConstInt<'ctx> = super::Val<'ctx, ConstIntInfo<'ctx>>;
 
 unsafe impl<'ctx> super::ValueInfo for ConstIntInfo<'ctx> {
     const TAG: super::ValueTag = super::ValueTag::ConstInt;
-    type Flags = bool;
+    type Flags = ();
 }
 
 impl<'ctx> ConstInt<'ctx> {
@@ -39,20 +42,31 @@ impl<'ctx> ConstInt<'ctx> {
         ctx: AllocContext<'ctx>,
         ty: IntegerTy<'ctx>,
         value: BorrowInteger<'ctx>,
-        signed: bool,
     ) -> ConstInt<'ctx> {
-        Self::create_in_place(ctx, ty.erase(), value, signed)
+        debug_assert!(*value >= 0 && value.significant_bits() <= u32::from(ty.bits().get()));
+        Self::create_in_place(ctx, ty.erase(), value, ())
     }
 
     #[inline]
-    pub fn is_signed(&self) -> bool {
-        self.flags()
+    pub fn bits(&self) -> u32 {
+        u32::from(self.ty().cast::<crate::types::IntegerInfo>().bits().get())
     }
 
+    /// The bit pattern interpreted as an unsigned integer, in `0..2^bits`
     #[inline]
-    pub fn value(&self) -> BorrowInteger<'ctx> {
+    pub fn as_unsigned(&self) -> BorrowInteger<'ctx> {
         self.info().value
     }
+
+    /// The bit pattern interpreted as a two's complement integer, in `-2^(bits-1)..2^(bits-1)`
+    pub fn as_signed(&self) -> rug::Integer {
+        rug::Integer::from(self.as_unsigned().keep_signed_bits_ref(self.bits()))
+    }
+
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        self.as_unsigned().is_zero()
+    }
 }
 
 impl<'ctx> Ctor<BorrowInteger<'ctx>> for ConstIntInfo<'ctx> {
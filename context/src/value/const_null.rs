@@ -0,0 +1,48 @@
+use init::{
+    layout_provider::{HasLayoutProvider, SizedLayoutProvider},
+    Ctor,
+};
+
+use crate::{
+    types::{AddressSpace, PointerTy},
+    AllocContext,
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ConstNullInfo {}
+
+impl core::fmt::Debug for ConstNullInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "null")
+    }
+}
+
+pub type ConstNull<'ctx> = super::Val<'ctx, ConstNullInfo>;
+
+unsafe impl super::ValueInfo for ConstNullInfo {
+    const TAG: super::ValueTag = super::ValueTag::ConstNull;
+    type Flags = ();
+}
+
+impl<'ctx> ConstNull<'ctx> {
+    pub(crate) fn new(ctx: AllocContext<'ctx>, ty: PointerTy<'ctx>) -> ConstNull<'ctx> {
+        Self::create_in_place(ctx, ty.erase(), (), ())
+    }
+
+    #[inline]
+    pub fn address_space(self) -> AddressSpace {
+        self.ty()
+            .cast::<crate::types::PointerInfo>()
+            .address_space()
+    }
+}
+
+impl Ctor for ConstNullInfo {
+    fn init(uninit: init::Uninit<'_, Self>, (): ()) -> init::Init<'_, Self> {
+        uninit.write(Self {})
+    }
+}
+
+impl HasLayoutProvider for ConstNullInfo {
+    type LayoutProvider = SizedLayoutProvider;
+}
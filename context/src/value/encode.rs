@@ -0,0 +1,194 @@
+use rug::integer::Order;
+
+use crate::{layout::DataLayout, types::FloatKind, Endianness};
+
+use super::{UnpackedValue, Value};
+
+impl<'ctx> Value<'ctx> {
+    /// The in-memory representation of this constant, as it would be
+    /// stored in the initializer of a global
+    ///
+    /// The result is [`DataLayout::alloc_size`] bytes long, and all padding is zeroed.
+    /// Returns `None` if the type of the value is unsized or scalable, or if the
+    /// value contains an address or a constant expression, which can only be resolved
+    /// when linking, or isn't a constant
+    pub fn to_bytes(self, layout: DataLayout<'ctx>) -> Option<Vec<u8>> {
+        let ty = self.ty();
+
+        if !layout.is_sized(ty) {
+            return None;
+        }
+
+        let size = layout.alloc_size(ty).get_fixed()?;
+        let mut bytes = vec![0; usize::try_from(size).ok()?];
+        self.write_bytes(layout, &mut bytes)?;
+        Some(bytes)
+    }
+
+    /// `out` must be zeroed, and at least as long as the store size of this value
+    fn write_bytes(self, layout: DataLayout<'ctx>, out: &mut [u8]) -> Option<()> {
+        match self.unpack() {
+            // undef and poison are free to be any value, so zero them like LLVM does
+            UnpackedValue::ConstAggrZero(_)
+            | UnpackedValue::ConstNull(_)
+            | UnpackedValue::Undef(_)
+            | UnpackedValue::Poison(_) => (),
+            // addresses are only known after linking
+            UnpackedValue::ConstExpr(_)
+            | UnpackedValue::GlobalVariable(_)
+            | UnpackedValue::Function(_) => return None,
+            // only known while the function runs
+            UnpackedValue::Argument(_) | UnpackedValue::Instruction(_) => return None,
+            UnpackedValue::ConstInt(value) => {
+                let store_size = value.bits().div_ceil(8) as usize;
+                // the bit pattern is never negative, so the padding bits are always zero
+                write_int(layout, &value.as_unsigned(), &mut out[..store_size])
+            }
+            UnpackedValue::ConstFloat(value) => {
+                let bits = value.bits();
+                match value.kind() {
+                    // a pair of doubles, the high-order one first
+                    FloatKind::PpcFp128 => {
+                        write_u128(layout, bits & u128::from(u64::MAX), &mut out[..8]);
+                        write_u128(layout, bits >> 64, &mut out[8..16]);
+                    }
+                    kind => {
+                        let store_size = (kind.storage_bits() / 8) as usize;
+                        write_u128(layout, bits, &mut out[..store_size])
+                    }
+                }
+            }
+            UnpackedValue::ConstArray(value) => {
+                let ty = self.ty().cast::<crate::types::ArrayInfo>();
+                let stride = layout.alloc_size(ty.item_ty()).unwrap_fixed() as usize;
+                for (i, item) in value.items().iter().enumerate() {
+                    item.write_bytes(layout, &mut out[i * stride..])?;
+                }
+            }
+            UnpackedValue::ConstStruct(value) => {
+                let ty = self.ty().cast::<crate::types::StructInfo>();
+                let struct_layout = layout.struct_layout(ty);
+                for (i, field) in value.items().iter().enumerate() {
+                    field
+                        .write_bytes(layout, &mut out[struct_layout.field_offset(i) as usize..])?;
+                }
+            }
+            UnpackedValue::ConstVector(value) => {
+                let ty = self.ty().cast::<crate::types::VectorInfo>();
+                let item_bits = layout.type_size_bits(ty.item_ty()).unwrap_fixed();
+
+                if item_bits % 8 == 0 {
+                    let stride = (item_bits / 8) as usize;
+                    for (i, item) in value.items().iter().enumerate() {
+                        item.write_bytes(layout, &mut out[i * stride..])?;
+                    }
+                    return Some(());
+                }
+
+                // vectors of small integers are packed into a single integer,
+                // with the first item in the lowest bits on little endian targets
+                let len = value.items().len() as u64;
+                let mut packed = rug::Integer::new();
+                for (i, item) in value.items().iter().enumerate() {
+                    let item = match item.unpack() {
+                        UnpackedValue::ConstInt(item) => item,
+                        UnpackedValue::Undef(_) | UnpackedValue::Poison(_) => continue,
+                        UnpackedValue::ConstExpr(_)
+                        | UnpackedValue::GlobalVariable(_)
+                        | UnpackedValue::Function(_)
+                        | UnpackedValue::Argument(_)
+                        | UnpackedValue::Instruction(_) => return None,
+                        _ => unreachable!(
+                            "only integers can have a size that is not a whole number of bytes"
+                        ),
+                    };
+
+                    let index = match layout.endianness() {
+                        Endianness::Little => i as u64,
+                        Endianness::Big => len - 1 - i as u64,
+                    };
+                    packed |=
+                        rug::Integer::from(&*item.as_unsigned() << (index * item_bits) as u32);
+                }
+
+                let store_size = (len * item_bits).div_ceil(8) as usize;
+                write_int(layout, &packed, &mut out[..store_size])
+            }
+        }
+
+        Some(())
+    }
+}
+
+/// Write a non-negative integer that fits in `out` in the target's byte order
+fn write_int(layout: DataLayout<'_>, value: &rug::Integer, out: &mut [u8]) {
+    let digits = value.to_digits::<u8>(Order::Lsf);
+    out[..digits.len()].copy_from_slice(&digits);
+
+    if layout.endianness() == Endianness::Big {
+        out.reverse()
+    }
+}
+
+/// Write the low bytes of `value` that fit in `out` in the target's byte order
+fn write_u128(layout: DataLayout<'_>, value: u128, out: &mut [u8]) {
+    let len = out.len();
+    out.copy_from_slice(&value.to_le_bytes()[..len]);
+
+    if layout.endianness() == Endianness::Big {
+        out.reverse()
+    }
+}
+
+#[test]
+fn test_to_bytes() {
+    let mut target = crate::Target::new(crate::PtrBits::_64, crate::PtrBits::_64);
+
+    crate::Context::with(target.clone(), |ctx| {
+        let layout = ctx.data_layout();
+        let value = ctx.value().intern_i32(0x01020304);
+        let value = ctx.const_int(ctx.i32(), value).unwrap();
+        assert_eq!(value.erase().to_bytes(layout), Some(vec![4, 3, 2, 1]));
+
+        let minus_one = ctx.value().intern_i32(-1);
+        let minus_one = ctx.const_int(ctx.int_ty_lit(12), minus_one).unwrap();
+        assert_eq!(minus_one.erase().to_bytes(layout), Some(vec![0xff, 0x0f]));
+
+        let half = ctx.const_f32(ctx.f32(), 3.5);
+        assert_eq!(half.erase().to_bytes(layout), Some(vec![0, 0, 0x60, 0x40]));
+
+        let one = ctx.const_int(ctx.i8(), ctx.value().one_value()).unwrap();
+        let ty = ctx.struct_ty(
+            (),
+            Default::default(),
+            &[ctx.i8().erase(), ctx.i32().erase()],
+        );
+        let value = ctx.const_struct(ty, &[one.erase(), value.erase()]).unwrap();
+        assert_eq!(value.to_bytes(layout), Some(vec![1, 0, 0, 0, 4, 3, 2, 1]));
+
+        let i1 = ctx.int_ty_lit(1);
+        let zero = ctx.const_int(i1, ctx.value().zero_value()).unwrap().erase();
+        let one = ctx.const_int(i1, ctx.value().one_value()).unwrap().erase();
+        let ty = ctx.vector_ty(4, i1);
+        let value = ctx.const_vector(ty, &[one, zero, one, one]).unwrap();
+        assert_eq!(value.to_bytes(layout), Some(vec![0b1101]));
+        assert!(ctx.const_vector(ty, &[zero; 4]).unwrap().is_zero());
+
+        let ty = ctx.array_ty(2, ctx.int_ty_lit(12));
+        let value = ctx
+            .const_array(ty, &[minus_one.erase(), minus_one.erase()])
+            .unwrap();
+        assert_eq!(value.to_bytes(layout), Some(vec![0xff, 0x0f, 0xff, 0x0f]));
+    });
+
+    target.endianness = Endianness::Big;
+    crate::Context::with(target, |ctx| {
+        let layout = ctx.data_layout();
+        let value = ctx.value().intern_i32(0x01020304);
+        let value = ctx.const_int(ctx.i32(), value).unwrap();
+        assert_eq!(value.erase().to_bytes(layout), Some(vec![1, 2, 3, 4]));
+
+        let half = ctx.const_f32(ctx.f32(), 3.5);
+        assert_eq!(half.erase().to_bytes(layout), Some(vec![0x40, 0x60, 0, 0]));
+    });
+}
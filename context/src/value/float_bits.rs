@@ -0,0 +1,249 @@
+use std::cmp::Ordering;
+
+use rug::{
+    float::{Round, Special},
+    Assign, Float, Integer,
+};
+
+use crate::types::FloatKind;
+
+// PowerPC double-double NaNs and infinities are stored in the high-order double
+const PPC_HALF: FloatKind = FloatKind::Ieee64Bit;
+
+pub(crate) fn storage_mask(kind: FloatKind) -> u128 {
+    match kind.storage_bits() {
+        128 => u128::MAX,
+        bits => (1 << bits) - 1,
+    }
+}
+
+/// The number of bits in the payload of a NaN
+pub(crate) fn nan_payload_bits(kind: FloatKind) -> u32 {
+    match kind {
+        FloatKind::PpcFp128 => PPC_HALF.mantissa_bits(),
+        _ => kind.mantissa_bits(),
+    }
+}
+
+pub(crate) fn nan_payload_of(kind: FloatKind, bits: u128) -> u128 {
+    let payload_mask: u128 = (1 << nan_payload_bits(kind)) - 1;
+    bits & payload_mask
+}
+
+/// The payload of the default quiet NaN
+pub(crate) fn quiet_nan_payload(kind: FloatKind) -> u128 {
+    1 << (nan_payload_bits(kind) - 1)
+}
+
+/// Move a NaN payload between formats, keeping the quiet bit and the high order bits
+pub(crate) fn convert_nan_payload(from: FloatKind, to: FloatKind, payload: u128) -> u128 {
+    let from_bits = nan_payload_bits(from);
+    let to_bits = nan_payload_bits(to);
+
+    let payload = if from_bits < to_bits {
+        payload << (to_bits - from_bits)
+    } else {
+        payload >> (from_bits - to_bits)
+    };
+
+    if payload == 0 {
+        quiet_nan_payload(to)
+    } else {
+        payload
+    }
+}
+
+/// The bit pattern of a NaN, a zero payload is replaced by the default quiet NaN
+pub(crate) fn nan_bits(kind: FloatKind, negative: bool, payload: u128) -> u128 {
+    let payload_bits = nan_payload_bits(kind);
+    let mut payload = payload & ((1u128 << payload_bits) - 1);
+    if payload == 0 {
+        payload = quiet_nan_payload(kind);
+    }
+
+    let kind = if kind == FloatKind::PpcFp128 {
+        PPC_HALF
+    } else {
+        kind
+    };
+
+    let exponent_mask: u128 = (1 << kind.exponent_bits()) - 1;
+    let mut bits = u128::from(negative) << (kind.storage_bits() - 1)
+        | exponent_mask << (kind.storage_bits() - 1 - kind.exponent_bits())
+        | payload;
+
+    if kind == FloatKind::X86Fp80 {
+        // the explicit integer bit
+        bits |= 1u128 << 63;
+    }
+
+    bits
+}
+
+/// Round the result of rounding to the precision of `kind` to the exponent range of `kind`
+///
+/// `rounded` must already have the precision of `kind` and `ordering` must be the
+/// direction in which it was rounded, so that subnormal results aren't double rounded
+pub(crate) fn round_to_kind(kind: FloatKind, (mut rounded, ordering): (Float, Ordering)) -> Float {
+    if let Some(exp) = rounded.get_exp() {
+        // rug uses the convention `0.1xxx * 2^exp` instead of IEEE's `1.xxx * 2^exp`
+        if exp - 1 > kind.max_exponent() {
+            let inf = if rounded.is_sign_negative() {
+                Special::NegInfinity
+            } else {
+                Special::Infinity
+            };
+            rounded.assign(inf);
+        } else {
+            let _ = rounded.subnormalize_round(kind.min_exponent() + 1, ordering, Round::Nearest);
+        }
+    }
+
+    rounded
+}
+
+/// The bit pattern of a non-NaN value that is exactly representable in `kind`
+pub(crate) fn to_bits(kind: FloatKind, value: &Float) -> u128 {
+    debug_assert!(!value.is_nan());
+
+    if kind == FloatKind::PpcFp128 {
+        let precision = PPC_HALF.precision();
+        let hi = round_to_kind(
+            PPC_HALF,
+            Float::with_val_round(precision, value, Round::Nearest),
+        );
+        let lo = if hi.is_finite() {
+            let lo = Float::with_val(value.prec() + 1, value - &hi);
+            round_to_kind(
+                PPC_HALF,
+                Float::with_val_round(precision, &lo, Round::Nearest),
+            )
+        } else {
+            Float::new(precision)
+        };
+
+        return to_bits(PPC_HALF, &hi) | to_bits(PPC_HALF, &lo) << 64;
+    }
+
+    let sign = u128::from(value.is_sign_negative()) << (kind.storage_bits() - 1);
+    let mantissa_bits = kind.mantissa_bits();
+    let exponent_offset = kind.storage_bits() - 1 - kind.exponent_bits();
+    let exponent_mask: u128 = (1 << kind.exponent_bits()) - 1;
+    let explicit_integer_bit = kind == FloatKind::X86Fp80;
+
+    if value.is_infinite() {
+        let integer_bit: u128 = if explicit_integer_bit { 1 << 63 } else { 0 };
+        return sign | exponent_mask << exponent_offset | integer_bit;
+    }
+
+    if value.is_zero() {
+        return sign;
+    }
+
+    let (significand, exp) = value.to_integer_exp().unwrap();
+
+    // value = significand * 2^exp, find the IEEE exponent of the value
+    let ieee_exp = value.get_exp().unwrap() - 1;
+    let biased_exp = ieee_exp.max(kind.min_exponent());
+    // the significand scaled such that the integer bit is at `mantissa_bits`
+    let shift = exp - (biased_exp - mantissa_bits as i32);
+    let significand = (significand.abs() << shift).to_u128().unwrap();
+
+    if ieee_exp < kind.min_exponent() {
+        // subnormal
+        sign | significand
+    } else {
+        let biased_exp = (biased_exp + kind.max_exponent()) as u128;
+        let mantissa = if explicit_integer_bit {
+            significand
+        } else {
+            significand & ((1u128 << mantissa_bits) - 1)
+        };
+
+        sign | biased_exp << exponent_offset | mantissa
+    }
+}
+
+/// The value of a bit pattern, NaNs lose their payload
+pub(crate) fn from_bits(kind: FloatKind, bits: u128) -> Float {
+    let bits = bits & storage_mask(kind);
+
+    if kind == FloatKind::PpcFp128 {
+        let hi = from_bits(PPC_HALF, bits & u128::from(u64::MAX));
+        if !hi.is_finite() {
+            return Float::with_val(kind.precision(), hi);
+        }
+        let lo = from_bits(PPC_HALF, bits >> 64);
+        let (sum, ordering) = Float::with_val_round(kind.precision(), &hi + &lo, Round::Nearest);
+        return round_to_kind(kind, (sum, ordering));
+    }
+
+    let precision = kind.precision();
+    let negative = bits >> (kind.storage_bits() - 1) != 0;
+    let mantissa_bits = kind.mantissa_bits();
+    let exponent_offset = kind.storage_bits() - 1 - kind.exponent_bits();
+    let exponent_mask: u128 = (1 << kind.exponent_bits()) - 1;
+    let biased_exp = (bits >> exponent_offset) & exponent_mask;
+
+    // the fraction, without the explicit integer bit of x86_fp80
+    let fraction = bits & ((1u128 << mantissa_bits) - 1);
+    let significand = if kind == FloatKind::X86Fp80 {
+        bits & ((1u128 << (mantissa_bits + 1)) - 1)
+    } else if biased_exp == 0 {
+        fraction
+    } else {
+        fraction | 1u128 << mantissa_bits
+    };
+
+    let value = if biased_exp == exponent_mask {
+        if fraction == 0 {
+            Float::with_val(precision, Special::Infinity)
+        } else {
+            Float::with_val(precision, Special::Nan)
+        }
+    } else {
+        let exp = (biased_exp as i32 - kind.max_exponent()).max(kind.min_exponent());
+        Float::with_val(precision, Integer::from(significand)) << (exp - mantissa_bits as i32)
+    };
+
+    if negative {
+        -value
+    } else {
+        value
+    }
+}
+
+/// Parse a hexadecimal float like `-0x1.8p3`, rounding it to `kind`
+pub(crate) fn parse_hex(kind: FloatKind, text: &str) -> Option<Float> {
+    let (negative, text) = match text.strip_prefix('-') {
+        Some(text) => (true, text),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+
+    let text = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))?;
+
+    let (mantissa, exp) = match text.find(['p', 'P']) {
+        Some(index) => (&text[..index], text[index + 1..].parse::<i32>().ok()?),
+        None => (text, 0),
+    };
+
+    let (int_digits, frac_digits) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int_digits.is_empty() && frac_digits.is_empty() {
+        return None;
+    }
+
+    let digits = [int_digits, frac_digits].concat();
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let significand = Integer::from_str_radix(&digits, 16).ok()?;
+
+    let exp = exp.checked_sub(i32::try_from(frac_digits.len()).ok()?.checked_mul(4)?)?;
+    let (value, ordering) = Float::with_val_round(kind.precision(), significand, Round::Nearest);
+    let value = round_to_kind(kind, (value << exp, ordering));
+
+    Some(if negative { -value } else { value })
+}
@@ -0,0 +1,315 @@
+use rug::Integer;
+
+/// A binary operation on integers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntBinOp {
+    Add,
+    Sub,
+    Mul,
+    UDiv,
+    SDiv,
+    URem,
+    SRem,
+    Shl,
+    LShr,
+    AShr,
+    And,
+    Or,
+    Xor,
+}
+
+/// The poison generating flags of an integer operation
+///
+/// Flags which don't apply to an operation are ignored
+/// (`nuw` and `nsw` apply to `add`, `sub`, `mul` and `shl`,
+/// `exact` applies to `udiv`, `sdiv`, `lshr` and `ashr`)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IntFlags {
+    /// The result is poison if it would overflow as an unsigned operation
+    pub nuw: bool,
+    /// The result is poison if it would overflow as a signed operation
+    pub nsw: bool,
+    /// The result is poison if any non-zero bits would be discarded
+    pub exact: bool,
+}
+
+impl IntFlags {
+    pub const NONE: Self = Self {
+        nuw: false,
+        nsw: false,
+        exact: false,
+    };
+}
+
+/// An integer comparison
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntPredicate {
+    Eq,
+    Ne,
+    Ugt,
+    Uge,
+    Ult,
+    Ule,
+    Sgt,
+    Sge,
+    Slt,
+    Sle,
+}
+
+impl IntBinOp {
+    pub fn name(self) -> &'static str {
+        match self {
+            IntBinOp::Add => "add",
+            IntBinOp::Sub => "sub",
+            IntBinOp::Mul => "mul",
+            IntBinOp::UDiv => "udiv",
+            IntBinOp::SDiv => "sdiv",
+            IntBinOp::URem => "urem",
+            IntBinOp::SRem => "srem",
+            IntBinOp::Shl => "shl",
+            IntBinOp::LShr => "lshr",
+            IntBinOp::AShr => "ashr",
+            IntBinOp::And => "and",
+            IntBinOp::Or => "or",
+            IntBinOp::Xor => "xor",
+        }
+    }
+}
+
+impl IntPredicate {
+    pub fn name(self) -> &'static str {
+        match self {
+            IntPredicate::Eq => "eq",
+            IntPredicate::Ne => "ne",
+            IntPredicate::Ugt => "ugt",
+            IntPredicate::Uge => "uge",
+            IntPredicate::Ult => "ult",
+            IntPredicate::Ule => "ule",
+            IntPredicate::Sgt => "sgt",
+            IntPredicate::Sge => "sge",
+            IntPredicate::Slt => "slt",
+            IntPredicate::Sle => "sle",
+        }
+    }
+
+    #[inline]
+    pub fn is_signed(self) -> bool {
+        matches!(
+            self,
+            IntPredicate::Sgt | IntPredicate::Sge | IntPredicate::Slt | IntPredicate::Sle
+        )
+    }
+}
+
+/// Interpret the bit pattern of a `bits` wide integer as a two's complement value
+fn signed(pattern: &Integer, bits: u32) -> Integer {
+    Integer::from(pattern.keep_signed_bits_ref(bits))
+}
+
+fn fits_signed(value: &Integer, bits: u32) -> bool {
+    value.signed_bits() <= bits
+}
+
+fn fits_unsigned(value: &Integer, bits: u32) -> bool {
+    *value >= 0 && value.significant_bits() <= bits
+}
+
+/// The bit pattern of `value` wrapped to `bits`
+fn wrap(value: Integer, bits: u32) -> Integer {
+    value.keep_bits(bits)
+}
+
+/// Fold `lhs op rhs` where both are the bit patterns of `bits` wide integers
+///
+/// The result is the bit pattern of the result, or `None` if the result is poison:
+/// division or remainder by zero, signed division overflow, shifting by at least
+/// `bits`, or violating any of the `flags`
+pub fn fold_int_binop(
+    op: IntBinOp,
+    flags: IntFlags,
+    bits: u32,
+    lhs: &Integer,
+    rhs: &Integer,
+) -> Option<Integer> {
+    debug_assert!(fits_unsigned(lhs, bits) && fits_unsigned(rhs, bits));
+
+    let result = match op {
+        IntBinOp::Add | IntBinOp::Sub | IntBinOp::Mul => {
+            let apply = |a: &Integer, b: &Integer| -> Integer {
+                match op {
+                    IntBinOp::Add => Integer::from(a + b),
+                    IntBinOp::Sub => Integer::from(a - b),
+                    _ => Integer::from(a * b),
+                }
+            };
+
+            let unsigned = apply(lhs, rhs);
+            if flags.nuw && !fits_unsigned(&unsigned, bits) {
+                return None;
+            }
+
+            if flags.nsw && !fits_signed(&apply(&signed(lhs, bits), &signed(rhs, bits)), bits) {
+                return None;
+            }
+
+            unsigned
+        }
+        IntBinOp::UDiv | IntBinOp::URem => {
+            if *rhs == 0 {
+                return None;
+            }
+
+            let (quotient, remainder) = lhs.clone().div_rem(rhs.clone());
+            if op == IntBinOp::URem {
+                remainder
+            } else if flags.exact && remainder != 0 {
+                return None;
+            } else {
+                quotient
+            }
+        }
+        IntBinOp::SDiv | IntBinOp::SRem => {
+            if *rhs == 0 {
+                return None;
+            }
+
+            let lhs = signed(lhs, bits);
+            let rhs = signed(rhs, bits);
+
+            // `MIN / -1` overflows
+            let quotient_overflows = rhs == -1 && !fits_signed(&Integer::from(-&lhs), bits);
+            if quotient_overflows {
+                return None;
+            }
+
+            // rounds towards zero, so the remainder has the sign of `lhs`
+            let (quotient, remainder) = lhs.div_rem(rhs);
+            if op == IntBinOp::SRem {
+                remainder
+            } else if flags.exact && remainder != 0 {
+                return None;
+            } else {
+                quotient
+            }
+        }
+        IntBinOp::Shl | IntBinOp::LShr | IntBinOp::AShr => {
+            let amount = rhs.to_u32().filter(|&amount| amount < bits)?;
+
+            match op {
+                IntBinOp::Shl => {
+                    let shifted = Integer::from(lhs << amount);
+                    let result = wrap(shifted.clone(), bits);
+
+                    if flags.nuw && result != shifted {
+                        return None;
+                    }
+
+                    // all of the shifted out bits must match the sign bit of the result
+                    if flags.nsw
+                        && Integer::from(signed(&result, bits) >> amount) != signed(lhs, bits)
+                    {
+                        return None;
+                    }
+
+                    result
+                }
+                _ => {
+                    if flags.exact && lhs.clone().keep_bits(amount) != 0 {
+                        return None;
+                    }
+
+                    if op == IntBinOp::LShr {
+                        Integer::from(lhs >> amount)
+                    } else {
+                        Integer::from(signed(lhs, bits) >> amount)
+                    }
+                }
+            }
+        }
+        IntBinOp::And => Integer::from(lhs & rhs),
+        IntBinOp::Or => Integer::from(lhs | rhs),
+        IntBinOp::Xor => Integer::from(lhs ^ rhs),
+    };
+
+    Some(wrap(result, bits))
+}
+
+/// Compare `lhs` and `rhs`, which are the bit patterns of `bits` wide integers
+pub fn fold_icmp(pred: IntPredicate, bits: u32, lhs: &Integer, rhs: &Integer) -> bool {
+    debug_assert!(fits_unsigned(lhs, bits) && fits_unsigned(rhs, bits));
+
+    let ordering = if pred.is_signed() {
+        signed(lhs, bits).cmp(&signed(rhs, bits))
+    } else {
+        lhs.cmp(rhs)
+    };
+
+    match pred {
+        IntPredicate::Eq => ordering.is_eq(),
+        IntPredicate::Ne => ordering.is_ne(),
+        IntPredicate::Ugt | IntPredicate::Sgt => ordering.is_gt(),
+        IntPredicate::Uge | IntPredicate::Sge => ordering.is_ge(),
+        IntPredicate::Ult | IntPredicate::Slt => ordering.is_lt(),
+        IntPredicate::Ule | IntPredicate::Sle => ordering.is_le(),
+    }
+}
+
+#[test]
+fn test_fold() {
+    let int = |x: i64| Integer::from(x).keep_bits(8);
+    let fold = |op, flags, a: i64, b: i64| {
+        fold_int_binop(op, flags, 8, &int(a), &int(b)).map(|x| x.to_u8().unwrap())
+    };
+
+    let nuw = IntFlags {
+        nuw: true,
+        ..IntFlags::NONE
+    };
+    let nsw = IntFlags {
+        nsw: true,
+        ..IntFlags::NONE
+    };
+    let exact = IntFlags {
+        exact: true,
+        ..IntFlags::NONE
+    };
+
+    assert_eq!(fold(IntBinOp::Add, IntFlags::NONE, 200, 100), Some(44));
+    assert_eq!(fold(IntBinOp::Add, nuw, 200, 100), None);
+    assert_eq!(fold(IntBinOp::Add, nsw, 200, 100), Some(44));
+    assert_eq!(fold(IntBinOp::Add, nsw, 100, 100), None);
+    assert_eq!(fold(IntBinOp::Sub, nuw, 1, 2), None);
+    assert_eq!(fold(IntBinOp::Sub, nsw, 1, 2), Some(0xff));
+    assert_eq!(fold(IntBinOp::Mul, nsw, -16, 8), Some(0x80));
+    assert_eq!(fold(IntBinOp::Mul, nsw, 16, 8), None);
+
+    assert_eq!(fold(IntBinOp::UDiv, IntFlags::NONE, 7, 0), None);
+    assert_eq!(fold(IntBinOp::UDiv, IntFlags::NONE, -1, 2), Some(127));
+    assert_eq!(fold(IntBinOp::UDiv, exact, 7, 2), None);
+    assert_eq!(
+        fold(IntBinOp::SDiv, IntFlags::NONE, -7, 2),
+        Some(-3i8 as u8)
+    );
+    assert_eq!(fold(IntBinOp::SDiv, IntFlags::NONE, -128, -1), None);
+    assert_eq!(
+        fold(IntBinOp::SRem, IntFlags::NONE, -7, 2),
+        Some(-1i8 as u8)
+    );
+    assert_eq!(fold(IntBinOp::SRem, IntFlags::NONE, -128, -1), None);
+    assert_eq!(fold(IntBinOp::URem, IntFlags::NONE, -7, 2), Some(1));
+
+    assert_eq!(fold(IntBinOp::Shl, IntFlags::NONE, 0x81, 1), Some(2));
+    assert_eq!(fold(IntBinOp::Shl, nuw, 0x81, 1), None);
+    assert_eq!(fold(IntBinOp::Shl, nsw, -1, 7), Some(0x80));
+    assert_eq!(fold(IntBinOp::Shl, nsw, 0x40, 1), None);
+    assert_eq!(fold(IntBinOp::Shl, IntFlags::NONE, 1, 8), None);
+    assert_eq!(fold(IntBinOp::LShr, IntFlags::NONE, -128, 7), Some(1));
+    assert_eq!(fold(IntBinOp::AShr, IntFlags::NONE, -128, 7), Some(0xff));
+    assert_eq!(fold(IntBinOp::AShr, exact, 3, 1), None);
+
+    assert_eq!(fold(IntBinOp::Xor, IntFlags::NONE, 0xf0, 0xff), Some(0x0f));
+
+    assert!(fold_icmp(IntPredicate::Ugt, 8, &int(-1), &int(1)));
+    assert!(fold_icmp(IntPredicate::Slt, 8, &int(-1), &int(1)));
+    assert!(fold_icmp(IntPredicate::Ne, 8, &int(-1), &int(1)));
+}
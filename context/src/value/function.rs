@@ -0,0 +1,164 @@
+use std::{cell::Cell, hash::Hash};
+
+use init::{
+    layout_provider::{HasLayoutProvider, SizedLayoutProvider},
+    Ctor,
+};
+
+use crate::{
+    types::{FunctionTy, PointerTy, Type},
+    AllocContext,
+};
+
+use super::{basic_block::BasicBlocks, Argument, BasicBlock, Linkage};
+
+/// A function, the value is its address
+///
+/// Functions are unique by identity, and are created by
+/// [`Module::add_function`](crate::Module::add_function).
+/// A function without any blocks is a declaration
+pub struct FunctionInfo<'ctx> {
+    name: Option<istr::IStr>,
+    function_ty: FunctionTy<'ctx>,
+    linkage: Cell<Linkage>,
+    arguments: &'ctx [Argument<'ctx>],
+    pub(super) first_block: Cell<Option<BasicBlock<'ctx>>>,
+    pub(super) last_block: Cell<Option<BasicBlock<'ctx>>>,
+}
+
+impl Eq for FunctionInfo<'_> {}
+impl PartialEq for FunctionInfo<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self, other)
+    }
+}
+
+impl Hash for FunctionInfo<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        core::ptr::hash(self, state)
+    }
+}
+
+impl core::fmt::Debug for FunctionInfo<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "@{name}"),
+            None => write!(f, "@<unnamed>"),
+        }
+    }
+}
+
+pub type Function<'ctx> = super::Val<'ctx, FunctionInfo<'ctx>>;
+
+unsafe impl<'ctx> super::ValueInfo for FunctionInfo<'ctx> {
+    const TAG: super::ValueTag = super::ValueTag::Function;
+    type Flags = ();
+}
+
+impl<'ctx> Function<'ctx> {
+    pub(crate) fn new(
+        ctx: AllocContext<'ctx>,
+        ty: PointerTy<'ctx>,
+        name: Option<istr::IStr>,
+        function_ty: FunctionTy<'ctx>,
+    ) -> Function<'ctx> {
+        let arguments = function_ty
+            .arguments_tys()
+            .iter()
+            .zip(0..)
+            .map(|(&ty, index)| Argument::new(ctx, ty, index))
+            .collect::<Vec<_>>();
+        let arguments = ctx.alloc_slice_copy(&arguments);
+
+        let init = FunctionInit {
+            name,
+            function_ty,
+            arguments,
+        };
+        let function = Self::create_in_place(ctx, ty.erase(), init, ());
+
+        for argument in arguments {
+            argument.set_parent(function)
+        }
+
+        function
+    }
+
+    #[inline]
+    pub fn name(self) -> Option<istr::IStr> {
+        self.info().name
+    }
+
+    /// The signature of the function, the function itself is a pointer
+    #[inline]
+    pub fn function_ty(self) -> FunctionTy<'ctx> {
+        self.info().function_ty
+    }
+
+    #[inline]
+    pub fn output_ty(self) -> Type<'ctx> {
+        self.function_ty().output_ty()
+    }
+
+    #[inline]
+    pub fn linkage(self) -> Linkage {
+        self.info().linkage.get()
+    }
+
+    #[inline]
+    pub fn set_linkage(self, linkage: Linkage) {
+        self.info().linkage.set(linkage)
+    }
+
+    #[inline]
+    pub fn arguments(self) -> &'ctx [Argument<'ctx>] {
+        self.info().arguments
+    }
+
+    /// The first block, which is run when the function is called
+    #[inline]
+    pub fn entry_block(self) -> Option<BasicBlock<'ctx>> {
+        self.info().first_block.get()
+    }
+
+    #[inline]
+    pub fn last_block(self) -> Option<BasicBlock<'ctx>> {
+        self.info().last_block.get()
+    }
+
+    pub fn blocks(self) -> BasicBlocks<'ctx> {
+        BasicBlocks {
+            next: self.entry_block(),
+        }
+    }
+
+    /// Whether the function has no body, so it must be defined in another module
+    #[inline]
+    pub fn is_declaration(self) -> bool {
+        self.entry_block().is_none()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct FunctionInit<'ctx> {
+    name: Option<istr::IStr>,
+    function_ty: FunctionTy<'ctx>,
+    arguments: &'ctx [Argument<'ctx>],
+}
+
+impl<'ctx> Ctor<FunctionInit<'ctx>> for FunctionInfo<'ctx> {
+    fn init(uninit: init::Uninit<'_, Self>, args: FunctionInit<'ctx>) -> init::Init<'_, Self> {
+        uninit.write(Self {
+            name: args.name,
+            function_ty: args.function_ty,
+            linkage: Cell::new(Linkage::External),
+            arguments: args.arguments,
+            first_block: Cell::new(None),
+            last_block: Cell::new(None),
+        })
+    }
+}
+
+impl<'ctx> HasLayoutProvider<FunctionInit<'ctx>> for FunctionInfo<'ctx> {
+    type LayoutProvider = SizedLayoutProvider;
+}
@@ -0,0 +1,173 @@
+use std::{cell::Cell, hash::Hash};
+
+use init::{
+    layout_provider::{HasLayoutProvider, SizedLayoutProvider},
+    Ctor,
+};
+
+use crate::{
+    error::{check_constant, check_ty, unwrap_or_panic},
+    layout::Align,
+    types::{AddressSpace, PointerTy, Type},
+    AllocContext, Error,
+};
+
+use super::{Linkage, Value};
+
+/// A global variable, the value is its address
+///
+/// Globals are unique by identity, and are created by [`Module::add_global`](crate::Module::add_global)
+pub struct GlobalVariableInfo<'ctx> {
+    name: Option<istr::IStr>,
+    value_ty: Type<'ctx>,
+    initializer: Cell<Option<Value<'ctx>>>,
+    constant: Cell<bool>,
+    align: Cell<Option<Align>>,
+    linkage: Cell<Linkage>,
+}
+
+impl Eq for GlobalVariableInfo<'_> {}
+impl PartialEq for GlobalVariableInfo<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self, other)
+    }
+}
+
+impl Hash for GlobalVariableInfo<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        core::ptr::hash(self, state)
+    }
+}
+
+impl core::fmt::Debug for GlobalVariableInfo<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name {
+            Some(name) => write!(f, "@{name}"),
+            None => write!(f, "@<unnamed>"),
+        }
+    }
+}
+
+pub type GlobalVariable<'ctx> = super::Val<'ctx, GlobalVariableInfo<'ctx>>;
+
+unsafe impl<'ctx> super::ValueInfo for GlobalVariableInfo<'ctx> {
+    const TAG: super::ValueTag = super::ValueTag::GlobalVariable;
+    type Flags = ();
+}
+
+impl<'ctx> GlobalVariable<'ctx> {
+    pub(crate) fn new(
+        ctx: AllocContext<'ctx>,
+        ty: PointerTy<'ctx>,
+        name: Option<istr::IStr>,
+        value_ty: Type<'ctx>,
+    ) -> GlobalVariable<'ctx> {
+        Self::create_in_place(ctx, ty.erase(), GlobalVariableInit { name, value_ty }, ())
+    }
+
+    #[inline]
+    pub fn name(self) -> Option<istr::IStr> {
+        self.info().name
+    }
+
+    /// The type of the value stored in the global, the global itself is a pointer
+    #[inline]
+    pub fn value_ty(self) -> Type<'ctx> {
+        self.info().value_ty
+    }
+
+    #[inline]
+    pub fn address_space(self) -> AddressSpace {
+        self.ty()
+            .cast::<crate::types::PointerInfo>()
+            .address_space()
+    }
+
+    /// The initial value of the global, or `None` if it is only declared
+    #[inline]
+    pub fn initializer(self) -> Option<Value<'ctx>> {
+        self.info().initializer.get()
+    }
+
+    /// # Panics
+    ///
+    /// If the type of `value` isn't the value type of this global
+    #[track_caller]
+    pub fn set_initializer(self, value: Option<Value<'ctx>>) {
+        unwrap_or_panic(self.try_set_initializer(value))
+    }
+
+    pub fn try_set_initializer(self, value: Option<Value<'ctx>>) -> Result<(), Error> {
+        if let Some(value) = value {
+            check_ty(self.value_ty(), value.ty())?;
+            check_constant(value)?;
+        }
+
+        self.info().initializer.set(value);
+        Ok(())
+    }
+
+    /// Whether the global is never written to
+    #[inline]
+    pub fn is_constant(self) -> bool {
+        self.info().constant.get()
+    }
+
+    #[inline]
+    pub fn set_constant(self, constant: bool) {
+        self.info().constant.set(constant)
+    }
+
+    /// The alignment of the global, or `None` to use the ABI alignment of its value type
+    #[inline]
+    pub fn align(self) -> Option<Align> {
+        self.info().align.get()
+    }
+
+    #[inline]
+    pub fn set_align(self, align: Option<Align>) {
+        self.info().align.set(align)
+    }
+
+    #[inline]
+    pub fn linkage(self) -> Linkage {
+        self.info().linkage.get()
+    }
+
+    #[inline]
+    pub fn set_linkage(self, linkage: Linkage) {
+        self.info().linkage.set(linkage)
+    }
+
+    /// Whether the global has no initializer, so it must be defined in another module
+    #[inline]
+    pub fn is_declaration(self) -> bool {
+        self.initializer().is_none()
+    }
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct GlobalVariableInit<'ctx> {
+    name: Option<istr::IStr>,
+    value_ty: Type<'ctx>,
+}
+
+impl<'ctx> Ctor<GlobalVariableInit<'ctx>> for GlobalVariableInfo<'ctx> {
+    fn init(
+        uninit: init::Uninit<'_, Self>,
+        args: GlobalVariableInit<'ctx>,
+    ) -> init::Init<'_, Self> {
+        uninit.write(Self {
+            name: args.name,
+            value_ty: args.value_ty,
+            initializer: Cell::new(None),
+            constant: Cell::new(false),
+            align: Cell::new(None),
+            linkage: Cell::new(Linkage::External),
+        })
+    }
+}
+
+impl<'ctx> HasLayoutProvider<GlobalVariableInit<'ctx>> for GlobalVariableInfo<'ctx> {
+    type LayoutProvider = SizedLayoutProvider;
+}
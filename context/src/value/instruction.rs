@@ -0,0 +1,271 @@
+use std::{cell::Cell, hash::Hash};
+
+use init::{
+    layout_provider::{HasLayoutProvider, SizedLayoutProvider},
+    Ctor,
+};
+
+use crate::{
+    error::{describe, unwrap_or_panic},
+    types::Type,
+    AllocContext, Error,
+};
+
+use super::{BasicBlock, Function, Opcode, Value};
+
+/// An operation in a basic block, the value is its result
+///
+/// Instructions are unique by identity, and are created by
+/// [`Context::instruction`](crate::Context::instruction)
+pub struct InstructionInfo<'ctx> {
+    opcode: Opcode<'ctx>,
+    name: Cell<Option<istr::IStr>>,
+    pub(super) parent: Cell<Option<BasicBlock<'ctx>>>,
+    pub(super) prev: Cell<Option<Instruction<'ctx>>>,
+    pub(super) next: Cell<Option<Instruction<'ctx>>>,
+    // phis and switches grow into the spare capacity at the end of the
+    // buffers, which are reallocated at twice the size once they are full
+    operands: Cell<&'ctx [Cell<Value<'ctx>>]>,
+    blocks: Cell<&'ctx [Cell<BasicBlock<'ctx>>]>,
+    operand_buf: Cell<&'ctx [Cell<Value<'ctx>>]>,
+    block_buf: Cell<&'ctx [Cell<BasicBlock<'ctx>>]>,
+    erased: Cell<bool>,
+}
+
+impl Eq for InstructionInfo<'_> {}
+impl PartialEq for InstructionInfo<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::eq(self, other)
+    }
+}
+
+impl Hash for InstructionInfo<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        core::ptr::hash(self, state)
+    }
+}
+
+impl core::fmt::Debug for InstructionInfo<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name.get() {
+            Some(name) => write!(f, "%{name}"),
+            None => write!(f, "%<unnamed>"),
+        }
+    }
+}
+
+pub type Instruction<'ctx> = super::Val<'ctx, InstructionInfo<'ctx>>;
+
+unsafe impl<'ctx> super::ValueInfo for InstructionInfo<'ctx> {
+    const TAG: super::ValueTag = super::ValueTag::Instruction;
+    type Flags = ();
+}
+
+impl<'ctx> Instruction<'ctx> {
+    /// `ty` must be the result type of `opcode` applied to `operands`
+    pub(crate) fn new(
+        ctx: AllocContext<'ctx>,
+        ty: Type<'ctx>,
+        opcode: Opcode<'ctx>,
+        operands: &[Value<'ctx>],
+        blocks: &[BasicBlock<'ctx>],
+    ) -> Instruction<'ctx> {
+        let init = InstructionInit {
+            opcode,
+            operands: ctx.alloc_slice_fill_iter(operands.iter().copied().map(Cell::new)),
+            blocks: ctx.alloc_slice_fill_iter(blocks.iter().copied().map(Cell::new)),
+        };
+
+        Self::create_in_place(ctx, ty, init, ())
+    }
+
+    #[inline]
+    pub fn opcode(self) -> Opcode<'ctx> {
+        self.info().opcode
+    }
+
+    #[inline]
+    pub fn name(self) -> Option<istr::IStr> {
+        self.info().name.get()
+    }
+
+    #[inline]
+    pub fn set_name(self, name: impl crate::name::Name) {
+        self.info().name.set(name.to_name())
+    }
+
+    #[inline]
+    pub fn is_terminator(self) -> bool {
+        self.opcode().is_terminator()
+    }
+
+    /// The block this instruction is in, or `None` if it hasn't been inserted yet
+    #[inline]
+    pub fn parent(self) -> Option<BasicBlock<'ctx>> {
+        self.info().parent.get()
+    }
+
+    /// The function this instruction is in
+    #[inline]
+    pub fn function(self) -> Option<Function<'ctx>> {
+        self.parent().map(BasicBlock::parent)
+    }
+
+    /// Whether this was erased with [`Context::erase_instruction`](crate::Context::erase_instruction)
+    #[inline]
+    pub fn is_erased(self) -> bool {
+        self.info().erased.get()
+    }
+
+    /// The previous instruction in the same block
+    #[inline]
+    pub fn prev(self) -> Option<Instruction<'ctx>> {
+        self.info().prev.get()
+    }
+
+    /// The next instruction in the same block
+    #[inline]
+    pub fn next(self) -> Option<Instruction<'ctx>> {
+        self.info().next.get()
+    }
+
+    /// Insert this instruction into the block of `before`, right before it
+    ///
+    /// # Panics
+    ///
+    /// If `before` isn't in a block, or for the same reasons as [`BasicBlock::append`]
+    #[track_caller]
+    pub fn insert_before(self, before: Instruction<'ctx>) {
+        unwrap_or_panic(self.try_insert_before(before))
+    }
+
+    pub fn try_insert_before(self, before: Instruction<'ctx>) -> Result<(), Error> {
+        let block = before.parent().ok_or_else(|| Error::NotInserted {
+            value: describe(before),
+        })?;
+
+        block.check_insert(self)?;
+        block.link(self, before.prev(), Some(before));
+        Ok(())
+    }
+
+    #[inline]
+    pub fn num_operands(self) -> usize {
+        self.info().operands.get().len()
+    }
+
+    #[inline]
+    pub fn operand(self, index: usize) -> Value<'ctx> {
+        self.info().operands.get()[index].get()
+    }
+
+    /// The value operands, see [`Opcode`] for what they are for each instruction
+    pub fn operands(self) -> impl ExactSizeIterator<Item = Value<'ctx>> + Clone + 'ctx {
+        self.info().operands.get().iter().map(Cell::get)
+    }
+
+    /// The block operands, see [`Opcode`] for what they are for each instruction
+    pub fn blocks(self) -> impl ExactSizeIterator<Item = BasicBlock<'ctx>> + Clone + 'ctx {
+        self.info().blocks.get().iter().map(Cell::get)
+    }
+
+    /// The blocks control may continue in after this instruction,
+    /// which is empty unless this is a terminator
+    pub fn successors(self) -> impl ExactSizeIterator<Item = BasicBlock<'ctx>> + Clone + 'ctx {
+        let blocks = if self.is_terminator() {
+            self.info().blocks.get()
+        } else {
+            &[]
+        };
+
+        blocks.iter().map(Cell::get)
+    }
+
+    /// The incoming values of a phi and the blocks they come from
+    pub fn incoming(
+        self,
+    ) -> impl ExactSizeIterator<Item = (Value<'ctx>, BasicBlock<'ctx>)> + Clone + 'ctx {
+        debug_assert!(matches!(self.opcode(), Opcode::Phi { .. }));
+        self.operands().zip(self.blocks())
+    }
+
+    pub(crate) fn operand_cells(self) -> &'ctx [Cell<Value<'ctx>>] {
+        self.info().operands.get()
+    }
+
+    /// Remove this from its block and drop its operands, the uses of the
+    /// operands must already be removed
+    pub(crate) fn erase_unchecked(self) {
+        if let Some(block) = self.parent() {
+            block.unlink(self);
+        }
+
+        let info = self.info();
+        info.operands.set(&[]);
+        info.blocks.set(&[]);
+        info.operand_buf.set(&[]);
+        info.block_buf.set(&[]);
+        info.erased.set(true);
+    }
+
+    /// Add an operand and a block, for phis and switches
+    pub(crate) fn push_operand(
+        self,
+        ctx: AllocContext<'ctx>,
+        value: Value<'ctx>,
+        block: BasicBlock<'ctx>,
+    ) {
+        let info = self.info();
+        push_cell(ctx, &info.operands, &info.operand_buf, value);
+        push_cell(ctx, &info.blocks, &info.block_buf, block);
+    }
+}
+
+/// Append `item` to `items`, which is always a prefix of `buf`
+fn push_cell<'ctx, T: Copy>(
+    ctx: AllocContext<'ctx>,
+    items: &Cell<&'ctx [Cell<T>]>,
+    buf: &Cell<&'ctx [Cell<T>]>,
+    item: T,
+) {
+    let len = items.get().len();
+
+    if buf.get().len() == len {
+        let old = items.get();
+        let capacity = (len * 2).max(4);
+        // the spare slots are never read, so fill them with anything
+        let new = (0..capacity).map(|i| Cell::new(old.get(i).map_or(item, Cell::get)));
+        buf.set(ctx.alloc_slice_fill_iter(new));
+    }
+
+    let buf = buf.get();
+    buf[len].set(item);
+    items.set(&buf[..=len]);
+}
+
+pub(crate) struct InstructionInit<'ctx> {
+    opcode: Opcode<'ctx>,
+    operands: &'ctx [Cell<Value<'ctx>>],
+    blocks: &'ctx [Cell<BasicBlock<'ctx>>],
+}
+
+impl<'ctx> Ctor<InstructionInit<'ctx>> for InstructionInfo<'ctx> {
+    fn init(uninit: init::Uninit<'_, Self>, args: InstructionInit<'ctx>) -> init::Init<'_, Self> {
+        uninit.write(Self {
+            opcode: args.opcode,
+            name: Cell::new(None),
+            parent: Cell::new(None),
+            prev: Cell::new(None),
+            next: Cell::new(None),
+            operands: Cell::new(args.operands),
+            blocks: Cell::new(args.blocks),
+            operand_buf: Cell::new(args.operands),
+            block_buf: Cell::new(args.blocks),
+            erased: Cell::new(false),
+        })
+    }
+}
+
+impl<'ctx> HasLayoutProvider<InstructionInit<'ctx>> for InstructionInfo<'ctx> {
+    type LayoutProvider = SizedLayoutProvider;
+}
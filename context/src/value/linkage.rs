@@ -0,0 +1,45 @@
+/// How a global or function is visible to and merged with other modules when linking
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Linkage {
+    /// Visible to other modules, and must be defined exactly once
+    #[default]
+    External,
+    /// Only visible inside this module
+    Internal,
+    /// Like [`Linkage::Internal`], but the symbol isn't in the object file's symbol table
+    Private,
+    /// May be replaced by a definition in another module
+    Weak,
+    /// Like [`Linkage::Weak`], but all definitions must be equivalent
+    WeakOdr,
+    /// Like [`Linkage::Weak`], but may be discarded if unused
+    LinkOnce,
+    /// Like [`Linkage::LinkOnce`], but all definitions must be equivalent
+    LinkOnceOdr,
+    /// A zero initialized global, merged with other common globals of the same name
+    Common,
+    /// A declaration which is null if it isn't defined in any module
+    ExternWeak,
+}
+
+impl Linkage {
+    pub fn name(self) -> &'static str {
+        match self {
+            Linkage::External => "external",
+            Linkage::Internal => "internal",
+            Linkage::Private => "private",
+            Linkage::Weak => "weak",
+            Linkage::WeakOdr => "weak_odr",
+            Linkage::LinkOnce => "linkonce",
+            Linkage::LinkOnceOdr => "linkonce_odr",
+            Linkage::Common => "common",
+            Linkage::ExternWeak => "extern_weak",
+        }
+    }
+
+    /// Whether other modules can see this symbol
+    #[inline]
+    pub fn is_external(self) -> bool {
+        !matches!(self, Linkage::Internal | Linkage::Private)
+    }
+}
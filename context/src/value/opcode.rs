@@ -0,0 +1,199 @@
+use crate::{
+    layout::Align,
+    types::{FunctionTy, Type},
+};
+
+use super::{IntBinOp, IntFlags, IntPredicate};
+
+/// The operation an [`Instruction`](super::Instruction) performs
+///
+/// The documentation of each variant lists the value operands, then the block operands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode<'ctx> {
+    /// `[lhs, rhs]`, integers or vectors of integers of the same type
+    IntBinary { op: IntBinOp, flags: IntFlags },
+    /// `[lhs, rhs]`, floats or vectors of floats of the same type
+    FloatBinary(FloatBinOp),
+    /// `[value]`, a float or vector of floats
+    FNeg,
+    /// `[lhs, rhs]`, integers or pointers (or vectors of them) of the same type,
+    /// the result is an `i1` (or a vector of `i1`)
+    ICmp(IntPredicate),
+    /// `[lhs, rhs]`, floats or vectors of floats of the same type,
+    /// the result is an `i1` (or a vector of `i1`)
+    FCmp(FloatPredicate),
+    /// `[value]`, converted to `ty`
+    Cast { op: CastOp, ty: Type<'ctx> },
+    /// `[len]`, an integer number of `allocated_ty` to allocate on the stack
+    Alloca {
+        allocated_ty: Type<'ctx>,
+        align: Option<Align>,
+    },
+    /// `[ptr]`, loads a `ty`
+    Load {
+        ty: Type<'ctx>,
+        align: Option<Align>,
+    },
+    /// `[value, ptr]`
+    Store { align: Option<Align> },
+    /// `[base, indices..]`, see [`Context::const_gep`](crate::Context::const_gep)
+    GetElementPtr {
+        source_ty: Type<'ctx>,
+        inbounds: bool,
+    },
+    /// `[callee, arguments..]`
+    Call { function_ty: FunctionTy<'ctx> },
+    /// `[condition, if_true, if_false]`, the condition is an `i1`, or a vector
+    /// of `i1` to select each lane
+    Select,
+    /// `[values..]`, `[blocks..]`, the value incoming from each predecessor
+    Phi { ty: Type<'ctx> },
+    /// `[]`, `[target]`
+    Br,
+    /// `[condition]`, `[if_true, if_false]`
+    CondBr,
+    /// `[value, cases..]`, `[default, targets..]`, the cases are distinct integer constants
+    Switch,
+    /// `[]` or `[value]`, which must match the output type of the function
+    Ret,
+    /// `[]`
+    Unreachable,
+}
+
+/// A binary operation on floats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatBinOp {
+    FAdd,
+    FSub,
+    FMul,
+    FDiv,
+    FRem,
+}
+
+/// A float comparison, ordered predicates are false if either operand is NaN,
+/// unordered ones are true
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FloatPredicate {
+    False,
+    Oeq,
+    Ogt,
+    Oge,
+    Olt,
+    Ole,
+    One,
+    Ord,
+    Ueq,
+    Ugt,
+    Uge,
+    Ult,
+    Ule,
+    Une,
+    Uno,
+    True,
+}
+
+/// A conversion between types, applied to each lane of vectors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CastOp {
+    Trunc,
+    ZExt,
+    SExt,
+    FPTrunc,
+    FPExt,
+    FPToUI,
+    FPToSI,
+    UIToFP,
+    SIToFP,
+    PtrToInt,
+    IntToPtr,
+    BitCast,
+    AddrSpaceCast,
+}
+
+impl Opcode<'_> {
+    pub fn name(self) -> &'static str {
+        match self {
+            Opcode::IntBinary { op, .. } => op.name(),
+            Opcode::FloatBinary(op) => op.name(),
+            Opcode::FNeg => "fneg",
+            Opcode::ICmp(_) => "icmp",
+            Opcode::FCmp(_) => "fcmp",
+            Opcode::Cast { op, .. } => op.name(),
+            Opcode::Alloca { .. } => "alloca",
+            Opcode::Load { .. } => "load",
+            Opcode::Store { .. } => "store",
+            Opcode::GetElementPtr { .. } => "getelementptr",
+            Opcode::Call { .. } => "call",
+            Opcode::Select => "select",
+            Opcode::Phi { .. } => "phi",
+            Opcode::Br | Opcode::CondBr => "br",
+            Opcode::Switch => "switch",
+            Opcode::Ret => "ret",
+            Opcode::Unreachable => "unreachable",
+        }
+    }
+
+    /// Whether this ends a basic block
+    #[inline]
+    pub fn is_terminator(self) -> bool {
+        matches!(
+            self,
+            Opcode::Br | Opcode::CondBr | Opcode::Switch | Opcode::Ret | Opcode::Unreachable
+        )
+    }
+}
+
+impl FloatBinOp {
+    pub fn name(self) -> &'static str {
+        match self {
+            FloatBinOp::FAdd => "fadd",
+            FloatBinOp::FSub => "fsub",
+            FloatBinOp::FMul => "fmul",
+            FloatBinOp::FDiv => "fdiv",
+            FloatBinOp::FRem => "frem",
+        }
+    }
+}
+
+impl FloatPredicate {
+    pub fn name(self) -> &'static str {
+        match self {
+            FloatPredicate::False => "false",
+            FloatPredicate::Oeq => "oeq",
+            FloatPredicate::Ogt => "ogt",
+            FloatPredicate::Oge => "oge",
+            FloatPredicate::Olt => "olt",
+            FloatPredicate::Ole => "ole",
+            FloatPredicate::One => "one",
+            FloatPredicate::Ord => "ord",
+            FloatPredicate::Ueq => "ueq",
+            FloatPredicate::Ugt => "ugt",
+            FloatPredicate::Uge => "uge",
+            FloatPredicate::Ult => "ult",
+            FloatPredicate::Ule => "ule",
+            FloatPredicate::Une => "une",
+            FloatPredicate::Uno => "uno",
+            FloatPredicate::True => "true",
+        }
+    }
+}
+
+impl CastOp {
+    pub fn name(self) -> &'static str {
+        match self {
+            CastOp::Trunc => "trunc",
+            CastOp::ZExt => "zext",
+            CastOp::SExt => "sext",
+            CastOp::FPTrunc => "fptrunc",
+            CastOp::FPExt => "fpext",
+            CastOp::FPToUI => "fptoui",
+            CastOp::FPToSI => "fptosi",
+            CastOp::UIToFP => "uitofp",
+            CastOp::SIToFP => "sitofp",
+            CastOp::PtrToInt => "ptrtoint",
+            CastOp::IntToPtr => "inttoptr",
+            CastOp::BitCast => "bitcast",
+            CastOp::AddrSpaceCast => "addrspacecast",
+        }
+    }
+}
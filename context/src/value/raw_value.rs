@@ -30,6 +30,18 @@ impl<'ctx> Hash for Value<'ctx> {
         match self.unpack() {
             UnpackedValue::ConstAggrZero(x) => x.hash(state),
             UnpackedValue::ConstInt(x) => x.hash(state),
+            UnpackedValue::ConstFloat(x) => x.hash(state),
+            UnpackedValue::ConstArray(x) => x.hash(state),
+            UnpackedValue::ConstStruct(x) => x.hash(state),
+            UnpackedValue::ConstVector(x) => x.hash(state),
+            UnpackedValue::ConstNull(x) => x.hash(state),
+            UnpackedValue::Undef(x) => x.hash(state),
+            UnpackedValue::Poison(x) => x.hash(state),
+            UnpackedValue::ConstExpr(x) => x.hash(state),
+            UnpackedValue::GlobalVariable(x) => x.hash(state),
+            UnpackedValue::Function(x) => x.hash(state),
+            UnpackedValue::Argument(x) => x.hash(state),
+            UnpackedValue::Instruction(x) => x.hash(state),
         }
     }
 }
@@ -40,6 +52,18 @@ impl<'ctx> core::fmt::Debug for Value<'ctx> {
         let x: &dyn core::fmt::Debug = match &x {
             UnpackedValue::ConstAggrZero(x) => x,
             UnpackedValue::ConstInt(x) => x,
+            UnpackedValue::ConstFloat(x) => x,
+            UnpackedValue::ConstArray(x) => x,
+            UnpackedValue::ConstStruct(x) => x,
+            UnpackedValue::ConstVector(x) => x,
+            UnpackedValue::ConstNull(x) => x,
+            UnpackedValue::Undef(x) => x,
+            UnpackedValue::Poison(x) => x,
+            UnpackedValue::ConstExpr(x) => x,
+            UnpackedValue::GlobalVariable(x) => x,
+            UnpackedValue::Function(x) => x,
+            UnpackedValue::Argument(x) => x,
+            UnpackedValue::Instruction(x) => x,
         };
 
         core::fmt::Debug::fmt(x, f)
@@ -78,8 +102,20 @@ impl<'ctx, T: ?Sized + ValueInfo + PartialEq> PartialEq for Val<'ctx, T> {
     fn eq(&self, other: &Self) -> bool {
         self.ty() == other.ty()
             && match T::TAG {
-                ValueTag::ConstAggrZero => true,
-                ValueTag::ConstInt => self.info() == other.info(),
+                ValueTag::ConstAggrZero
+                | ValueTag::ConstNull
+                | ValueTag::Undef
+                | ValueTag::Poison => true,
+                ValueTag::ConstInt
+                | ValueTag::ConstFloat
+                | ValueTag::ConstArray
+                | ValueTag::ConstStruct
+                | ValueTag::ConstVector
+                | ValueTag::ConstExpr
+                | ValueTag::GlobalVariable
+                | ValueTag::Function
+                | ValueTag::Argument
+                | ValueTag::Instruction => self.info() == other.info(),
             }
     }
 }
@@ -88,8 +124,17 @@ impl<'ctx, T: ?Sized + ValueInfo + Hash> Hash for Val<'ctx, T> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         self.ty().hash(state);
         match T::TAG {
-            ValueTag::ConstAggrZero => (),
-            ValueTag::ConstInt => self.info().hash(state),
+            ValueTag::ConstAggrZero | ValueTag::ConstNull | ValueTag::Undef | ValueTag::Poison => {}
+            ValueTag::ConstInt
+            | ValueTag::ConstFloat
+            | ValueTag::ConstArray
+            | ValueTag::ConstStruct
+            | ValueTag::ConstVector
+            | ValueTag::ConstExpr
+            | ValueTag::GlobalVariable
+            | ValueTag::Function
+            | ValueTag::Argument
+            | ValueTag::Instruction => self.info().hash(state),
         }
     }
 }
@@ -154,12 +199,36 @@ impl<T: ?Sized + core::fmt::Debug + ValueInfo> core::fmt::Debug for ValueInfoDat
 pub enum ValueTag {
     ConstAggrZero,
     ConstInt,
+    ConstFloat,
+    ConstArray,
+    ConstStruct,
+    ConstVector,
+    ConstNull,
+    Undef,
+    Poison,
+    ConstExpr,
+    GlobalVariable,
+    Function,
+    Argument,
+    Instruction,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum UnpackedValue<'ctx> {
     ConstAggrZero(super::ConstAggrZero<'ctx>),
     ConstInt(super::ConstInt<'ctx>),
+    ConstFloat(super::ConstFloat<'ctx>),
+    ConstArray(super::ConstArray<'ctx>),
+    ConstStruct(super::ConstStruct<'ctx>),
+    ConstVector(super::ConstVector<'ctx>),
+    ConstNull(super::ConstNull<'ctx>),
+    Undef(super::Undef<'ctx>),
+    Poison(super::Poison<'ctx>),
+    ConstExpr(super::ConstExpr<'ctx>),
+    GlobalVariable(super::GlobalVariable<'ctx>),
+    Function(super::Function<'ctx>),
+    Argument(super::Argument<'ctx>),
+    Instruction(super::Instruction<'ctx>),
 }
 
 /// # Safety
@@ -248,6 +317,13 @@ impl<'ctx> Value<'ctx> {
         unsafe { self.data.as_ref().value_tag }
     }
 
+    /// Whether this value is known without running any code, which includes
+    /// the addresses of globals and functions
+    #[inline]
+    pub fn is_constant(self) -> bool {
+        !matches!(self.tag(), ValueTag::Argument | ValueTag::Instruction)
+    }
+
     pub fn cast<T: ?Sized + ValueInfo>(self) -> Val<'ctx, T> {
         #[cold]
         #[inline(never)]
@@ -290,6 +366,20 @@ impl<'ctx> Value<'ctx> {
                 UnpackedValue::ConstAggrZero(unsafe { self.cast_unchecked() })
             }
             ValueTag::ConstInt => UnpackedValue::ConstInt(unsafe { self.cast_unchecked() }),
+            ValueTag::ConstFloat => UnpackedValue::ConstFloat(unsafe { self.cast_unchecked() }),
+            ValueTag::ConstArray => UnpackedValue::ConstArray(unsafe { self.cast_unchecked() }),
+            ValueTag::ConstStruct => UnpackedValue::ConstStruct(unsafe { self.cast_unchecked() }),
+            ValueTag::ConstVector => UnpackedValue::ConstVector(unsafe { self.cast_unchecked() }),
+            ValueTag::ConstNull => UnpackedValue::ConstNull(unsafe { self.cast_unchecked() }),
+            ValueTag::Undef => UnpackedValue::Undef(unsafe { self.cast_unchecked() }),
+            ValueTag::Poison => UnpackedValue::Poison(unsafe { self.cast_unchecked() }),
+            ValueTag::ConstExpr => UnpackedValue::ConstExpr(unsafe { self.cast_unchecked() }),
+            ValueTag::GlobalVariable => {
+                UnpackedValue::GlobalVariable(unsafe { self.cast_unchecked() })
+            }
+            ValueTag::Function => UnpackedValue::Function(unsafe { self.cast_unchecked() }),
+            ValueTag::Argument => UnpackedValue::Argument(unsafe { self.cast_unchecked() }),
+            ValueTag::Instruction => UnpackedValue::Instruction(unsafe { self.cast_unchecked() }),
         }
     }
 }
@@ -0,0 +1,72 @@
+use init::{
+    layout_provider::{HasLayoutProvider, SizedLayoutProvider},
+    Ctor,
+};
+
+use crate::{types::Type, AllocContext};
+
+/// An unspecified value, each use may observe a different bit pattern
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UndefInfo {}
+
+/// The result of an operation with undefined behaviour, which poisons
+/// every value computed from it
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PoisonInfo {}
+
+impl core::fmt::Debug for UndefInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "undef")
+    }
+}
+
+impl core::fmt::Debug for PoisonInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "poison")
+    }
+}
+
+pub type Undef<'ctx> = super::Val<'ctx, UndefInfo>;
+pub type Poison<'ctx> = super::Val<'ctx, PoisonInfo>;
+
+unsafe impl super::ValueInfo for UndefInfo {
+    const TAG: super::ValueTag = super::ValueTag::Undef;
+    type Flags = ();
+}
+
+unsafe impl super::ValueInfo for PoisonInfo {
+    const TAG: super::ValueTag = super::ValueTag::Poison;
+    type Flags = ();
+}
+
+impl<'ctx> Undef<'ctx> {
+    pub(crate) fn new(ctx: AllocContext<'ctx>, ty: Type<'ctx>) -> Undef<'ctx> {
+        Self::create_in_place(ctx, ty, (), ())
+    }
+}
+
+impl<'ctx> Poison<'ctx> {
+    pub(crate) fn new(ctx: AllocContext<'ctx>, ty: Type<'ctx>) -> Poison<'ctx> {
+        Self::create_in_place(ctx, ty, (), ())
+    }
+}
+
+impl Ctor for UndefInfo {
+    fn init(uninit: init::Uninit<'_, Self>, (): ()) -> init::Init<'_, Self> {
+        uninit.write(Self {})
+    }
+}
+
+impl Ctor for PoisonInfo {
+    fn init(uninit: init::Uninit<'_, Self>, (): ()) -> init::Init<'_, Self> {
+        uninit.write(Self {})
+    }
+}
+
+impl HasLayoutProvider for UndefInfo {
+    type LayoutProvider = SizedLayoutProvider;
+}
+
+impl HasLayoutProvider for PoisonInfo {
+    type LayoutProvider = SizedLayoutProvider;
+}
@@ -0,0 +1,47 @@
+use super::{Instruction, Value};
+
+/// An operand of an instruction, see [`Context::uses`](crate::Context::uses)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Use<'ctx> {
+    user: Instruction<'ctx>,
+    index: usize,
+}
+
+impl<'ctx> Use<'ctx> {
+    pub(crate) fn new(user: Instruction<'ctx>, index: usize) -> Self {
+        Self { user, index }
+    }
+
+    /// The instruction which has the value as an operand
+    #[inline]
+    pub fn user(self) -> Instruction<'ctx> {
+        self.user
+    }
+
+    /// The position of the value in the operands of the user
+    #[inline]
+    pub fn operand_index(self) -> usize {
+        self.index
+    }
+
+    /// The value which is used
+    #[inline]
+    pub fn get(self) -> Value<'ctx> {
+        self.user.operand(self.index)
+    }
+}
+
+/// The instructions which use a value, once for each operand it is used as,
+/// see [`Context::users`](crate::Context::users)
+#[derive(Clone)]
+pub struct Users<'ctx> {
+    pub(crate) uses: std::vec::IntoIter<Use<'ctx>>,
+}
+
+impl<'ctx> Iterator for Users<'ctx> {
+    type Item = Instruction<'ctx>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.uses.next().map(Use::user)
+    }
+}
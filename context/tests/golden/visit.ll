@@ -0,0 +1,26 @@
+source_filename = "golden"
+target datalayout = "e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-i128:128-f80:128-n8:16:32:64-S128"
+
+%Node = type { i64, ptr, { i8, <4 x float> } }
+%0 = type <{ i1, [3 x i16] }>
+
+@count = internal global i64 0, align 8
+@0 = private constant <{ i8, i32 }> <{ i8 1, i32 -1 }>
+@nodes = global [2 x %Node] zeroinitializer
+@flags = addrspace(1) global %0 undef
+@ones = global { half, x86_fp80 } { half 0xH3C00, x86_fp80 0xK3FFF8000000000000000 }
+@second = global ptr getelementptr inbounds (%Node, ptr @nodes, i64 1)
+@callback = global ptr @visit
+
+define i64 @visit(ptr %node, i32 %depth) {
+entry:
+  %0 = getelementptr %Node, ptr %node, i64 0, i32 0
+  %1 = load i64, ptr %0
+  %2 = sext i32 %depth to i64
+  %3 = add nsw i64 %1, %2
+  %4 = icmp eq ptr %node, null
+  %5 = select i1 %4, i64 0, i64 %3
+  ret i64 %5
+}
+
+declare void @free(ptr)